@@ -2,8 +2,6 @@ use cosmwasm_std::{
     entry_point, to_binary, Binary, Deps, DepsMut, Env, MessageInfo, QueryResponse, Response,
     StdError, StdResult, Uint128,
 };
-use sha2::{Digest, Sha256};
-use sha3::Keccak256;
 use std::ops::Deref;
 
 use crate::ethereum::{
@@ -94,12 +92,10 @@ pub fn query_verify_cosmos(
     public_key: &[u8],
 ) -> StdResult<VerifyResponse> {
     // Hashing
-    let hash = Sha256::digest(message);
+    let hash = deps.api.sha256(message);
 
     // Verification
-    let result = deps
-        .api
-        .secp256k1_verify(hash.as_ref(), signature, public_key);
+    let result = deps.api.secp256k1_verify(&hash, signature, public_key);
     match result {
         Ok(verifies) => Ok(VerifyResponse { verifies }),
         Err(err) => Err(err.into()),
@@ -115,10 +111,9 @@ pub fn query_verify_ethereum_text(
     let signer_address = decode_address(signer_address)?;
 
     // Hashing
-    let mut hasher = Keccak256::new();
-    hasher.update(format!("\x19Ethereum Signed Message:\n{}", message.len()));
-    hasher.update(message);
-    let hash = hasher.finalize();
+    let mut prefixed = format!("\x19Ethereum Signed Message:\n{}", message.len()).into_bytes();
+    prefixed.extend_from_slice(message.as_bytes());
+    let hash = deps.api.keccak256(&prefixed);
 
     // Decompose signature
     let (v, rs) = match signature.split_last() {
@@ -129,7 +124,7 @@ pub fn query_verify_ethereum_text(
 
     // Verification
     let calculated_pubkey = deps.api.secp256k1_recover_pubkey(&hash, rs, recovery)?;
-    let calculated_address = ethereum_address_raw(&calculated_pubkey)?;
+    let calculated_address = ethereum_address_raw(deps.api, &calculated_pubkey)?;
     if signer_address != calculated_address {
         return Ok(VerifyResponse { verifies: false });
     }
@@ -1,4 +1,5 @@
-use cosmwasm_vm::testing::mock_instance;
+use cosmwasm_vm::testing::{mock_instance, mock_instance_with_options, MockInstanceOptions};
+use cosmwasm_vm::{features_from_csv, FLOATS_FEATURE};
 
 // This line will test the output of cargo wasm
 static WASM: &[u8] = include_bytes!("../target/wasm32-unknown-unknown/release/floaty.wasm");
@@ -10,3 +11,16 @@ static WASM: &[u8] = include_bytes!("../target/wasm32-unknown-unknown/release/fl
 fn instantiate_fails() {
     let mut _deps = mock_instance(WASM, &[]);
 }
+
+#[test]
+fn instantiate_works_with_floats_capability() {
+    let mut supported_features = features_from_csv("iterator,staking");
+    supported_features.insert(FLOATS_FEATURE.to_string());
+    let _deps = mock_instance_with_options(
+        WASM,
+        MockInstanceOptions {
+            supported_features,
+            ..Default::default()
+        },
+    );
+}
@@ -1,8 +1,12 @@
+use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 use std::env::current_dir;
 use std::fs::create_dir_all;
-use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
-use xcvm::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, ComposableMsg};
-use xcvm::state::State;
+use xcvm::msg::{
+    ComposableMsg, CurrentAmountResponse, ExecuteMsg, InstantiateMsg, ListOwnablesResponse,
+    ListPendingOperationsResponse, ListProtocolsResponse, MigrateMsg, OwnerResponse, QueryMsg,
+    SudoMsg,
+};
+use xcvm::state::{Config, ContractVersion, MintReceipt, Ownable, PendingOperation, Protocol};
 
 fn main() {
     let mut out_dir = current_dir().unwrap();
@@ -14,8 +18,20 @@ fn main() {
     export_schema(&schema_for!(InstantiateMsg), &out_dir);
     export_schema(&schema_for!(ExecuteMsg), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
+    export_schema(&schema_for!(MigrateMsg), &out_dir);
+    export_schema(&schema_for!(SudoMsg), &out_dir);
     export_schema(&schema_for!(ComposableMsg), &out_dir);
+    export_schema(&schema_for!(CurrentAmountResponse), &out_dir);
+    export_schema(&schema_for!(OwnerResponse), &out_dir);
+    export_schema(&schema_for!(ListOwnablesResponse), &out_dir);
+    export_schema(&schema_for!(ListPendingOperationsResponse), &out_dir);
+    export_schema(&schema_for!(ListProtocolsResponse), &out_dir);
 
     // state
-    export_schema(&schema_for!(State), &out_dir);
+    export_schema(&schema_for!(ContractVersion), &out_dir);
+    export_schema(&schema_for!(Config), &out_dir);
+    export_schema(&schema_for!(Ownable), &out_dir);
+    export_schema(&schema_for!(PendingOperation), &out_dir);
+    export_schema(&schema_for!(MintReceipt), &out_dir);
+    export_schema(&schema_for!(Protocol), &out_dir);
 }
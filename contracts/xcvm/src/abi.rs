@@ -0,0 +1,121 @@
+use cosmwasm_std::Uint256;
+use ethabi::{Function, Token};
+
+use crate::errors::HackError;
+
+/// A protocol executed as a call to a Solidity contract.
+///
+/// The prototype declares the outputs of the called function, so that the return data the
+/// router delivers back with the callback can be decoded into typed tokens.
+pub trait EthereumProtocol {
+    /// The ABI of the called function, including its outputs
+    fn ethereum_prototype() -> Function;
+
+    /// Decodes the ABI encoded return data of a call to `ethereum_prototype`.
+    ///
+    /// Like Solidity, this follows the offsets of dynamic types wherever they point to and
+    /// ignores trailing data, so non-canonical encodings are accepted. Data that is too short
+    /// for the declared outputs is rejected.
+    fn decode_output(data: &[u8]) -> Result<Vec<Token>, HackError> {
+        let function = Self::ethereum_prototype();
+        function
+            .decode_output(data)
+            .map_err(|e| HackError::AbiDecodingFailed {
+                function: function.signature(),
+                // ethabi only implements `Display` for its errors with the `std` feature
+                reason: format!("{:?}", e),
+            })
+    }
+}
+
+/// Converts a `uint<M>` token into a `Uint256`, returning `None` for other tokens.
+pub fn token_to_uint256(token: &Token) -> Option<Uint256> {
+    match token {
+        Token::Uint(value) => {
+            let mut bytes = [0u8; 32];
+            value.to_big_endian(&mut bytes);
+            Some(Uint256::from_be_bytes(bytes))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethabi::{Param, ParamType, StateMutability};
+    use ethereum_types::U256;
+
+    struct Balances;
+
+    impl EthereumProtocol for Balances {
+        fn ethereum_prototype() -> Function {
+            Function {
+                name: "balances".to_owned(),
+                inputs: vec![],
+                outputs: vec![
+                    Param {
+                        name: "amount".into(),
+                        kind: ParamType::Uint(256),
+                        internal_type: None,
+                    },
+                    Param {
+                        name: "holder".into(),
+                        kind: ParamType::String,
+                        internal_type: None,
+                    },
+                ],
+                constant: None,
+                state_mutability: StateMutability::View,
+            }
+        }
+    }
+
+    #[test]
+    fn decode_output_works() {
+        let data = ethabi::encode(&[Token::Uint(U256::from(42)), Token::String("alice".into())]);
+        let tokens = Balances::decode_output(&data).unwrap();
+        assert_eq!(
+            tokens,
+            [Token::Uint(U256::from(42)), Token::String("alice".into())]
+        );
+    }
+
+    #[test]
+    fn decode_output_works_for_non_canonical_encodings() {
+        let expected = [Token::Uint(U256::from(42)), Token::String("alice".into())];
+
+        // trailing data
+        let mut data = ethabi::encode(&expected);
+        data.extend_from_slice(&[0u8; 32]);
+        assert_eq!(Balances::decode_output(&data).unwrap(), expected);
+
+        // the tail of the string starts one word later than in the canonical encoding
+        let canonical = ethabi::encode(&expected);
+        let mut data = canonical[..32].to_vec();
+        data.extend_from_slice(&ethabi::encode(&[Token::Uint(U256::from(0x60))]));
+        data.extend_from_slice(&[0u8; 32]);
+        data.extend_from_slice(&canonical[64..]);
+        assert_eq!(Balances::decode_output(&data).unwrap(), expected);
+    }
+
+    #[test]
+    fn decode_output_fails_for_invalid_data() {
+        // too short
+        let err = Balances::decode_output(&[0u8; 31]).unwrap_err();
+        assert!(matches!(err, HackError::AbiDecodingFailed { .. }));
+
+        // the string is cut off
+        let data = ethabi::encode(&[Token::Uint(U256::from(42)), Token::String("alice".into())]);
+        let err = Balances::decode_output(&data[..data.len() - 32]).unwrap_err();
+        assert!(matches!(err, HackError::AbiDecodingFailed { .. }));
+    }
+
+    #[test]
+    fn token_to_uint256_works() {
+        let token = Token::Uint(U256::from(0x0102u64) << 200);
+        let expected = Uint256::from(0x0102u64) << 200;
+        assert_eq!(token_to_uint256(&token), Some(expected));
+        assert_eq!(token_to_uint256(&Token::Bool(true)), None);
+    }
+}
@@ -1,13 +1,46 @@
+use crate::abi::{token_to_uint256, EthereumProtocol};
 use crate::errors::HackError;
+use crate::ethereum::{parse_ethereum_address, to_checksum_address};
 use crate::msg::*;
-use crate::state::{State, STATE_KEY};
-use cosmwasm_std::{entry_point, to_vec, Addr, DepsMut, Env, MessageInfo, Response, StdError};
+use crate::state::{
+    config, config_read, contract_version, contract_version_read, mint_receipts,
+    mint_receipts_read, ownables, ownables_read, pending_operations, pending_operations_read,
+    protocol_key, protocols, protocols_read, Config, ContractVersion, LegacyState, MintReceipt,
+    Ownable, PendingOperation, Protocol, ProtocolEncoding, CONTRACT_NAME, LEGACY_STATE_KEY,
+    STATE_VERSION,
+};
+use cosmwasm_std::{
+    entry_point, from_slice, to_binary, Addr, Api, Binary, CosmosMsg, Deps, DepsMut, Env, Event,
+    MessageInfo, Order, QueryResponse, Response, StdError, StdResult, Storage, Uint256,
+};
 use ethabi::{encode, Function, Param, ParamType, StateMutability, Token};
 use ethereum_types::H160;
 use xcvm_core::*;
 
-fn ratio_one() -> Amount {
-    Amount::Ratio(100)
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+/// Derives the salt of a spawned XCVM program from the execution context and the ownable.
+///
+/// The same sender minting the same ownable in the same transaction of the same block always
+/// gets the same salt, while any other combination yields a different one.
+pub fn spawn_salt(api: &dyn Api, env: &Env, sender: &Addr, ownable_id: &str) -> Vec<u8> {
+    let tx_index = env
+        .transaction
+        .as_ref()
+        .map(|tx| tx.index)
+        .unwrap_or_default();
+    let mut preimage = Vec::new();
+    preimage.extend_from_slice(env.block.chain_id.as_bytes());
+    preimage.extend_from_slice(&env.block.height.to_be_bytes());
+    preimage.extend_from_slice(&tx_index.to_be_bytes());
+    preimage.extend_from_slice(env.contract.address.as_bytes());
+    // length prefixes keep the variable length fields from running into each other
+    preimage.extend_from_slice(&(sender.as_str().len() as u64).to_be_bytes());
+    preimage.extend_from_slice(sender.as_bytes());
+    preimage.extend_from_slice(&(ownable_id.len() as u64).to_be_bytes());
+    preimage.extend_from_slice(ownable_id.as_bytes());
+    api.sha256(&preimage).to_vec()
 }
 
 // Available protocols
@@ -15,16 +48,33 @@ fn ratio_one() -> Amount {
 
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct Mint {
+    network: XCVMNetwork,
+    encoding: ProtocolEncoding,
+    contract: H160,
     to: H160,
     ownableId: String,
 }
 
 impl Mint {
-    pub fn new(to: H160, ownableId: String) -> Self {
-        Mint { to, ownableId }
+    pub fn new(
+        network: XCVMNetwork,
+        encoding: ProtocolEncoding,
+        contract: H160,
+        to: H160,
+        ownableId: String,
+    ) -> Self {
+        Mint {
+            network,
+            encoding,
+            contract,
+            to,
+            ownableId,
+        }
     }
+}
 
-    pub fn ethereum_prototype() -> Function {
+impl EthereumProtocol for Mint {
+    fn ethereum_prototype() -> Function {
         Function {
             name: "mint".to_owned(),
             inputs: vec![
@@ -39,7 +89,11 @@ impl Mint {
                     internal_type: None,
                 },
             ],
-            outputs: vec![],
+            outputs: vec![Param {
+                name: "tokenId".into(),
+                kind: ParamType::Uint(256),
+                internal_type: None,
+            }],
             constant: None,
             state_mutability: StateMutability::Payable,
         }
@@ -55,27 +109,24 @@ pub enum MintError {
 impl XCVMProtocol<XCVMNetwork> for Mint {
     type Error = MintError;
     fn serialize(&self, network: XCVMNetwork) -> Result<Vec<u8>, Self::Error> {
-        match network {
-            XCVMNetwork::ETHEREUM => {
-                // let contract_address = H160::from_str("0xe7f1725E7734CE288F8367e1Bb143E90bb3F0512");
-                // .expect("impossible");
-                let contract_address =
-                    H160::from_slice("0xe7f1725E7734CE288F8367e1Bb143E90bb3F0512".as_bytes());
-
+        if network != self.network {
+            return Err(MintError::UnsupportedNetwork);
+        }
+        match self.encoding {
+            ProtocolEncoding::EthereumAbi => {
                 let encoded_call = Self::ethereum_prototype()
                     .encode_input(&[
                         Token::Address(self.to),
                         Token::String(String::from(self.ownableId.as_str())),
                     ])
                     .map_err(|_| MintError::EncodingFailed)?;
-                Ok(encode(&[Token::Address(contract_address), Token::Bytes(encoded_call)]).into())
+                Ok(encode(&[Token::Address(self.contract), Token::Bytes(encoded_call)]).into())
             }
-            _ => Err(MintError::UnsupportedNetwork),
         }
     }
 }
 
-// Instantiation of the contract, does nothing.
+// Instantiation of the contract, issues the first ownable to the sender.
 #[entry_point]
 pub fn instantiate(
     deps: DepsMut,
@@ -83,18 +134,87 @@ pub fn instantiate(
     info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, HackError> {
-    let addr_string = info.sender.into_string();
-    let owner = Addr::unchecked(String::from(addr_string.as_str()));
-    let issuer = Addr::unchecked(addr_string);
-    let state = State {
-        owner,
-        issuer,
-        max_capacity: msg.max_capacity,
-        current_capacity: msg.max_capacity,
-    };
     deps.api.debug("Instantiating contract");
-    deps.storage.set(STATE_KEY, &to_vec(&state)?);
-    Ok(Response::new().add_attribute("issuer", state.issuer.into_string()))
+    contract_version(deps.storage).save(&ContractVersion {
+        contract: CONTRACT_NAME.to_string(),
+        version: STATE_VERSION,
+    })?;
+    config(deps.storage).save(&Config {
+        admin: info.sender.clone(),
+        router: deps.api.addr_validate(&msg.router)?,
+    })?;
+    for protocol in msg.protocols {
+        let protocol = normalize_protocol(deps.api, protocol)?;
+        protocols(deps.storage).save(&protocol_key(protocol.network)?, &protocol)?;
+    }
+    issue_ownable(
+        deps.storage,
+        &msg.ownable_id,
+        &info.sender,
+        msg.max_capacity,
+    )?;
+    Ok(Response::new().add_attribute("issuer", info.sender))
+}
+
+#[entry_point]
+pub fn migrate(mut deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, HackError> {
+    // contracts without version record predate versioning and use the legacy layout
+    let stored = contract_version_read(deps.storage)
+        .may_load()?
+        .unwrap_or_else(|| ContractVersion {
+            contract: CONTRACT_NAME.to_string(),
+            version: 0,
+        });
+    if stored.contract != CONTRACT_NAME || stored.version > STATE_VERSION {
+        return Err(HackError::CannotMigrate {
+            from: stored.contract,
+            from_version: stored.version,
+            to: CONTRACT_NAME.to_string(),
+            to_version: STATE_VERSION,
+        });
+    }
+
+    // apply upgrade routines one layout version at a time
+    if stored.version < 1 {
+        migrate_legacy_state(deps.branch(), &msg)?;
+    }
+
+    contract_version(deps.storage).save(&ContractVersion {
+        contract: CONTRACT_NAME.to_string(),
+        version: STATE_VERSION,
+    })?;
+    Ok(Response::new()
+        .add_attribute("from_version", stored.version.to_string())
+        .add_attribute("to_version", STATE_VERSION.to_string()))
+}
+
+/// Upgrades version 0 to version 1: the single `LegacyState` becomes an entry of the ownables
+/// bucket and its issuer becomes the admin.
+fn migrate_legacy_state(deps: DepsMut, msg: &MigrateMsg) -> Result<(), HackError> {
+    let data = deps
+        .storage
+        .get(LEGACY_STATE_KEY)
+        .ok_or_else(|| StdError::not_found("LegacyState"))?;
+    let legacy: LegacyState = from_slice(&data)?;
+    let router = msg.router.as_deref().ok_or(HackError::MissingRouter {})?;
+    let ownable_id = msg.ownable_id.as_deref().unwrap_or("ownable-1");
+
+    config(deps.storage).save(&Config {
+        admin: legacy.issuer.clone(),
+        router: deps.api.addr_validate(router)?,
+    })?;
+    ownables(deps.storage).save(
+        ownable_id.as_bytes(),
+        &Ownable {
+            owner: legacy.owner,
+            issuer: legacy.issuer,
+            max_capacity: legacy.max_capacity,
+            current_capacity: legacy.current_capacity,
+            locked: false,
+        },
+    )?;
+    deps.storage.remove(LEGACY_STATE_KEY);
+    Ok(())
 }
 
 // Actual execution of the contract.
@@ -107,52 +227,1344 @@ pub fn execute(
     msg: ExecuteMsg,
 ) -> Result<Response<ComposableMsg>, HackError> {
     match msg {
-        ExecuteMsg::Consume { amount } => {
-            deps.api.debug("Consume consume");
-            // hex encoded picasso address
-            let user_addr = hex::decode(&info.sender.as_bytes()[2..])
-                .map_err(|_| HackError::Std(StdError::generic_err("Impossible; QED;")))?;
-            let res = Response::new().add_attribute("msg", "consume");
-            Ok(res)
-        }
-        ExecuteMsg::Mint { owner } => {
-            deps.api.debug("Minting");
-            // hex encoded picasso address
-            let user_addr = hex::decode(&info.sender.as_bytes()[2..])
-                .map_err(|_| HackError::Std(StdError::generic_err("Impossible; QED;")))?;
-            // let owner_eth_addr = H160::from_str(&owner);
-            let owner_eth_addr = H160::from_slice(owner.as_bytes());
-
-            let program: Program = (|| {
-                Ok(
-                    XCVMProgramBuilder::from(Some("Mint_parent".into()), XCVMNetwork::PICASSO)
-                        .spawn::<_, MintError>(
-                            Some("Mint_children".into()),
-                            XCVMNetwork::ETHEREUM,
-                            Vec::new(),
-                            XCVMTransfer::empty(),
-                            |f| Ok(f.call(Mint::new(owner_eth_addr, "ownable-1".to_string()))?),
-                        )?
-                        .build(),
-                )
-            })()
-            .map_err(|_: MintError| {
-                HackError::Std(StdError::generic_err("Couldn't build XCVM program."))
-            })?;
-
-            let res = Response::new().add_attribute("msg", "mint");
-            Ok(res)
-        }
-        ExecuteMsg::Transfer { to } => {
-            deps.api.debug("Transferring");
-            let user_addr = hex::decode(&info.sender.as_bytes()[2..])
-                .map_err(|_| HackError::Std(StdError::generic_err("Impossible; QED;")))?;
-            // let program: Program = (|| {})().map_err(|_: SwapError| {
-            //     HackError::Std(StdError::generic_err("Couldn't build XCVM program."))
-            // })?;
-
-            let res = Response::new().add_attribute("msg", "transfer");
-            Ok(res)
+        ExecuteMsg::Issue {
+            ownable_id,
+            max_capacity,
+        } => do_issue(deps, info, ownable_id, max_capacity),
+        ExecuteMsg::Consume { ownable_id, amount } => do_consume(deps, info, ownable_id, amount),
+        ExecuteMsg::Mint {
+            ownable_id,
+            owner,
+            network,
+        } => do_mint(deps, env, info, ownable_id, owner, network),
+        ExecuteMsg::Transfer { ownable_id, to } => do_transfer(deps, info, ownable_id, to),
+        ExecuteMsg::RegisterProtocol { protocol } => do_register_protocol(deps, info, protocol),
+        ExecuteMsg::UnregisterProtocol { network } => do_unregister_protocol(deps, info, network),
+        ExecuteMsg::XcvmCallback { salt, result } => {
+            if info.sender != config_read(deps.storage).load()?.router {
+                return Err(HackError::Unauthorized {});
+            }
+            handle_callback(deps, salt, result)
+        }
+    }
+}
+
+/// The chain can report spawn results directly, without going through the router.
+#[entry_point]
+pub fn sudo(deps: DepsMut, _env: Env, msg: SudoMsg) -> Result<Response<ComposableMsg>, HackError> {
+    match msg {
+        SudoMsg::XcvmCallback { salt, result } => handle_callback(deps, salt, result),
+    }
+}
+
+fn do_mint(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    ownable_id: String,
+    owner: String,
+    network: XCVMNetwork,
+) -> Result<Response<ComposableMsg>, HackError> {
+    deps.api.debug("Minting");
+    let mut ownable = ownables_read(deps.storage).load(ownable_id.as_bytes())?;
+    if info.sender != ownable.owner {
+        return Err(HackError::Unauthorized {});
+    }
+    if ownable.locked {
+        return Err(HackError::OwnableLocked { ownable_id });
+    }
+    let protocol = protocols_read(deps.storage)
+        .may_load(&protocol_key(network)?)?
+        .ok_or_else(|| HackError::UnregisteredNetwork {
+            network: format!("{:?}", network),
+        })?;
+    let mint = match protocol.encoding {
+        ProtocolEncoding::EthereumAbi => Mint::new(
+            network,
+            protocol.encoding,
+            parse_ethereum_address(deps.api, &protocol.contract)?,
+            parse_ethereum_address(deps.api, &owner)?,
+            ownable_id.clone(),
+        ),
+    };
+
+    let program: Program = (|| {
+        Ok(
+            XCVMProgramBuilder::from(Some("Mint_parent".into()), XCVMNetwork::PICASSO)
+                .spawn::<_, MintError>(
+                    Some("Mint_children".into()),
+                    network,
+                    Vec::new(),
+                    XCVMTransfer::empty(),
+                    |f| Ok(f.call(mint)?),
+                )?
+                .build(),
+        )
+    })()
+    .map_err(|_: MintError| {
+        HackError::Std(StdError::generic_err("Couldn't build XCVM program."))
+    })?;
+
+    let salt = spawn_salt(deps.api, &env, &info.sender, &ownable_id);
+    if pending_operations_read(deps.storage)
+        .may_load(&salt)?
+        .is_some()
+    {
+        return Err(HackError::DuplicateOperation {
+            salt: hex::encode(&salt),
+        });
+    }
+    // the ownable stays locked until the remote mint failed
+    ownable.locked = true;
+    ownables(deps.storage).save(ownable_id.as_bytes(), &ownable)?;
+    pending_operations(deps.storage).save(
+        &salt,
+        &PendingOperation {
+            ownable_id,
+            network,
+            recipient: owner,
+            created_at: env.block.height,
+        },
+    )?;
+
+    let res = Response::new()
+        .add_attribute("msg", "mint")
+        .add_attribute("salt", hex::encode(&salt))
+        .add_message(CosmosMsg::Custom(ComposableMsg::XCVM {
+            salt,
+            funds: XCVMTransfer::empty(),
+            program,
+        }));
+    Ok(res)
+}
+
+/// Settles the pending operation spawned with `salt`.
+///
+/// A successful mint leaves the ownable locked on this chain, since it now lives on the
+/// destination network. If the router relays the return data of the remote call, the minted
+/// token id is stored as a receipt. Return data that cannot be decoded does not undo the
+/// remote mint, so the operation is settled anyway and the error is reported as the
+/// `token_id_error` attribute. A failed mint unlocks the ownable again.
+fn handle_callback(
+    deps: DepsMut,
+    salt: Binary,
+    result: CallbackResult,
+) -> Result<Response<ComposableMsg>, HackError> {
+    let operation = pending_operations_read(deps.storage)
+        .may_load(&salt)?
+        .ok_or_else(|| HackError::UnknownOperation {
+            salt: hex::encode(salt.as_slice()),
+        })?;
+    pending_operations(deps.storage).remove(&salt);
+
+    let mut event = Event::new("xcvm_callback")
+        .add_attribute("salt", hex::encode(salt.as_slice()))
+        .add_attribute("ownable_id", operation.ownable_id.clone());
+    match result {
+        CallbackResult::Success { data } => {
+            event = event.add_attribute("result", "success");
+            if let Some(data) = data {
+                match decode_token_id(&data) {
+                    Ok(token_id) => {
+                        mint_receipts(deps.storage).save(
+                            operation.ownable_id.as_bytes(),
+                            &MintReceipt {
+                                network: operation.network,
+                                recipient: operation.recipient,
+                                token_id,
+                            },
+                        )?;
+                        event = event.add_attribute("token_id", token_id.to_string());
+                    }
+                    Err(err) => {
+                        event = event.add_attribute("token_id_error", err.to_string());
+                    }
+                }
+            }
+        }
+        CallbackResult::Failure { reason } => {
+            let mut bucket = ownables(deps.storage);
+            let mut ownable = bucket.load(operation.ownable_id.as_bytes())?;
+            ownable.locked = false;
+            bucket.save(operation.ownable_id.as_bytes(), &ownable)?;
+            event = event
+                .add_attribute("result", "failure")
+                .add_attribute("reason", reason);
+        }
+    }
+
+    Ok(Response::new()
+        .add_attribute("msg", "xcvm_callback")
+        .add_event(event))
+}
+
+/// Decodes the return data of `mint(address,string)` into the id of the minted token.
+fn decode_token_id(data: &[u8]) -> Result<Uint256, HackError> {
+    match Mint::decode_output(data)?.as_slice() {
+        [token] => token_to_uint256(token).ok_or_else(|| HackError::AbiDecodingFailed {
+            function: Mint::ethereum_prototype().signature(),
+            reason: format!("unexpected token id {:?}", token),
+        }),
+        tokens => Err(HackError::AbiDecodingFailed {
+            function: Mint::ethereum_prototype().signature(),
+            reason: format!("unexpected outputs {:?}", tokens),
+        }),
+    }
+}
+
+fn issue_ownable(
+    storage: &mut dyn Storage,
+    ownable_id: &str,
+    issuer: &Addr,
+    max_capacity: u8,
+) -> Result<(), HackError> {
+    let mut bucket = ownables(storage);
+    if bucket.may_load(ownable_id.as_bytes())?.is_some() {
+        return Err(HackError::OwnableExists {
+            ownable_id: ownable_id.to_string(),
+        });
+    }
+    bucket.save(
+        ownable_id.as_bytes(),
+        &Ownable {
+            owner: issuer.clone(),
+            issuer: issuer.clone(),
+            max_capacity,
+            current_capacity: max_capacity,
+            locked: false,
+        },
+    )?;
+    Ok(())
+}
+
+fn do_issue(
+    deps: DepsMut,
+    info: MessageInfo,
+    ownable_id: String,
+    max_capacity: u8,
+) -> Result<Response<ComposableMsg>, HackError> {
+    deps.api.debug("Issuing");
+    issue_ownable(deps.storage, &ownable_id, &info.sender, max_capacity)?;
+
+    let res = Response::new()
+        .add_attribute("msg", "issue")
+        .add_attribute("ownable_id", ownable_id)
+        .add_attribute("issuer", info.sender);
+    Ok(res)
+}
+
+/// Validates the contract address of a protocol and brings it into its canonical form.
+fn normalize_protocol(api: &dyn Api, protocol: Protocol) -> Result<Protocol, HackError> {
+    let contract = match protocol.encoding {
+        ProtocolEncoding::EthereumAbi => to_checksum_address(
+            api,
+            parse_ethereum_address(api, &protocol.contract)?.as_bytes(),
+        ),
+    };
+    Ok(Protocol {
+        contract,
+        ..protocol
+    })
+}
+
+fn do_register_protocol(
+    deps: DepsMut,
+    info: MessageInfo,
+    protocol: Protocol,
+) -> Result<Response<ComposableMsg>, HackError> {
+    if info.sender != config_read(deps.storage).load()?.admin {
+        return Err(HackError::Unauthorized {});
+    }
+
+    let protocol = normalize_protocol(deps.api, protocol)?;
+    protocols(deps.storage).save(&protocol_key(protocol.network)?, &protocol)?;
+
+    let res = Response::new()
+        .add_attribute("msg", "register_protocol")
+        .add_attribute("network", format!("{:?}", protocol.network))
+        .add_attribute("contract", protocol.contract);
+    Ok(res)
+}
+
+fn do_unregister_protocol(
+    deps: DepsMut,
+    info: MessageInfo,
+    network: XCVMNetwork,
+) -> Result<Response<ComposableMsg>, HackError> {
+    if info.sender != config_read(deps.storage).load()?.admin {
+        return Err(HackError::Unauthorized {});
+    }
+
+    protocols(deps.storage).remove(&protocol_key(network)?);
+
+    let res = Response::new()
+        .add_attribute("msg", "unregister_protocol")
+        .add_attribute("network", format!("{:?}", network));
+    Ok(res)
+}
+
+fn do_consume(
+    deps: DepsMut,
+    info: MessageInfo,
+    ownable_id: String,
+    amount: u8,
+) -> Result<Response<ComposableMsg>, HackError> {
+    deps.api.debug("Consuming");
+    let mut ownable = ownables_read(deps.storage).load(ownable_id.as_bytes())?;
+    if info.sender != ownable.owner {
+        return Err(HackError::Unauthorized {});
+    }
+    if ownable.locked {
+        return Err(HackError::OwnableLocked { ownable_id });
+    }
+    if amount > 100 {
+        return Err(HackError::InvalidPercentage { amount });
+    }
+
+    // `amount` is a percentage of the remaining capacity
+    let old_capacity = ownable.current_capacity;
+    let consumed = (u16::from(old_capacity) * u16::from(amount) / 100) as u8;
+    ownable.current_capacity = old_capacity - consumed;
+    ownables(deps.storage).save(ownable_id.as_bytes(), &ownable)?;
+
+    let res = Response::new().add_attribute("msg", "consume").add_event(
+        Event::new("consume")
+            .add_attribute("ownable_id", ownable_id)
+            .add_attribute("old_capacity", old_capacity.to_string())
+            .add_attribute("new_capacity", ownable.current_capacity.to_string()),
+    );
+    Ok(res)
+}
+
+fn do_transfer(
+    deps: DepsMut,
+    info: MessageInfo,
+    ownable_id: String,
+    to: String,
+) -> Result<Response<ComposableMsg>, HackError> {
+    deps.api.debug("Transferring");
+    let mut ownable = ownables_read(deps.storage).load(ownable_id.as_bytes())?;
+    if info.sender != ownable.owner {
+        return Err(HackError::Unauthorized {});
+    }
+    if ownable.locked {
+        return Err(HackError::OwnableLocked { ownable_id });
+    }
+
+    let new_owner = deps.api.addr_validate(&to)?;
+    let old_owner = std::mem::replace(&mut ownable.owner, new_owner);
+    ownables(deps.storage).save(ownable_id.as_bytes(), &ownable)?;
+
+    let res = Response::new().add_attribute("msg", "transfer").add_event(
+        Event::new("transfer")
+            .add_attribute("ownable_id", ownable_id)
+            .add_attribute("old_owner", old_owner)
+            .add_attribute("new_owner", ownable.owner),
+    );
+    Ok(res)
+}
+
+#[entry_point]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<QueryResponse> {
+    match msg {
+        QueryMsg::GetCurrentAmount { ownable_id } => {
+            to_binary(&query_current_amount(deps, ownable_id)?)
+        }
+        QueryMsg::GetOwner { ownable_id } => to_binary(&query_owner(deps, ownable_id)?),
+        QueryMsg::ListOwnables { start_after, limit } => {
+            to_binary(&query_list_ownables(deps, start_after, limit)?)
+        }
+        QueryMsg::GetPendingOperation { salt } => to_binary(&query_pending_operation(deps, salt)?),
+        QueryMsg::ListPendingOperations { start_after, limit } => {
+            to_binary(&query_list_pending_operations(deps, start_after, limit)?)
+        }
+        QueryMsg::GetProtocol { network } => to_binary(&query_protocol(deps, network)?),
+        QueryMsg::ListProtocols {} => to_binary(&query_list_protocols(deps)?),
+        QueryMsg::GetMintReceipt { ownable_id } => {
+            to_binary(&query_mint_receipt(deps, ownable_id)?)
+        }
+    }
+}
+
+fn query_current_amount(deps: Deps, ownable_id: String) -> StdResult<CurrentAmountResponse> {
+    let ownable = ownables_read(deps.storage).load(ownable_id.as_bytes())?;
+    Ok(CurrentAmountResponse {
+        current_capacity: ownable.current_capacity,
+        max_capacity: ownable.max_capacity,
+    })
+}
+
+fn query_owner(deps: Deps, ownable_id: String) -> StdResult<OwnerResponse> {
+    let ownable = ownables_read(deps.storage).load(ownable_id.as_bytes())?;
+    Ok(OwnerResponse {
+        owner: ownable.owner.into(),
+        issuer: ownable.issuer.into(),
+        ownable_id,
+    })
+}
+
+/// The range start is inclusive, the smallest key after `key` is `key\0`.
+fn exclusive_start(mut key: Vec<u8>) -> Vec<u8> {
+    key.push(0);
+    key
+}
+
+fn query_list_ownables(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<ListOwnablesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|id| exclusive_start(id.into_bytes()));
+    let ownables: StdResult<Vec<_>> = ownables_read(deps.storage)
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (key, ownable) = item?;
+            Ok(OwnableInfo {
+                ownable_id: String::from_utf8(key)?,
+                owner: ownable.owner.into(),
+                issuer: ownable.issuer.into(),
+                current_capacity: ownable.current_capacity,
+                max_capacity: ownable.max_capacity,
+                locked: ownable.locked,
+            })
+        })
+        .collect();
+    Ok(ListOwnablesResponse {
+        ownables: ownables?,
+    })
+}
+
+fn query_pending_operation(deps: Deps, salt: Binary) -> StdResult<PendingOperation> {
+    pending_operations_read(deps.storage).load(&salt)
+}
+
+fn query_list_pending_operations(
+    deps: Deps,
+    start_after: Option<Binary>,
+    limit: Option<u32>,
+) -> StdResult<ListPendingOperationsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|salt| exclusive_start(salt.into()));
+    let operations: StdResult<Vec<_>> = pending_operations_read(deps.storage)
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (salt, operation) = item?;
+            Ok(PendingOperationInfo {
+                salt: salt.into(),
+                operation,
+            })
+        })
+        .collect();
+    Ok(ListPendingOperationsResponse {
+        operations: operations?,
+    })
+}
+
+fn query_protocol(deps: Deps, network: XCVMNetwork) -> StdResult<Protocol> {
+    protocols_read(deps.storage).load(&protocol_key(network)?)
+}
+
+fn query_list_protocols(deps: Deps) -> StdResult<ListProtocolsResponse> {
+    let protocols: StdResult<Vec<_>> = protocols_read(deps.storage)
+        .range(None, None, Order::Ascending)
+        .map(|item| item.map(|(_, protocol)| protocol))
+        .collect();
+    Ok(ListProtocolsResponse {
+        protocols: protocols?,
+    })
+}
+
+fn query_mint_receipt(deps: Deps, ownable_id: String) -> StdResult<MintReceipt> {
+    mint_receipts_read(deps.storage).load(ownable_id.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MockApi};
+    use cosmwasm_std::{from_binary, to_vec};
+
+    const CREATOR: &str = "0x1234";
+    const OWNABLE_ID: &str = "ownable-1";
+    const TARGET_CONTRACT: &str = "0xe7f1725E7734CE288F8367e1Bb143E90bb3F0512";
+    const OWNER_ETH: &str = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+    const ROUTER: &str = "0xfeed";
+
+    fn ethereum_protocol(contract: &str) -> Protocol {
+        Protocol {
+            network: XCVMNetwork::ETHEREUM,
+            contract: String::from(contract),
+            encoding: ProtocolEncoding::EthereumAbi,
+        }
+    }
+
+    fn instantiate_ownable(deps: DepsMut) {
+        let msg = InstantiateMsg {
+            max_capacity: 100,
+            ownable_id: String::from(OWNABLE_ID),
+            protocols: vec![ethereum_protocol(TARGET_CONTRACT)],
+            router: String::from(ROUTER),
+        };
+        instantiate(deps, mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+    }
+
+    fn mint_ownable(deps: DepsMut) -> Binary {
+        let msg = ExecuteMsg::Mint {
+            ownable_id: String::from(OWNABLE_ID),
+            owner: String::from(OWNER_ETH),
+            network: XCVMNetwork::ETHEREUM,
+        };
+        let api = deps.api;
+        execute(deps, mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+        spawn_salt(api, &mock_env(), &Addr::unchecked(CREATOR), OWNABLE_ID).into()
+    }
+
+    #[test]
+    fn proper_initialization() {
+        let mut deps = mock_dependencies();
+        instantiate_ownable(deps.as_mut());
+
+        let config = config_read(&deps.storage).load().unwrap();
+        assert_eq!(config.admin, CREATOR);
+        assert_eq!(config.router, ROUTER);
+        let ownable = ownables_read(&deps.storage)
+            .load(OWNABLE_ID.as_bytes())
+            .unwrap();
+        assert_eq!(
+            ownable,
+            Ownable {
+                owner: Addr::unchecked(CREATOR),
+                issuer: Addr::unchecked(CREATOR),
+                max_capacity: 100,
+                current_capacity: 100,
+                locked: false,
+            }
+        );
+        let protocol = query_protocol(deps.as_ref(), XCVMNetwork::ETHEREUM).unwrap();
+        assert_eq!(protocol, ethereum_protocol(TARGET_CONTRACT));
+    }
+
+    #[test]
+    fn instantiate_normalizes_target_contract() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            max_capacity: 100,
+            ownable_id: String::from(OWNABLE_ID),
+            protocols: vec![ethereum_protocol(&TARGET_CONTRACT.to_lowercase())],
+            router: String::from(ROUTER),
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+        let protocol = query_protocol(deps.as_ref(), XCVMNetwork::ETHEREUM).unwrap();
+        assert_eq!(protocol.contract, TARGET_CONTRACT);
+    }
+
+    #[test]
+    fn instantiate_fails_for_invalid_target_contract() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            max_capacity: 100,
+            ownable_id: String::from(OWNABLE_ID),
+            protocols: vec![ethereum_protocol(
+                "0xe7f1725E7734CE288F8367e1Bb143E90bb3F05",
+            )],
+            router: String::from(ROUTER),
+        };
+        let err = instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap_err();
+        assert!(matches!(err, HackError::InvalidEthereumAddress { .. }));
+    }
+
+    fn store_legacy_state(storage: &mut dyn Storage) {
+        let legacy = LegacyState {
+            owner: Addr::unchecked("0xabcd"),
+            issuer: Addr::unchecked(CREATOR),
+            max_capacity: 100,
+            current_capacity: 40,
+        };
+        storage.set(LEGACY_STATE_KEY, &to_vec(&legacy).unwrap());
+    }
+
+    #[test]
+    fn instantiate_stores_contract_version() {
+        let mut deps = mock_dependencies();
+        instantiate_ownable(deps.as_mut());
+
+        let version = contract_version_read(&deps.storage).load().unwrap();
+        assert_eq!(
+            version,
+            ContractVersion {
+                contract: String::from(CONTRACT_NAME),
+                version: STATE_VERSION,
+            }
+        );
+    }
+
+    #[test]
+    fn migrate_upgrades_legacy_state() {
+        let mut deps = mock_dependencies();
+        store_legacy_state(&mut deps.storage);
+
+        let msg = MigrateMsg {
+            ownable_id: None,
+            router: Some(String::from(ROUTER)),
+        };
+        let res = migrate(deps.as_mut(), mock_env(), msg).unwrap();
+        assert_eq!(res.attributes, [("from_version", "0"), ("to_version", "1")]);
+
+        assert_eq!(deps.storage.get(LEGACY_STATE_KEY), None);
+        let config = config_read(&deps.storage).load().unwrap();
+        assert_eq!(config.admin, CREATOR);
+        assert_eq!(config.router, ROUTER);
+        let ownable = ownables_read(&deps.storage).load(b"ownable-1").unwrap();
+        assert_eq!(
+            ownable,
+            Ownable {
+                owner: Addr::unchecked("0xabcd"),
+                issuer: Addr::unchecked(CREATOR),
+                max_capacity: 100,
+                current_capacity: 40,
+                locked: false,
+            }
+        );
+        let version = contract_version_read(&deps.storage).load().unwrap();
+        assert_eq!(version.version, STATE_VERSION);
+    }
+
+    #[test]
+    fn migrate_uses_given_legacy_ownable_id() {
+        let mut deps = mock_dependencies();
+        store_legacy_state(&mut deps.storage);
+
+        let msg = MigrateMsg {
+            ownable_id: Some(String::from("potion")),
+            router: Some(String::from(ROUTER)),
+        };
+        migrate(deps.as_mut(), mock_env(), msg).unwrap();
+        let owner = query_owner(deps.as_ref(), String::from("potion")).unwrap();
+        assert_eq!(owner.owner, "0xabcd");
+    }
+
+    #[test]
+    fn migrate_legacy_state_requires_router() {
+        let mut deps = mock_dependencies();
+        store_legacy_state(&mut deps.storage);
+
+        let msg = MigrateMsg {
+            ownable_id: None,
+            router: None,
+        };
+        let err = migrate(deps.as_mut(), mock_env(), msg).unwrap_err();
+        assert_eq!(err, HackError::MissingRouter {});
+        assert!(deps.storage.get(LEGACY_STATE_KEY).is_some());
+    }
+
+    #[test]
+    fn migrate_current_version_keeps_state() {
+        let mut deps = mock_dependencies();
+        instantiate_ownable(deps.as_mut());
+
+        let msg = MigrateMsg {
+            ownable_id: None,
+            router: None,
+        };
+        let res = migrate(deps.as_mut(), mock_env(), msg).unwrap();
+        assert_eq!(res.attributes, [("from_version", "1"), ("to_version", "1")]);
+        let owner = query_owner(deps.as_ref(), String::from(OWNABLE_ID)).unwrap();
+        assert_eq!(owner.owner, CREATOR);
+    }
+
+    #[test]
+    fn migrate_refuses_newer_or_foreign_versions() {
+        let mut deps = mock_dependencies();
+        instantiate_ownable(deps.as_mut());
+        let msg = MigrateMsg {
+            ownable_id: None,
+            router: None,
+        };
+
+        contract_version(&mut deps.storage)
+            .save(&ContractVersion {
+                contract: String::from(CONTRACT_NAME),
+                version: STATE_VERSION + 1,
+            })
+            .unwrap();
+        let err = migrate(deps.as_mut(), mock_env(), msg.clone()).unwrap_err();
+        assert!(matches!(err, HackError::CannotMigrate { .. }));
+
+        contract_version(&mut deps.storage)
+            .save(&ContractVersion {
+                contract: String::from("hackatom"),
+                version: STATE_VERSION,
+            })
+            .unwrap();
+        let err = migrate(deps.as_mut(), mock_env(), msg).unwrap_err();
+        assert!(matches!(err, HackError::CannotMigrate { .. }));
+    }
+
+    #[test]
+    fn issue_works() {
+        let mut deps = mock_dependencies();
+        instantiate_ownable(deps.as_mut());
+
+        let msg = ExecuteMsg::Issue {
+            ownable_id: String::from("ownable-2"),
+            max_capacity: 50,
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("0xabcd", &[]), msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            [
+                ("msg", "issue"),
+                ("ownable_id", "ownable-2"),
+                ("issuer", "0xabcd")
+            ]
+        );
+
+        let owner = query_owner(deps.as_ref(), String::from("ownable-2")).unwrap();
+        assert_eq!(owner.owner, "0xabcd");
+        assert_eq!(owner.issuer, "0xabcd");
+        let amount = query_current_amount(deps.as_ref(), String::from("ownable-2")).unwrap();
+        assert_eq!(amount.current_capacity, 50);
+
+        // the first ownable is untouched
+        let owner = query_owner(deps.as_ref(), String::from(OWNABLE_ID)).unwrap();
+        assert_eq!(owner.owner, CREATOR);
+    }
+
+    #[test]
+    fn issue_fails_for_existing_id() {
+        let mut deps = mock_dependencies();
+        instantiate_ownable(deps.as_mut());
+
+        let msg = ExecuteMsg::Issue {
+            ownable_id: String::from(OWNABLE_ID),
+            max_capacity: 50,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("0xabcd", &[]), msg).unwrap_err();
+        assert_eq!(
+            err,
+            HackError::OwnableExists {
+                ownable_id: String::from(OWNABLE_ID)
+            }
+        );
+
+        // ownable should not change
+        let owner = query_owner(deps.as_ref(), String::from(OWNABLE_ID)).unwrap();
+        assert_eq!(owner.owner, CREATOR);
+    }
+
+    #[test]
+    fn mint_fails_for_invalid_owner() {
+        let mut deps = mock_dependencies();
+        instantiate_ownable(deps.as_mut());
+
+        let msg = ExecuteMsg::Mint {
+            ownable_id: String::from(OWNABLE_ID),
+            owner: String::from("0x5AAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"),
+            network: XCVMNetwork::ETHEREUM,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap_err();
+        assert!(matches!(err, HackError::InvalidEthereumAddress { .. }));
+    }
+
+    #[test]
+    fn mint_fails_for_unknown_ownable() {
+        let mut deps = mock_dependencies();
+        instantiate_ownable(deps.as_mut());
+
+        let msg = ExecuteMsg::Mint {
+            ownable_id: String::from("ownable-2"),
+            owner: String::from(OWNER_ETH),
+            network: XCVMNetwork::ETHEREUM,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap_err();
+        assert!(matches!(err, HackError::Std(StdError::NotFound { .. })));
+    }
+
+    #[test]
+    fn mint_fails_for_unregistered_network() {
+        let mut deps = mock_dependencies();
+        instantiate_ownable(deps.as_mut());
+
+        let msg = ExecuteMsg::Mint {
+            ownable_id: String::from(OWNABLE_ID),
+            owner: String::from(OWNER_ETH),
+            network: XCVMNetwork::PICASSO,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap_err();
+        assert!(matches!(err, HackError::UnregisteredNetwork { .. }));
+    }
+
+    #[test]
+    fn mint_works() {
+        let mut deps = mock_dependencies();
+        instantiate_ownable(deps.as_mut());
+
+        let msg = ExecuteMsg::Mint {
+            ownable_id: String::from(OWNABLE_ID),
+            owner: String::from(OWNER_ETH),
+            network: XCVMNetwork::ETHEREUM,
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+        assert_eq!(res.messages.len(), 1);
+        let salt = spawn_salt(
+            &deps.api,
+            &mock_env(),
+            &Addr::unchecked(CREATOR),
+            OWNABLE_ID,
+        );
+        match &res.messages[0].msg {
+            CosmosMsg::Custom(ComposableMsg::XCVM { salt: s, .. }) => assert_eq!(s, &salt),
+            msg => panic!("Unexpected message: {:?}", msg),
+        }
+
+        // the ownable is locked while the mint is pending
+        let ownable = ownables_read(&deps.storage)
+            .load(OWNABLE_ID.as_bytes())
+            .unwrap();
+        assert!(ownable.locked);
+        let operation = query_pending_operation(deps.as_ref(), salt.into()).unwrap();
+        assert_eq!(
+            operation,
+            PendingOperation {
+                ownable_id: String::from(OWNABLE_ID),
+                network: XCVMNetwork::ETHEREUM,
+                recipient: String::from(OWNER_ETH),
+                created_at: mock_env().block.height,
+            }
+        );
+
+        let msg = ExecuteMsg::Consume {
+            ownable_id: String::from(OWNABLE_ID),
+            amount: 10,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap_err();
+        assert!(matches!(err, HackError::OwnableLocked { .. }));
+    }
+
+    #[test]
+    fn mint_fails_for_wrong_sender() {
+        let mut deps = mock_dependencies();
+        instantiate_ownable(deps.as_mut());
+
+        let msg = ExecuteMsg::Mint {
+            ownable_id: String::from(OWNABLE_ID),
+            owner: String::from(OWNER_ETH),
+            network: XCVMNetwork::ETHEREUM,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("0xabcd", &[]), msg).unwrap_err();
+        assert_eq!(err, HackError::Unauthorized {});
+    }
+
+    #[test]
+    fn successful_callback_keeps_ownable_locked() {
+        let mut deps = mock_dependencies();
+        instantiate_ownable(deps.as_mut());
+        let salt = mint_ownable(deps.as_mut());
+
+        let msg = ExecuteMsg::XcvmCallback {
+            salt: salt.clone(),
+            result: CallbackResult::Success { data: None },
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info(ROUTER, &[]), msg).unwrap();
+        assert_eq!(
+            res.events[0].attributes,
+            [
+                ("salt", hex::encode(salt.as_slice()).as_str()),
+                ("ownable_id", OWNABLE_ID),
+                ("result", "success"),
+            ]
+        );
+
+        let ownable = ownables_read(&deps.storage)
+            .load(OWNABLE_ID.as_bytes())
+            .unwrap();
+        assert!(ownable.locked);
+        let err = query_pending_operation(deps.as_ref(), salt).unwrap_err();
+        assert!(matches!(err, StdError::NotFound { .. }));
+    }
+
+    #[test]
+    fn successful_callback_stores_mint_receipt() {
+        let mut deps = mock_dependencies();
+        instantiate_ownable(deps.as_mut());
+        let salt = mint_ownable(deps.as_mut());
+
+        let data = encode(&[Token::Uint(42u64.into())]);
+        let msg = ExecuteMsg::XcvmCallback {
+            salt,
+            result: CallbackResult::Success {
+                data: Some(data.into()),
+            },
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info(ROUTER, &[]), msg).unwrap();
+        assert_eq!(res.events[0].attributes[3], ("token_id", "42"));
+
+        let msg = QueryMsg::GetMintReceipt {
+            ownable_id: String::from(OWNABLE_ID),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let receipt: MintReceipt = from_binary(&res).unwrap();
+        assert_eq!(
+            receipt,
+            MintReceipt {
+                network: XCVMNetwork::ETHEREUM,
+                recipient: String::from(OWNER_ETH),
+                token_id: Uint256::from(42u64),
+            }
+        );
+    }
+
+    #[test]
+    fn callback_settles_operation_for_invalid_return_data() {
+        let mut deps = mock_dependencies();
+        instantiate_ownable(deps.as_mut());
+        let salt = mint_ownable(deps.as_mut());
+
+        // too short for a uint256
+        let data = vec![0xab; 16];
+        let msg = ExecuteMsg::XcvmCallback {
+            salt: salt.clone(),
+            result: CallbackResult::Success {
+                data: Some(data.into()),
+            },
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info(ROUTER, &[]), msg).unwrap();
+        let attributes = &res.events[0].attributes;
+        assert_eq!(attributes[2], ("result", "success"));
+        assert_eq!(attributes[3].key, "token_id_error");
+        assert!(attributes[3].value.contains("mint(address,string)"));
+
+        // the mint happened on the destination network, so the operation is settled
+        // and the ownable stays locked, only the receipt is missing
+        let err = query_pending_operation(deps.as_ref(), salt).unwrap_err();
+        assert!(matches!(err, StdError::NotFound { .. }));
+        let ownable = ownables_read(&deps.storage)
+            .load(OWNABLE_ID.as_bytes())
+            .unwrap();
+        assert!(ownable.locked);
+        let receipt = mint_receipts_read(&deps.storage)
+            .may_load(OWNABLE_ID.as_bytes())
+            .unwrap();
+        assert_eq!(receipt, None);
+    }
+
+    #[test]
+    fn failed_callback_unlocks_ownable() {
+        let mut deps = mock_dependencies();
+        instantiate_ownable(deps.as_mut());
+        let salt = mint_ownable(deps.as_mut());
+
+        let msg = SudoMsg::XcvmCallback {
+            salt: salt.clone(),
+            result: CallbackResult::Failure {
+                reason: String::from("out of gas"),
+            },
+        };
+        sudo(deps.as_mut(), mock_env(), msg).unwrap();
+
+        let ownable = ownables_read(&deps.storage)
+            .load(OWNABLE_ID.as_bytes())
+            .unwrap();
+        assert!(!ownable.locked);
+        let list = query_list_pending_operations(deps.as_ref(), None, None).unwrap();
+        assert!(list.operations.is_empty());
+
+        // the same callback cannot be applied twice
+        let msg = SudoMsg::XcvmCallback {
+            salt,
+            result: CallbackResult::Success { data: None },
+        };
+        let err = sudo(deps.as_mut(), mock_env(), msg).unwrap_err();
+        assert!(matches!(err, HackError::UnknownOperation { .. }));
+    }
+
+    #[test]
+    fn callback_fails_for_non_router() {
+        let mut deps = mock_dependencies();
+        instantiate_ownable(deps.as_mut());
+        let salt = mint_ownable(deps.as_mut());
+
+        let msg = ExecuteMsg::XcvmCallback {
+            salt: salt.clone(),
+            result: CallbackResult::Failure {
+                reason: String::from("forged"),
+            },
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap_err();
+        assert_eq!(err, HackError::Unauthorized {});
+
+        // operation is still pending
+        let list = query_list_pending_operations(deps.as_ref(), None, None).unwrap();
+        assert_eq!(list.operations.len(), 1);
+        assert_eq!(list.operations[0].salt, salt);
+    }
+
+    #[test]
+    fn mint_serializes_configured_target_contract() {
+        let api = MockApi::default();
+        let contract = parse_ethereum_address(&api, TARGET_CONTRACT).unwrap();
+        let to = parse_ethereum_address(&api, OWNER_ETH).unwrap();
+        let mint = Mint::new(
+            XCVMNetwork::ETHEREUM,
+            ProtocolEncoding::EthereumAbi,
+            contract,
+            to,
+            String::from(OWNABLE_ID),
+        );
+
+        let payload = mint.serialize(XCVMNetwork::ETHEREUM).unwrap();
+        let tokens = ethabi::decode(&[ParamType::Address, ParamType::Bytes], &payload).unwrap();
+        assert_eq!(tokens[0], Token::Address(contract));
+        let expected_call = Mint::ethereum_prototype()
+            .encode_input(&[Token::Address(to), Token::String(String::from(OWNABLE_ID))])
+            .unwrap();
+        assert_eq!(tokens[1], Token::Bytes(expected_call));
+
+        let err = mint.serialize(XCVMNetwork::PICASSO).unwrap_err();
+        assert_eq!(err, MintError::UnsupportedNetwork);
+    }
+
+    #[test]
+    fn register_protocol_works() {
+        let mut deps = mock_dependencies();
+        instantiate_ownable(deps.as_mut());
+
+        let other_contract = "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359";
+        let msg = ExecuteMsg::RegisterProtocol {
+            protocol: Protocol {
+                network: XCVMNetwork::PICASSO,
+                contract: other_contract.to_lowercase(),
+                encoding: ProtocolEncoding::EthereumAbi,
+            },
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::ListProtocols {}).unwrap();
+        let list: ListProtocolsResponse = from_binary(&res).unwrap();
+        assert_eq!(list.protocols.len(), 2);
+        let picasso = query_protocol(deps.as_ref(), XCVMNetwork::PICASSO).unwrap();
+        assert_eq!(picasso.contract, other_contract);
+
+        // registering again replaces the entry
+        let msg = ExecuteMsg::RegisterProtocol {
+            protocol: ethereum_protocol(other_contract),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+        let ethereum = query_protocol(deps.as_ref(), XCVMNetwork::ETHEREUM).unwrap();
+        assert_eq!(ethereum.contract, other_contract);
+
+        let msg = ExecuteMsg::UnregisterProtocol {
+            network: XCVMNetwork::ETHEREUM,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetProtocol {
+                network: XCVMNetwork::ETHEREUM,
+            },
+        );
+        assert!(matches!(res.unwrap_err(), StdError::NotFound { .. }));
+    }
+
+    #[test]
+    fn register_protocol_fails_for_non_admin() {
+        let mut deps = mock_dependencies();
+        instantiate_ownable(deps.as_mut());
+
+        let msg = ExecuteMsg::RegisterProtocol {
+            protocol: ethereum_protocol(OWNER_ETH),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("0xabcd", &[]), msg).unwrap_err();
+        assert_eq!(err, HackError::Unauthorized {});
+
+        let msg = ExecuteMsg::UnregisterProtocol {
+            network: XCVMNetwork::ETHEREUM,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("0xabcd", &[]), msg).unwrap_err();
+        assert_eq!(err, HackError::Unauthorized {});
+
+        // registry should not change
+        let protocol = query_protocol(deps.as_ref(), XCVMNetwork::ETHEREUM).unwrap();
+        assert_eq!(protocol, ethereum_protocol(TARGET_CONTRACT));
+    }
+
+    #[test]
+    fn query_works() {
+        let mut deps = mock_dependencies();
+        instantiate_ownable(deps.as_mut());
+
+        let msg = QueryMsg::GetCurrentAmount {
+            ownable_id: String::from(OWNABLE_ID),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let amount: CurrentAmountResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            amount,
+            CurrentAmountResponse {
+                current_capacity: 100,
+                max_capacity: 100,
+            }
+        );
+
+        let msg = QueryMsg::GetOwner {
+            ownable_id: String::from(OWNABLE_ID),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let owner: OwnerResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            owner,
+            OwnerResponse {
+                owner: String::from(CREATOR),
+                issuer: String::from(CREATOR),
+                ownable_id: String::from(OWNABLE_ID),
+            }
+        );
+    }
+
+    #[test]
+    fn list_ownables_works() {
+        let mut deps = mock_dependencies();
+        instantiate_ownable(deps.as_mut());
+        for i in 2..=5 {
+            let msg = ExecuteMsg::Issue {
+                ownable_id: format!("ownable-{}", i),
+                max_capacity: 10 * i,
+            };
+            execute(deps.as_mut(), mock_env(), mock_info("0xabcd", &[]), msg).unwrap();
+        }
+
+        let ids = |res: ListOwnablesResponse| -> Vec<String> {
+            res.ownables.into_iter().map(|o| o.ownable_id).collect()
+        };
+
+        let all = query_list_ownables(deps.as_ref(), None, None).unwrap();
+        assert_eq!(all.ownables.len(), 5);
+        assert_eq!(
+            all.ownables[1],
+            OwnableInfo {
+                ownable_id: String::from("ownable-2"),
+                owner: String::from("0xabcd"),
+                issuer: String::from("0xabcd"),
+                current_capacity: 20,
+                max_capacity: 20,
+                locked: false,
+            }
+        );
+
+        let first_page = query_list_ownables(deps.as_ref(), None, Some(2)).unwrap();
+        assert_eq!(ids(first_page), ["ownable-1", "ownable-2"]);
+        let second_page =
+            query_list_ownables(deps.as_ref(), Some(String::from("ownable-2")), Some(2)).unwrap();
+        assert_eq!(ids(second_page), ["ownable-3", "ownable-4"]);
+        let last_page =
+            query_list_ownables(deps.as_ref(), Some(String::from("ownable-4")), Some(2)).unwrap();
+        assert_eq!(ids(last_page), ["ownable-5"]);
+
+        // limit is capped
+        let capped = query_list_ownables(deps.as_ref(), None, Some(1000)).unwrap();
+        assert_eq!(capped.ownables.len(), 5);
+    }
+
+    #[test]
+    fn consume_works() {
+        let mut deps = mock_dependencies();
+        instantiate_ownable(deps.as_mut());
+
+        let msg = ExecuteMsg::Consume {
+            ownable_id: String::from(OWNABLE_ID),
+            amount: 25,
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+        assert_eq!(res.events.len(), 1);
+        assert_eq!(
+            res.events[0].attributes,
+            [
+                ("ownable_id", OWNABLE_ID),
+                ("old_capacity", "100"),
+                ("new_capacity", "75")
+            ]
+        );
+
+        // a percentage of what is left, not of the maximum
+        let msg = ExecuteMsg::Consume {
+            ownable_id: String::from(OWNABLE_ID),
+            amount: 20,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+        let amount = query_current_amount(deps.as_ref(), String::from(OWNABLE_ID)).unwrap();
+        assert_eq!(amount.current_capacity, 60);
+        assert_eq!(amount.max_capacity, 100);
+
+        let msg = ExecuteMsg::Consume {
+            ownable_id: String::from(OWNABLE_ID),
+            amount: 100,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+        let amount = query_current_amount(deps.as_ref(), String::from(OWNABLE_ID)).unwrap();
+        assert_eq!(amount.current_capacity, 0);
+    }
+
+    #[test]
+    fn consume_fails_for_invalid_percentage() {
+        let mut deps = mock_dependencies();
+        instantiate_ownable(deps.as_mut());
+
+        let msg = ExecuteMsg::Consume {
+            ownable_id: String::from(OWNABLE_ID),
+            amount: 101,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap_err();
+        assert_eq!(err, HackError::InvalidPercentage { amount: 101 });
+    }
+
+    #[test]
+    fn consume_fails_for_wrong_sender() {
+        let mut deps = mock_dependencies();
+        instantiate_ownable(deps.as_mut());
+
+        let msg = ExecuteMsg::Consume {
+            ownable_id: String::from(OWNABLE_ID),
+            amount: 10,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("0xabcd", &[]), msg).unwrap_err();
+        assert_eq!(err, HackError::Unauthorized {});
+
+        // state should not change
+        let amount = query_current_amount(deps.as_ref(), String::from(OWNABLE_ID)).unwrap();
+        assert_eq!(amount.current_capacity, 100);
+    }
+
+    #[test]
+    fn transfer_works() {
+        let mut deps = mock_dependencies();
+        instantiate_ownable(deps.as_mut());
+
+        let msg = ExecuteMsg::Transfer {
+            ownable_id: String::from(OWNABLE_ID),
+            to: String::from("0xabcd"),
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+        assert_eq!(
+            res.events[0].attributes,
+            [
+                ("ownable_id", OWNABLE_ID),
+                ("old_owner", CREATOR),
+                ("new_owner", "0xabcd")
+            ]
+        );
+
+        let owner = query_owner(deps.as_ref(), String::from(OWNABLE_ID)).unwrap();
+        assert_eq!(owner.owner, "0xabcd");
+        assert_eq!(owner.issuer, CREATOR);
+
+        // the previous owner lost control
+        let msg = ExecuteMsg::Consume {
+            ownable_id: String::from(OWNABLE_ID),
+            amount: 10,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap_err();
+        assert_eq!(err, HackError::Unauthorized {});
+    }
+
+    #[test]
+    fn transfer_fails_for_invalid_address() {
+        let mut deps = mock_dependencies();
+        instantiate_ownable(deps.as_mut());
+
+        let msg = ExecuteMsg::Transfer {
+            ownable_id: String::from(OWNABLE_ID),
+            to: String::from("0xABCD"),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap_err();
+        assert!(matches!(err, HackError::Std(StdError::GenericErr { .. })));
+    }
+
+    #[test]
+    fn transfer_fails_for_wrong_sender() {
+        let mut deps = mock_dependencies();
+        instantiate_ownable(deps.as_mut());
+
+        let msg = ExecuteMsg::Transfer {
+            ownable_id: String::from(OWNABLE_ID),
+            to: String::from("0xabcd"),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("0xabcd", &[]), msg).unwrap_err();
+        assert_eq!(err, HackError::Unauthorized {});
+    }
+
+    #[test]
+    fn query_fails_for_unknown_ownable() {
+        let mut deps = mock_dependencies();
+        instantiate_ownable(deps.as_mut());
+
+        let msg = QueryMsg::GetOwner {
+            ownable_id: String::from("ownable-2"),
+        };
+        let err = query(deps.as_ref(), mock_env(), msg).unwrap_err();
+        assert!(matches!(err, StdError::NotFound { .. }));
+    }
+
+    #[test]
+    fn spawn_salt_is_deterministic() {
+        let api = MockApi::default();
+        let env = mock_env();
+        let sender = Addr::unchecked("0xdeadbeef");
+        assert_eq!(
+            spawn_salt(&api, &env, &sender, OWNABLE_ID),
+            spawn_salt(&api, &env, &sender, OWNABLE_ID)
+        );
+        assert_eq!(spawn_salt(&api, &env, &sender, OWNABLE_ID).len(), 32);
+    }
+
+    #[test]
+    fn spawn_salt_depends_on_context() {
+        let api = MockApi::default();
+        let env = mock_env();
+        let sender = Addr::unchecked("0xdeadbeef");
+        let salt = spawn_salt(&api, &env, &sender, OWNABLE_ID);
+
+        let other_sender = Addr::unchecked("0xbeefdead");
+        assert_ne!(spawn_salt(&api, &env, &other_sender, OWNABLE_ID), salt);
+
+        assert_ne!(spawn_salt(&api, &env, &sender, "ownable-2"), salt);
+
+        let mut next_block = mock_env();
+        next_block.block.height += 1;
+        assert_ne!(spawn_salt(&api, &next_block, &sender, OWNABLE_ID), salt);
+    }
+
+    #[test]
+    fn mint_works_for_multiple_ownables_in_one_transaction() {
+        let mut deps = mock_dependencies();
+        instantiate_ownable(deps.as_mut());
+        let msg = ExecuteMsg::Issue {
+            ownable_id: String::from("ownable-2"),
+            max_capacity: 100,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let mut salts = Vec::new();
+        for ownable_id in [OWNABLE_ID, "ownable-2"] {
+            let msg = ExecuteMsg::Mint {
+                ownable_id: String::from(ownable_id),
+                owner: String::from(OWNER_ETH),
+                network: XCVMNetwork::ETHEREUM,
+            };
+            let res = execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+            match &res.messages[0].msg {
+                CosmosMsg::Custom(ComposableMsg::XCVM { salt, .. }) => salts.push(salt.clone()),
+                msg => panic!("Unexpected message: {:?}", msg),
+            }
+        }
+        assert_ne!(salts[0], salts[1]);
+
+        for (salt, ownable_id) in salts.into_iter().zip([OWNABLE_ID, "ownable-2"]) {
+            let operation = query_pending_operation(deps.as_ref(), salt.into()).unwrap();
+            assert_eq!(operation.ownable_id, ownable_id);
         }
     }
 }
@@ -9,4 +9,29 @@ pub enum HackError {
     // this is whatever we want
     #[error("Unauthorized")]
     Unauthorized {},
+    #[error("Invalid percentage: {amount} is greater than 100")]
+    InvalidPercentage { amount: u8 },
+    #[error("Invalid Ethereum address '{address}': {reason}")]
+    InvalidEthereumAddress { address: String, reason: String },
+    #[error("Ownable {ownable_id} already exists")]
+    OwnableExists { ownable_id: String },
+    #[error("No protocol registered for network {network}")]
+    UnregisteredNetwork { network: String },
+    #[error("Ownable {ownable_id} is locked by a cross-chain operation")]
+    OwnableLocked { ownable_id: String },
+    #[error("An operation with salt {salt} is already pending")]
+    DuplicateOperation { salt: String },
+    #[error("No pending operation with salt {salt}")]
+    UnknownOperation { salt: String },
+    #[error("Cannot migrate from {from} version {from_version} to {to} version {to_version}")]
+    CannotMigrate {
+        from: String,
+        from_version: u32,
+        to: String,
+        to_version: u32,
+    },
+    #[error("Failed to decode output of {function}: {reason}")]
+    AbiDecodingFailed { function: String, reason: String },
+    #[error("Migrating legacy state requires a router address")]
+    MissingRouter {},
 }
@@ -0,0 +1,119 @@
+use cosmwasm_std::Api;
+use ethereum_types::H160;
+
+use crate::errors::HackError;
+
+/// Parses a `0x` prefixed, hex encoded Ethereum address.
+///
+/// Addresses in mixed case must carry a valid [EIP-55] checksum. All lowercase or all uppercase
+/// addresses carry no checksum information and are accepted as they are.
+///
+/// [EIP-55]: https://github.com/ethereum/EIPs/blob/master/EIPS/eip-55.md
+pub fn parse_ethereum_address(api: &dyn Api, input: &str) -> Result<H160, HackError> {
+    let invalid = |reason: &str| HackError::InvalidEthereumAddress {
+        address: input.to_string(),
+        reason: reason.to_string(),
+    };
+
+    let hex_part = input
+        .strip_prefix("0x")
+        .ok_or_else(|| invalid("missing 0x prefix"))?;
+    if hex_part.len() != 40 {
+        return Err(invalid("expected 40 hex characters"));
+    }
+    let bytes = hex::decode(hex_part).map_err(|_| invalid("invalid hex encoding"))?;
+
+    let is_mixed_case = hex_part.chars().any(|c| c.is_ascii_lowercase())
+        && hex_part.chars().any(|c| c.is_ascii_uppercase());
+    if is_mixed_case && to_checksum_address(api, &bytes)[2..] != *hex_part {
+        return Err(invalid("invalid EIP-55 checksum"));
+    }
+
+    Ok(H160::from_slice(&bytes))
+}
+
+/// Encodes the given 20 address bytes as `0x` prefixed hex with an [EIP-55] checksum.
+///
+/// [EIP-55]: https://github.com/ethereum/EIPs/blob/master/EIPS/eip-55.md
+pub fn to_checksum_address(api: &dyn Api, address: &[u8]) -> String {
+    let lower = hex::encode(address);
+    let hash = api.keccak256(lower.as_bytes());
+    let checksummed: String = lower
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let nibble = (hash[i / 2] >> (if i % 2 == 0 { 4 } else { 0 })) & 0x0f;
+            if nibble >= 8 {
+                c.to_ascii_uppercase()
+            } else {
+                c
+            }
+        })
+        .collect();
+    format!("0x{}", checksummed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::MockApi;
+
+    // Test vectors from https://github.com/ethereum/EIPs/blob/master/EIPS/eip-55.md
+    const CHECKSUMMED: [&str; 4] = [
+        "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+        "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+        "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+        "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+    ];
+
+    #[test]
+    fn parse_ethereum_address_works() {
+        let api = MockApi::default();
+        for address in CHECKSUMMED {
+            let parsed = parse_ethereum_address(&api, address).unwrap();
+            assert_eq!(parsed.as_bytes(), hex::decode(&address[2..]).unwrap());
+        }
+
+        // no checksum information
+        let lower =
+            parse_ethereum_address(&api, "0xe7f1725e7734ce288f8367e1bb143e90bb3f0512").unwrap();
+        let upper =
+            parse_ethereum_address(&api, "0xE7F1725E7734CE288F8367E1BB143E90BB3F0512").unwrap();
+        let mixed =
+            parse_ethereum_address(&api, "0xe7f1725E7734CE288F8367e1Bb143E90bb3F0512").unwrap();
+        assert_eq!(lower, upper);
+        assert_eq!(lower, mixed);
+    }
+
+    #[test]
+    fn parse_ethereum_address_fails_for_invalid_input() {
+        let api = MockApi::default();
+        let invalid = [
+            // missing prefix
+            "5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            // too short
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeA",
+            // too long
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed00",
+            // not hex
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeg",
+            // wrong checksum
+            "0x5AAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+        ];
+        for address in invalid {
+            match parse_ethereum_address(&api, address).unwrap_err() {
+                HackError::InvalidEthereumAddress { address: a, .. } => assert_eq!(a, address),
+                err => panic!("Unexpected error: {:?}", err),
+            }
+        }
+    }
+
+    #[test]
+    fn to_checksum_address_works() {
+        let api = MockApi::default();
+        for address in CHECKSUMMED {
+            let bytes = hex::decode(address[2..].to_lowercase()).unwrap();
+            assert_eq!(to_checksum_address(&api, &bytes), address);
+        }
+    }
+}
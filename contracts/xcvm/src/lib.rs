@@ -1,4 +1,9 @@
+pub mod abi;
 pub mod contract;
 mod errors;
+pub mod ethereum;
 pub mod msg;
 pub mod state;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod testing;
@@ -1,4 +1,5 @@
-use cosmwasm_std::{Addr, CustomMsg};
+use crate::state::{PendingOperation, Protocol};
+use cosmwasm_std::{Binary, CustomMsg};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
@@ -6,29 +7,166 @@ use xcvm_core::*;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
+    /// Capacity of the first ownable, issued to the instantiator
     pub max_capacity: u8,
+    /// Id of the first ownable, issued to the instantiator
     pub ownable_id: String,
+    /// Initial entries of the protocol registry
+    pub protocols: Vec<Protocol>,
+    /// Address of the XCVM router allowed to report spawn results
+    pub router: String,
+}
+
+/// MigrateMsg upgrades the storage layout of older deployments to the current one.
+///
+/// The fields are only needed when migrating from the legacy single ownable layout,
+/// which stored neither the ownable id nor the router.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {
+    /// Id of the legacy ownable, defaults to "ownable-1"
+    pub ownable_id: Option<String>,
+    /// Address of the XCVM router, required for legacy deployments
+    pub router: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
+    // issues a new ownable owned by the sender
+    Issue {
+        ownable_id: String,
+        max_capacity: u8,
+    },
     // consumes percentage of remaining potion
     #[serde(rename = "consume")]
-    Consume { amount: u8 },
+    Consume {
+        ownable_id: String,
+        amount: u8,
+    },
     // transfers ownership
     #[serde(rename = "transfer")]
-    Transfer { to: Addr },
-    // mints on eth side
+    Transfer {
+        ownable_id: String,
+        to: String,
+    },
+    // mints on the destination network side
     #[serde(rename = "mint")]
-    Mint { owner: String },
+    Mint {
+        ownable_id: String,
+        owner: String,
+        network: XCVMNetwork,
+    },
+    // adds or replaces the registry entry of a network (admin only)
+    RegisterProtocol {
+        protocol: Protocol,
+    },
+    // removes the registry entry of a network (admin only)
+    UnregisterProtocol {
+        network: XCVMNetwork,
+    },
+    // reports the result of a spawned program (router only)
+    XcvmCallback {
+        salt: Binary,
+        result: CallbackResult,
+    },
+}
+
+/// SudoMsg is only exposed for internal Cosmos SDK modules to call.
+/// The chain can use it to report spawn results instead of the router.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SudoMsg {
+    XcvmCallback {
+        salt: Binary,
+        result: CallbackResult,
+    },
+}
+
+/// Outcome of a spawned program on the destination network
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CallbackResult {
+    Success {
+        /// ABI encoded return data of the remote call, if relayed by the router
+        #[serde(default)]
+        data: Option<Binary>,
+    },
+    Failure {
+        reason: String,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
-    GetCurrentAmount {},
-    GetOwner {},
+    /// Returns the remaining and maximum capacity of the ownable
+    GetCurrentAmount { ownable_id: String },
+    /// Returns the owner, issuer and id of the ownable
+    GetOwner { ownable_id: String },
+    /// Lists ownables ordered by id, starting after `start_after`.
+    /// Returns at most `limit` entries (default 10, max 30).
+    ListOwnables {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Returns the pending operation spawned with the given salt
+    GetPendingOperation { salt: Binary },
+    /// Lists pending operations ordered by salt, starting after `start_after`.
+    /// Returns at most `limit` entries (default 10, max 30).
+    ListPendingOperations {
+        start_after: Option<Binary>,
+        limit: Option<u32>,
+    },
+    /// Returns the registry entry of the given network
+    GetProtocol { network: XCVMNetwork },
+    /// Returns all registry entries
+    ListProtocols {},
+    /// Returns the receipt of the last successful mint of the ownable
+    GetMintReceipt { ownable_id: String },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CurrentAmountResponse {
+    pub current_capacity: u8,
+    pub max_capacity: u8,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OwnerResponse {
+    pub owner: String,
+    pub issuer: String,
+    pub ownable_id: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OwnableInfo {
+    pub ownable_id: String,
+    pub owner: String,
+    pub issuer: String,
+    pub current_capacity: u8,
+    pub max_capacity: u8,
+    pub locked: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ListOwnablesResponse {
+    pub ownables: Vec<OwnableInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingOperationInfo {
+    pub salt: Binary,
+    pub operation: PendingOperation,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ListPendingOperationsResponse {
+    pub operations: Vec<PendingOperationInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ListProtocolsResponse {
+    pub protocols: Vec<Protocol>,
 }
 
 #[non_exhaustive]
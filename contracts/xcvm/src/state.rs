@@ -1,12 +1,152 @@
-use cosmwasm_std::Addr;
+use cosmwasm_std::{to_vec, Addr, StdResult, Storage, Uint256};
+use cosmwasm_storage::{
+    bucket, bucket_read, singleton, singleton_read, Bucket, ReadonlyBucket, ReadonlySingleton,
+    Singleton,
+};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-pub const STATE_KEY: &[u8] = b"state";
+use xcvm_core::XCVMNetwork;
+
+/// Name stored in the version record, to detect migrations from unrelated contracts
+pub const CONTRACT_NAME: &str = "xcvm";
+/// Version of the storage layout written by this code
+pub const STATE_VERSION: u32 = 1;
+
+const VERSION_KEY: &[u8] = b"contract_version";
+/// Key of the single `LegacyState` of contracts without version record
+pub const LEGACY_STATE_KEY: &[u8] = b"state";
+const CONFIG_KEY: &[u8] = b"config";
+const OWNABLE_PREFIX: &[u8] = b"ownable";
+const PROTOCOL_PREFIX: &[u8] = b"protocol";
+const PENDING_OPERATION_PREFIX: &[u8] = b"pending";
+const MINT_RECEIPT_PREFIX: &[u8] = b"receipt";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ContractVersion {
+    pub contract: String,
+    /// Version of the storage layout, see `STATE_VERSION`
+    pub version: u32,
+}
+
+/// Storage layout version 0: a single ownable, without version record
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LegacyState {
+    pub owner: Addr,
+    pub issuer: Addr,
+    pub max_capacity: u8,
+    pub current_capacity: u8,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Config {
+    /// The admin manages the protocol registry
+    pub admin: Addr,
+    /// The XCVM router reports the results of spawned programs
+    pub router: Addr,
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct State {
+pub struct Ownable {
     pub owner: Addr,
     pub issuer: Addr,
     pub max_capacity: u8,
     pub current_capacity: u8,
+    /// Locked ownables are being or have been minted on another network and
+    /// cannot be consumed, transferred or minted again
+    #[serde(default)]
+    pub locked: bool,
+}
+
+/// How calls to the ownable contract of a network are encoded
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProtocolEncoding {
+    /// Solidity contract ABI, `contract` is a `0x` prefixed hex address
+    EthereumAbi,
+}
+
+/// Registry entry describing where and how to mint on a destination network
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Protocol {
+    pub network: XCVMNetwork,
+    /// Address of the ownable contract on `network`, in the format of `encoding`
+    pub contract: String,
+    pub encoding: ProtocolEncoding,
+}
+
+/// A spawned mint waiting for the router to report its result
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingOperation {
+    pub ownable_id: String,
+    pub network: XCVMNetwork,
+    /// Address of the new owner on `network`
+    pub recipient: String,
+    /// Block height at which the program was spawned
+    pub created_at: u64,
+}
+
+/// The outcome of a successful mint, as reported by the destination network
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MintReceipt {
+    pub network: XCVMNetwork,
+    /// Address of the new owner on `network`
+    pub recipient: String,
+    /// Id of the token minted on `network`
+    pub token_id: Uint256,
+}
+
+pub fn contract_version(storage: &mut dyn Storage) -> Singleton<ContractVersion> {
+    singleton(storage, VERSION_KEY)
+}
+
+pub fn contract_version_read(storage: &dyn Storage) -> ReadonlySingleton<ContractVersion> {
+    singleton_read(storage, VERSION_KEY)
+}
+
+pub fn config(storage: &mut dyn Storage) -> Singleton<Config> {
+    singleton(storage, CONFIG_KEY)
+}
+
+pub fn config_read(storage: &dyn Storage) -> ReadonlySingleton<Config> {
+    singleton_read(storage, CONFIG_KEY)
+}
+
+/// All ownables managed by this contract, keyed by ownable id
+pub fn ownables(storage: &mut dyn Storage) -> Bucket<Ownable> {
+    bucket(storage, OWNABLE_PREFIX)
+}
+
+pub fn ownables_read(storage: &dyn Storage) -> ReadonlyBucket<Ownable> {
+    bucket_read(storage, OWNABLE_PREFIX)
+}
+
+/// Pending operations, keyed by the salt of the spawned program
+pub fn pending_operations(storage: &mut dyn Storage) -> Bucket<PendingOperation> {
+    bucket(storage, PENDING_OPERATION_PREFIX)
+}
+
+pub fn pending_operations_read(storage: &dyn Storage) -> ReadonlyBucket<PendingOperation> {
+    bucket_read(storage, PENDING_OPERATION_PREFIX)
+}
+
+/// Receipts of settled mints, keyed by ownable id
+pub fn mint_receipts(storage: &mut dyn Storage) -> Bucket<MintReceipt> {
+    bucket(storage, MINT_RECEIPT_PREFIX)
+}
+
+pub fn mint_receipts_read(storage: &dyn Storage) -> ReadonlyBucket<MintReceipt> {
+    bucket_read(storage, MINT_RECEIPT_PREFIX)
+}
+
+/// The protocol registry, keyed by `protocol_key`
+pub fn protocols(storage: &mut dyn Storage) -> Bucket<Protocol> {
+    bucket(storage, PROTOCOL_PREFIX)
+}
+
+pub fn protocols_read(storage: &dyn Storage) -> ReadonlyBucket<Protocol> {
+    bucket_read(storage, PROTOCOL_PREFIX)
+}
+
+pub fn protocol_key(network: XCVMNetwork) -> StdResult<Vec<u8>> {
+    to_vec(&network)
 }
@@ -0,0 +1,126 @@
+use std::collections::VecDeque;
+
+use cosmwasm_std::Binary;
+use ethabi::{ParamType, Token};
+use ethereum_types::H160;
+use xcvm_core::{XCVMInstruction, XCVMNetwork, XCVMTransfer};
+
+use crate::abi::EthereumProtocol;
+use crate::contract::Mint;
+use crate::msg::{CallbackResult, ComposableMsg, ExecuteMsg, Program};
+
+/// A contract call the router would perform on an EVM destination network
+#[derive(Clone, Debug, PartialEq)]
+pub struct EthereumCall {
+    pub network: XCVMNetwork,
+    pub contract: H160,
+    /// ABI encoded calldata, starting with the 4 byte function selector
+    pub calldata: Vec<u8>,
+}
+
+impl EthereumCall {
+    /// Decodes the calldata as `mint(address,string)`, returning the recipient and ownable id.
+    pub fn decode_mint(&self) -> Result<(H160, String), String> {
+        let function = Mint::ethereum_prototype();
+        if self.calldata.len() < 4 || self.calldata[..4] != function.short_signature() {
+            return Err(format!(
+                "calldata does not start with the selector of {}",
+                function.signature()
+            ));
+        }
+        match function
+            .decode_input(&self.calldata[4..])
+            .map_err(|e| format!("{:?}", e))?
+            .as_slice()
+        {
+            [Token::Address(to), Token::String(ownable_id)] => Ok((*to, ownable_id.clone())),
+            tokens => Err(format!("unexpected mint arguments: {:?}", tokens)),
+        }
+    }
+}
+
+/// A mock of the XCVM router for tests.
+///
+/// It unpacks the programs emitted by the contract the way the host chain would and records
+/// the calls that would be executed on the destination networks instead of relaying them.
+#[derive(Default)]
+pub struct MockRouter {
+    /// Salts of all dispatched programs, in dispatch order
+    pub salts: Vec<Binary>,
+    /// All calls performed on destination networks, in dispatch order
+    pub calls: Vec<EthereumCall>,
+}
+
+impl MockRouter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Dispatches a message emitted by the contract and returns the salt of the program.
+    pub fn dispatch(&mut self, msg: &ComposableMsg) -> Result<Binary, String> {
+        match msg {
+            ComposableMsg::XCVM {
+                salt,
+                funds,
+                program,
+            } => {
+                if funds != &XCVMTransfer::empty() {
+                    return Err(format!("unexpected funds: {:?}", funds));
+                }
+                self.run(XCVMNetwork::PICASSO, program)?;
+                let salt = Binary::from(salt.as_slice());
+                self.salts.push(salt.clone());
+                Ok(salt)
+            }
+        }
+    }
+
+    /// Builds the callback the router would send back to the contract once the program settled.
+    pub fn callback(salt: Binary, result: CallbackResult) -> ExecuteMsg {
+        ExecuteMsg::XcvmCallback { salt, result }
+    }
+
+    /// Builds the result of a successful mint, returning `token_id` like the Solidity contract.
+    pub fn mint_success(token_id: u64) -> CallbackResult {
+        CallbackResult::Success {
+            data: Some(ethabi::encode(&[Token::Uint(token_id.into())]).into()),
+        }
+    }
+
+    fn run(&mut self, network: XCVMNetwork, program: &Program) -> Result<(), String> {
+        let instructions: &VecDeque<_> = &program.instructions;
+        for instruction in instructions {
+            match instruction {
+                XCVMInstruction::Spawn {
+                    network: destination,
+                    program,
+                    ..
+                } => self.run(*destination, program)?,
+                XCVMInstruction::Call { encoded } => {
+                    if network == XCVMNetwork::PICASSO {
+                        return Err(String::from("unexpected call on the origin network"));
+                    }
+                    self.calls.push(decode_ethereum_call(network, encoded)?);
+                }
+                instruction => {
+                    return Err(format!("unexpected instruction: {:?}", instruction));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Splits a call serialized by an EVM protocol into the target contract and its calldata.
+fn decode_ethereum_call(network: XCVMNetwork, encoded: &[u8]) -> Result<EthereumCall, String> {
+    let tokens = ethabi::decode(&[ParamType::Address, ParamType::Bytes], encoded)
+        .map_err(|e| format!("{:?}", e))?;
+    match tokens.as_slice() {
+        [Token::Address(contract), Token::Bytes(calldata)] => Ok(EthereumCall {
+            network,
+            contract: *contract,
+            calldata: calldata.clone(),
+        }),
+        tokens => Err(format!("unexpected call encoding: {:?}", tokens)),
+    }
+}
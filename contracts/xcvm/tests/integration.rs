@@ -0,0 +1,236 @@
+//! This integration test tries to run and call the generated wasm.
+//! It depends on a Wasm build being available, which you can create with `cargo wasm`.
+//! Then running `cargo integration-test` will validate we can properly call into that generated Wasm.
+//!
+//! The messages emitted by the contract are fed into `xcvm::testing::MockRouter`, which unpacks
+//! the XCVM programs the way the host chain would and records the calls made on Ethereum.
+
+use cosmwasm_std::testing::MockApi as StdMockApi;
+use cosmwasm_std::{
+    from_binary, to_vec, Addr, ContractResult, CosmosMsg, Empty, Response, Uint256,
+};
+use cosmwasm_vm::testing::{
+    execute, instantiate, migrate, mock_env, mock_info, mock_instance, query, sudo, MockApi,
+    MockQuerier, MockStorage,
+};
+use cosmwasm_vm::{Instance, Storage};
+use ethereum_types::H160;
+use xcvm_core::XCVMNetwork;
+
+use xcvm::contract::spawn_salt;
+use xcvm::ethereum::parse_ethereum_address;
+use xcvm::msg::{
+    CallbackResult, ComposableMsg, ExecuteMsg, InstantiateMsg, ListPendingOperationsResponse,
+    MigrateMsg, OwnerResponse, QueryMsg, SudoMsg,
+};
+use xcvm::state::{LegacyState, MintReceipt, Protocol, ProtocolEncoding, LEGACY_STATE_KEY};
+use xcvm::testing::MockRouter;
+
+// This line will test the output of cargo wasm
+static WASM: &[u8] = include_bytes!("../target/wasm32-unknown-unknown/release/xcvm.wasm");
+// You can uncomment this line instead to test productionified build from rust-optimizer
+// static WASM: &[u8] = include_bytes!("../contract.wasm");
+
+const CREATOR: &str = "0x1234";
+const ROUTER: &str = "0xfeed";
+const OWNABLE_ID: &str = "ownable-1";
+const TARGET_CONTRACT: &str = "0xe7f1725E7734CE288F8367e1Bb143E90bb3F0512";
+const OWNER_ETH: &str = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+
+fn setup() -> Instance<MockApi, MockStorage, MockQuerier> {
+    let mut deps = mock_instance(WASM, &[]);
+    let msg = InstantiateMsg {
+        max_capacity: 100,
+        ownable_id: String::from(OWNABLE_ID),
+        protocols: vec![Protocol {
+            network: XCVMNetwork::ETHEREUM,
+            contract: String::from(TARGET_CONTRACT),
+            encoding: ProtocolEncoding::EthereumAbi,
+        }],
+        router: String::from(ROUTER),
+    };
+    let info = mock_info(CREATOR, &[]);
+    let res: Response<Empty> = instantiate(&mut deps, mock_env(), info, msg).unwrap();
+    assert_eq!(res.messages.len(), 0);
+    deps
+}
+
+/// Mints the ownable and returns the messages the contract emitted
+fn mint(deps: &mut Instance<MockApi, MockStorage, MockQuerier>) -> Vec<ComposableMsg> {
+    let msg = ExecuteMsg::Mint {
+        ownable_id: String::from(OWNABLE_ID),
+        owner: String::from(OWNER_ETH),
+        network: XCVMNetwork::ETHEREUM,
+    };
+    let res: Response<ComposableMsg> =
+        execute(deps, mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+    res.messages
+        .into_iter()
+        .map(|sub_msg| match sub_msg.msg {
+            CosmosMsg::Custom(msg) => msg,
+            msg => panic!("Unexpected message: {:?}", msg),
+        })
+        .collect()
+}
+
+fn list_pending_operations(
+    deps: &mut Instance<MockApi, MockStorage, MockQuerier>,
+) -> ListPendingOperationsResponse {
+    let msg = QueryMsg::ListPendingOperations {
+        start_after: None,
+        limit: None,
+    };
+    from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap()
+}
+
+#[test]
+fn proper_initialization() {
+    let mut deps = setup();
+
+    let msg = QueryMsg::GetOwner {
+        ownable_id: String::from(OWNABLE_ID),
+    };
+    let owner: OwnerResponse = from_binary(&query(&mut deps, mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(owner.owner, CREATOR);
+    assert_eq!(owner.issuer, CREATOR);
+}
+
+#[test]
+fn mint_emits_ethereum_mint_call() {
+    let mut deps = setup();
+    let msgs = mint(&mut deps);
+    assert_eq!(msgs.len(), 1);
+
+    let mut router = MockRouter::new();
+    let salt = router.dispatch(&msgs[0]).unwrap();
+    assert_eq!(
+        salt.as_slice(),
+        spawn_salt(
+            &StdMockApi::default(),
+            &mock_env(),
+            &Addr::unchecked(CREATOR),
+            OWNABLE_ID
+        )
+    );
+
+    assert_eq!(router.calls.len(), 1);
+    let call = &router.calls[0];
+    assert_eq!(call.network, XCVMNetwork::ETHEREUM);
+    assert_eq!(
+        call.contract,
+        parse_ethereum_address(&StdMockApi::default(), TARGET_CONTRACT).unwrap()
+    );
+
+    // keccak256("mint(address,string)")[..4]
+    assert_eq!(call.calldata[..4], [0xd0, 0xde, 0xf5, 0x21]);
+    let (to, ownable_id) = call.decode_mint().unwrap();
+    assert_eq!(
+        to,
+        parse_ethereum_address(&StdMockApi::default(), OWNER_ETH).unwrap()
+    );
+    assert_eq!(to, H160::from_slice(&hex::decode(&OWNER_ETH[2..]).unwrap()));
+    assert_eq!(ownable_id, OWNABLE_ID);
+}
+
+#[test]
+fn router_callback_settles_pending_mint() {
+    let mut deps = setup();
+    let msgs = mint(&mut deps);
+    let mut router = MockRouter::new();
+    let salt = router.dispatch(&msgs[0]).unwrap();
+
+    let pending = list_pending_operations(&mut deps);
+    assert_eq!(pending.operations.len(), 1);
+    assert_eq!(pending.operations[0].salt, salt);
+
+    // only the router may report results
+    let msg = MockRouter::callback(salt.clone(), MockRouter::mint_success(7));
+    let res: ContractResult<Response<ComposableMsg>> =
+        execute(&mut deps, mock_env(), mock_info(CREATOR, &[]), msg.clone());
+    assert!(res.unwrap_err().contains("Unauthorized"));
+
+    let _res: Response<ComposableMsg> =
+        execute(&mut deps, mock_env(), mock_info(ROUTER, &[]), msg).unwrap();
+    assert_eq!(list_pending_operations(&mut deps).operations.len(), 0);
+
+    let msg = QueryMsg::GetMintReceipt {
+        ownable_id: String::from(OWNABLE_ID),
+    };
+    let receipt: MintReceipt = from_binary(&query(&mut deps, mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(receipt.network, XCVMNetwork::ETHEREUM);
+    assert_eq!(receipt.recipient, OWNER_ETH);
+    assert_eq!(receipt.token_id, Uint256::from(7u64));
+
+    // the ownable now lives on Ethereum
+    let msg = ExecuteMsg::Transfer {
+        ownable_id: String::from(OWNABLE_ID),
+        to: String::from("0xabcd"),
+    };
+    let res: ContractResult<Response<ComposableMsg>> =
+        execute(&mut deps, mock_env(), mock_info(CREATOR, &[]), msg);
+    assert!(res.unwrap_err().contains("is locked"));
+}
+
+#[test]
+fn failed_mint_unlocks_ownable() {
+    let mut deps = setup();
+    let msgs = mint(&mut deps);
+    let mut router = MockRouter::new();
+    let salt = router.dispatch(&msgs[0]).unwrap();
+
+    let msg = SudoMsg::XcvmCallback {
+        salt,
+        result: CallbackResult::Failure {
+            reason: String::from("execution reverted"),
+        },
+    };
+    let _res: Response<ComposableMsg> = sudo(&mut deps, mock_env(), msg).unwrap();
+    assert_eq!(list_pending_operations(&mut deps).operations.len(), 0);
+
+    let msg = ExecuteMsg::Transfer {
+        ownable_id: String::from(OWNABLE_ID),
+        to: String::from("0xabcd"),
+    };
+    let _res: Response<ComposableMsg> =
+        execute(&mut deps, mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+}
+
+#[test]
+fn migrate_upgrades_legacy_state() {
+    let mut deps = mock_instance(WASM, &[]);
+    let legacy = LegacyState {
+        owner: Addr::unchecked(CREATOR),
+        issuer: Addr::unchecked(CREATOR),
+        max_capacity: 100,
+        current_capacity: 100,
+    };
+    deps.with_storage(|store| {
+        store
+            .set(LEGACY_STATE_KEY, &to_vec(&legacy).unwrap())
+            .0
+            .expect("error writing db");
+        Ok(())
+    })
+    .unwrap();
+
+    let msg = MigrateMsg {
+        ownable_id: Some(String::from(OWNABLE_ID)),
+        router: Some(String::from(ROUTER)),
+    };
+    let _res: Response<Empty> = migrate(&mut deps, mock_env(), msg).unwrap();
+
+    // the legacy ownable can be minted like a new one
+    let msg = ExecuteMsg::RegisterProtocol {
+        protocol: Protocol {
+            network: XCVMNetwork::ETHEREUM,
+            contract: String::from(TARGET_CONTRACT),
+            encoding: ProtocolEncoding::EthereumAbi,
+        },
+    };
+    let _res: Response<ComposableMsg> =
+        execute(&mut deps, mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+    let msgs = mint(&mut deps);
+    let mut router = MockRouter::new();
+    router.dispatch(&msgs[0]).unwrap();
+    assert_eq!(router.calls[0].decode_mint().unwrap().1, OWNABLE_ID);
+}
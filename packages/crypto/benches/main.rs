@@ -11,8 +11,14 @@ use k256::ecdsa::SigningKey; // type alias
 use k256::elliptic_curve::sec1::ToEncodedPoint;
 use sha2::Sha256;
 
+use bls12_381::hash_to_curve::{ExpandMsgXmd, HashToCurve};
+use bls12_381::{G1Affine, G2Affine, G2Projective, Scalar};
+use schnorrkel::{ExpansionMode, MiniSecretKey};
+
 use cosmwasm_crypto::{
-    ed25519_batch_verify, ed25519_verify, secp256k1_recover_pubkey, secp256k1_verify,
+    blake2b_256, bls12_381_aggregate_verify, ed25519_batch_verify, ed25519_verify,
+    ethereum_recover_address, keccak256, secp256k1_recover_pubkey, secp256k1_verify,
+    secp256r1_verify, sha256, sr25519_verify, BLS12_381_DST, SR25519_SIGNING_CONTEXT,
 };
 use std::cmp::min;
 
@@ -20,6 +26,11 @@ const COSMOS_SECP256K1_MSG_HEX: &str = "0a93010a90010a1c2f636f736d6f732e62616e6b
 const COSMOS_SECP256K1_SIGNATURE_HEX: &str = "c9dd20e07464d3a688ff4b710b1fbc027e495e797cfa0b4804da2ed117959227772de059808f765aa29b8f92edf30f4c2c5a438e30d3fe6897daa7141e3ce6f9";
 const COSMOS_SECP256K1_PUBKEY_BASE64: &str = "A08EGB7ro1ORuFhjOnZcSgwYlpe0DSFjVNUIkNNQxwKQ";
 
+// Test vector from https://www.rfc-editor.org/rfc/rfc6979#appendix-A.2.5 (P-256, SHA-256)
+const SECP256R1_MSG: &str = "sample";
+const SECP256R1_SIGNATURE_HEX: &str = "efd48b2aacb6a8fd1140dd9cd45e81d69d2c877b56aaf991c34d0ea84eaf3716f7cb1c942d657c41d436c7a1b6e29f65f3e900dbb9aff4064dc4ab2f843acda8";
+const SECP256R1_PUBKEY_HEX: &str = "0460fed4ba255a9d31c961eb74c6356d68c049b8923b61fa6ce669622e60f29fb67903fe1008b8bc99a41ae9e95628bc64f2f1b20c2d7e9f5177a3c294d4462299";
+
 // TEST 3 test vector from https://tools.ietf.org/html/rfc8032#section-7.1
 const COSMOS_ED25519_MSG_HEX: &str = "af82";
 const COSMOS_ED25519_SIGNATURE_HEX: &str = "6291d657deec24024827e69c3abe01a30ce548a284743a445e3680d7db5ac3ac18ff9b538d16f290ae67f760984dc6594a7c15e9716ed28dc027beceea1ec40a";
@@ -51,6 +62,30 @@ fn read_cosmos_sigs() -> Vec<Encoded> {
     serde_json::from_reader(reader).unwrap()
 }
 
+/// Creates BLS12-381 public keys and an aggregate signature of the given messages, where
+/// the i-th key signs the i-th message or the only message
+fn bls12_381_sign(messages: &[&[u8]], signers: u64) -> (Vec<Vec<u8>>, Vec<u8>) {
+    let mut public_keys = vec![];
+    let mut signature = G2Projective::identity();
+    for i in 0..signers {
+        let secret = Scalar::from(i + 1);
+        let message = messages[min(i as usize, messages.len() - 1)];
+        public_keys.push(
+            G1Affine::from(G1Affine::generator() * secret)
+                .to_compressed()
+                .to_vec(),
+        );
+        signature += <G2Projective as HashToCurve<ExpandMsgXmd<Sha256>>>::hash_to_curve(
+            message,
+            BLS12_381_DST,
+        ) * secret;
+    }
+    (
+        public_keys,
+        G2Affine::from(signature).to_compressed().to_vec(),
+    )
+}
+
 #[allow(clippy::type_complexity)]
 fn read_decode_cosmos_sigs() -> (Vec<Vec<u8>>, Vec<Vec<u8>>, Vec<Vec<u8>>) {
     let codes = read_cosmos_sigs();
@@ -86,6 +121,15 @@ fn bench_crypto(c: &mut Criterion) {
         });
     });
 
+    group.bench_function("secp256r1_verify", |b| {
+        let message_hash = Sha256::digest(SECP256R1_MSG.as_bytes());
+        let signature = hex::decode(SECP256R1_SIGNATURE_HEX).unwrap();
+        let public_key = hex::decode(SECP256R1_PUBKEY_HEX).unwrap();
+        b.iter(|| {
+            assert!(secp256r1_verify(&message_hash, &signature, &public_key).unwrap());
+        });
+    });
+
     group.bench_function("secp256k1_recover_pubkey", |b| {
         let message_hash =
             hex!("82ff40c0a986c6a5cfad4ddf4c3aa6996f1a7837f9c398e17e5de5cbd5a12b28");
@@ -107,6 +151,20 @@ fn bench_crypto(c: &mut Criterion) {
         });
     });
 
+    group.bench_function("ethereum_recover_address", |b| {
+        // Signed text "connect all the things" from contracts/crypto-verify
+        let message_hash =
+            hex!("858dc8f47a065dee00b28dc0064215b7f0a56c005fa8e823bbb4ce58bdb288cc");
+        let r_s = hex!("dada130255a447ecf434a2df9193e6fbba663e4546c35c075cd6eea21d8c7cb1714b9b65a4f7f604ff6aad55fba73f8c36514a512bbbba03709b37069194f8a4");
+        let v = 27;
+        let expected = hex!("12890d2cce102216644c59dae5baed380d84830c");
+
+        b.iter(|| {
+            let address = ethereum_recover_address(&message_hash, &r_s, v).unwrap();
+            assert_eq!(address, expected);
+        });
+    });
+
     group.bench_function("ed25519_verify", |b| {
         let message = hex::decode(COSMOS_ED25519_MSG_HEX).unwrap();
         let signature = hex::decode(COSMOS_ED25519_SIGNATURE_HEX).unwrap();
@@ -116,6 +174,20 @@ fn bench_crypto(c: &mut Criterion) {
         });
     });
 
+    group.bench_function("sr25519_verify", |b| {
+        let message = hex::decode(COSMOS_ED25519_MSG_HEX).unwrap();
+        let keypair = MiniSecretKey::from_bytes(&[7u8; 32])
+            .unwrap()
+            .expand_to_keypair(ExpansionMode::Ed25519);
+        let signature = keypair
+            .sign_simple(SR25519_SIGNING_CONTEXT, &message)
+            .to_bytes();
+        let public_key = keypair.public.to_bytes();
+        b.iter(|| {
+            assert!(sr25519_verify(&message, &signature, &public_key).unwrap());
+        });
+    });
+
     // Ed25519 batch verification of different batch lengths
     {
         let (messages, signatures, public_keys) = read_decode_cosmos_sigs();
@@ -171,6 +243,60 @@ fn bench_crypto(c: &mut Criterion) {
         }
     }
 
+    // BLS12-381 aggregate verification of one message signed by n keys (FastAggregateVerify)
+    for n in [1, 16, 512] {
+        let message: &[u8] = b"block root";
+        let (public_keys, signature) = bls12_381_sign(&[message], n);
+        let public_keys: Vec<&[u8]> = public_keys.iter().map(|pk| pk.as_slice()).collect();
+        group.bench_function(
+            format!(
+                "bls12_381_aggregate_verify_one_message_{}",
+                convert_no_fmt(n as i64)
+            ),
+            |b| {
+                b.iter(|| {
+                    assert!(
+                        bls12_381_aggregate_verify(&[message], &signature, &public_keys).unwrap()
+                    );
+                });
+            },
+        );
+    }
+
+    // BLS12-381 aggregate verification of n messages signed by n keys (AggregateVerify)
+    for n in [1, 4, 16] {
+        let messages: Vec<Vec<u8>> = (0..n)
+            .map(|i| format!("message {}", i).into_bytes())
+            .collect();
+        let messages: Vec<&[u8]> = messages.iter().map(|m| m.as_slice()).collect();
+        let (public_keys, signature) = bls12_381_sign(&messages, n);
+        let public_keys: Vec<&[u8]> = public_keys.iter().map(|pk| pk.as_slice()).collect();
+        group.bench_function(
+            format!("bls12_381_aggregate_verify_{}", convert_no_fmt(n as i64)),
+            |b| {
+                b.iter(|| {
+                    assert!(
+                        bls12_381_aggregate_verify(&messages, &signature, &public_keys).unwrap()
+                    );
+                });
+            },
+        );
+    }
+
+    {
+        // Hash a 1 KiB message to get the per-byte costs of the hashing imports
+        let message = [0x42u8; 1024];
+        group.bench_function("sha256_1kib", |b| {
+            b.iter(|| sha256(&message));
+        });
+        group.bench_function("keccak256_1kib", |b| {
+            b.iter(|| keccak256(&message));
+        });
+        group.bench_function("blake2b_256_1kib", |b| {
+            b.iter(|| blake2b_256(&message));
+        });
+    }
+
     group.finish();
 }
 
@@ -0,0 +1,228 @@
+use bls12_381::hash_to_curve::{ExpandMsgXmd, HashToCurve};
+use bls12_381::{
+    multi_miller_loop, G1Affine, G1Projective, G2Affine, G2Prepared, G2Projective, Gt,
+};
+use sha2::Sha256;
+
+use crate::errors::{CryptoError, CryptoResult};
+
+/// Length of a serialized compressed public key (a point in G1)
+pub const BLS12_381_PUBKEY_LEN: usize = 48;
+
+/// Length of a serialized compressed signature (a point in G2)
+pub const BLS12_381_SIGNATURE_LEN: usize = 96;
+
+/// The domain separation tag of the proof of possession scheme with signatures in G2,
+/// as used by Ethereum consensus clients.
+pub const BLS12_381_DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+
+/// BLS12-381 aggregate signature verification.
+///
+/// This function verifies an aggregate signature of one or more messages, with the public keys
+/// of the signers, using the proof of possession scheme of the
+/// [BLS signature draft](https://datatracker.ietf.org/doc/html/draft-irtf-cfrg-bls-signature-05)
+/// with public keys in G1 and signatures in G2, like Ethereum consensus clients.
+///
+/// Two variants are supported in the input:
+///  - Equal number of messages and public keys: `AggregateVerify`, every public key signed
+///    its own message.
+///  - One message and one or more public keys: `FastAggregateVerify`, all public keys signed
+///    the same message, e.g. a block root signed by a sync committee.
+///
+/// `FastAggregateVerify` is only secure if every public key has been checked with a proof of
+/// possession beforehand. This function does not do that check. Without it, an attacker can
+/// register a rogue key derived from other signers' keys and forge an aggregate signature
+/// over the message on their behalf. Callers must only pass public keys that were validated
+/// this way, e.g. the registered keys of an Ethereum sync committee.
+///
+/// Any other variants of input vectors, including the empty case, result in an error.
+///
+/// The signature and public keys are in the compressed ZCash serialization format:
+/// - signature: compressed G2 point (96 bytes).
+/// - public key: compressed G1 point (48 bytes), not the identity.
+pub fn bls12_381_aggregate_verify(
+    messages: &[&[u8]],
+    signature: &[u8],
+    public_keys: &[&[u8]],
+) -> CryptoResult<bool> {
+    // Structural checks
+    let messages_len = messages.len();
+    let public_keys_len = public_keys.len();
+    let fast = messages_len == 1 && public_keys_len >= 1;
+    if public_keys_len == 0 || !(messages_len == public_keys_len || fast) {
+        return Err(CryptoError::batch_err(
+            "Mismatched / erroneous number of messages / public keys",
+        ));
+    }
+
+    // Validation
+    let signature = read_signature(signature)?;
+    let public_keys = public_keys
+        .iter()
+        .map(|public_key| read_pubkey(public_key))
+        .collect::<CryptoResult<Vec<G1Affine>>>()?;
+
+    // Pairing terms: e(pk_1, H(m_1)) * ... * e(pk_n, H(m_n)) * e(-g1, signature) == 1
+    let mut terms: Vec<(G1Affine, G2Prepared)> = Vec::with_capacity(messages_len + 1);
+    if fast {
+        let aggregated = public_keys
+            .iter()
+            .fold(G1Projective::identity(), |acc, public_key| acc + public_key);
+        terms.push((G1Affine::from(aggregated), hash_to_g2(messages[0])));
+    } else {
+        for (&message, public_key) in messages.iter().zip(public_keys) {
+            terms.push((public_key, hash_to_g2(message)));
+        }
+    }
+    terms.push((-G1Affine::generator(), G2Prepared::from(signature)));
+
+    // Verification
+    let terms: Vec<(&G1Affine, &G2Prepared)> = terms.iter().map(|(p, q)| (p, q)).collect();
+    Ok(multi_miller_loop(&terms).final_exponentiation() == Gt::identity())
+}
+
+fn hash_to_g2(message: &[u8]) -> G2Prepared {
+    let point =
+        <G2Projective as HashToCurve<ExpandMsgXmd<Sha256>>>::hash_to_curve(message, BLS12_381_DST);
+    G2Prepared::from(G2Affine::from(point))
+}
+
+/// Reads a compressed G2 point, which includes the subgroup check
+fn read_signature(data: &[u8]) -> CryptoResult<G2Affine> {
+    let data: &[u8; BLS12_381_SIGNATURE_LEN] = data
+        .try_into()
+        .map_err(|_| CryptoError::invalid_signature_format())?;
+    Option::from(G2Affine::from_compressed(data)).ok_or_else(CryptoError::invalid_signature_format)
+}
+
+/// Reads a compressed G1 point, which includes the subgroup check, and rejects the identity
+fn read_pubkey(data: &[u8]) -> CryptoResult<G1Affine> {
+    let data: &[u8; BLS12_381_PUBKEY_LEN] = data
+        .try_into()
+        .map_err(|_| CryptoError::invalid_pubkey_format())?;
+    let point: G1Affine = Option::from(G1Affine::from_compressed(data))
+        .ok_or_else(CryptoError::invalid_pubkey_format)?;
+    if bool::from(point.is_identity()) {
+        return Err(CryptoError::invalid_pubkey_format());
+    }
+    Ok(point)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bls12_381::Scalar;
+
+    const MSG1: &[u8] = b"Hello World!";
+    const MSG2: &[u8] = b"Hello BLS!";
+    const MSG3: &[u8] = b"Hello light client!";
+
+    fn pubkey(secret: u64) -> [u8; BLS12_381_PUBKEY_LEN] {
+        G1Affine::from(G1Affine::generator() * Scalar::from(secret)).to_compressed()
+    }
+
+    fn sign(secret: u64, message: &[u8]) -> G2Projective {
+        <G2Projective as HashToCurve<ExpandMsgXmd<Sha256>>>::hash_to_curve(message, BLS12_381_DST)
+            * Scalar::from(secret)
+    }
+
+    fn aggregate(signatures: &[G2Projective]) -> [u8; BLS12_381_SIGNATURE_LEN] {
+        let sum = signatures
+            .iter()
+            .fold(G2Projective::identity(), |acc, signature| acc + signature);
+        G2Affine::from(sum).to_compressed()
+    }
+
+    #[test]
+    fn bls12_381_aggregate_verify_works_for_single_signature() {
+        let signature = aggregate(&[sign(11, MSG1)]);
+        let public_key = pubkey(11);
+
+        assert!(bls12_381_aggregate_verify(&[MSG1], &signature, &[&public_key]).unwrap());
+
+        // Wrong message fails
+        assert!(!bls12_381_aggregate_verify(&[MSG2], &signature, &[&public_key]).unwrap());
+
+        // Other pubkey fails
+        let other_public_key = pubkey(12);
+        assert!(!bls12_381_aggregate_verify(&[MSG1], &signature, &[&other_public_key]).unwrap());
+    }
+
+    #[test]
+    fn bls12_381_aggregate_verify_works_for_one_message() {
+        let signature = aggregate(&[sign(11, MSG1), sign(22, MSG1), sign(33, MSG1)]);
+        let public_keys = [pubkey(11), pubkey(22), pubkey(33)];
+        let public_keys: Vec<&[u8]> = public_keys.iter().map(|pk| pk.as_slice()).collect();
+
+        assert!(bls12_381_aggregate_verify(&[MSG1], &signature, &public_keys).unwrap());
+
+        // Missing signer fails
+        assert!(!bls12_381_aggregate_verify(&[MSG1], &signature, &public_keys[..2]).unwrap());
+
+        // Wrong message fails
+        assert!(!bls12_381_aggregate_verify(&[MSG2], &signature, &public_keys).unwrap());
+    }
+
+    #[test]
+    fn bls12_381_aggregate_verify_works_for_multiple_messages() {
+        let signature = aggregate(&[sign(11, MSG1), sign(22, MSG2), sign(33, MSG3)]);
+        let public_keys = [pubkey(11), pubkey(22), pubkey(33)];
+        let public_keys: Vec<&[u8]> = public_keys.iter().map(|pk| pk.as_slice()).collect();
+
+        assert!(bls12_381_aggregate_verify(&[MSG1, MSG2, MSG3], &signature, &public_keys).unwrap());
+
+        // Swapped messages fail
+        assert!(
+            !bls12_381_aggregate_verify(&[MSG2, MSG1, MSG3], &signature, &public_keys).unwrap()
+        );
+    }
+
+    #[test]
+    fn bls12_381_aggregate_verify_errors_for_wrong_number_of_inputs() {
+        let signature = aggregate(&[sign(11, MSG1), sign(22, MSG2)]);
+        let public_keys = [pubkey(11), pubkey(22)];
+        let public_keys: Vec<&[u8]> = public_keys.iter().map(|pk| pk.as_slice()).collect();
+
+        for (messages, public_keys) in [
+            (vec![MSG1, MSG2, MSG3], public_keys.clone()),
+            (vec![MSG1, MSG2], vec![public_keys[0]]),
+            (vec![], vec![]),
+            (vec![MSG1], vec![]),
+        ] {
+            match bls12_381_aggregate_verify(&messages, &signature, &public_keys).unwrap_err() {
+                CryptoError::BatchErr { .. } => {}
+                err => panic!("Unexpected error: {:?}", err),
+            }
+        }
+    }
+
+    #[test]
+    fn bls12_381_aggregate_verify_errors_for_malformed_input() {
+        let signature = aggregate(&[sign(11, MSG1)]);
+        let public_key = pubkey(11);
+
+        match bls12_381_aggregate_verify(&[MSG1], &signature[..95], &[&public_key]).unwrap_err() {
+            CryptoError::InvalidSignatureFormat { .. } => {}
+            err => panic!("Unexpected error: {:?}", err),
+        }
+        match bls12_381_aggregate_verify(&[MSG1], &signature, &[&public_key[..47]]).unwrap_err() {
+            CryptoError::InvalidPubkeyFormat { .. } => {}
+            err => panic!("Unexpected error: {:?}", err),
+        }
+
+        // Not a point on the curve
+        let mut broken_public_key = public_key;
+        broken_public_key[47] ^= 0x01;
+        match bls12_381_aggregate_verify(&[MSG1], &signature, &[&broken_public_key]).unwrap_err() {
+            CryptoError::InvalidPubkeyFormat { .. } => {}
+            err => panic!("Unexpected error: {:?}", err),
+        }
+
+        // The identity is not a valid public key
+        let identity = G1Affine::identity().to_compressed();
+        match bls12_381_aggregate_verify(&[MSG1], &signature, &[&identity]).unwrap_err() {
+            CryptoError::InvalidPubkeyFormat { .. } => {}
+            err => panic!("Unexpected error: {:?}", err),
+        }
+    }
+}
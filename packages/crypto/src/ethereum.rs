@@ -0,0 +1,118 @@
+use crate::errors::{CryptoError, CryptoResult};
+use crate::hashing::keccak256;
+use crate::secp256k1::secp256k1_recover_pubkey;
+
+/// Length of an Ethereum address in bytes
+pub const ETHEREUM_ADDRESS_LEN: usize = 20;
+
+/// Gets the secp256k1 recovery param from the value `v` of an Ethereum signature.
+///
+/// `v` is either 27 or 28 for signatures without replay protection, or
+/// `chain_id * 2 + 35` or `chain_id * 2 + 36` for signatures using a chain ID as described
+/// in [EIP-155]. All other values are invalid.
+///
+/// [EIP-155]: https://github.com/ethereum/EIPs/blob/master/EIPS/eip-155.md
+pub fn ethereum_recovery_param(v: u64) -> CryptoResult<u8> {
+    match v {
+        27 | 28 => Ok((v - 27) as u8),
+        v if v >= 35 => Ok(((v - 35) % 2) as u8),
+        _ => Err(CryptoError::invalid_recovery_param()),
+    }
+}
+
+/// Recovers the Ethereum address of the signer from a message hash and a signature,
+/// like the `ecrecover` precompile of the EVM.
+///
+/// The signature is given as `r` and `s` (64 bytes) and the Ethereum style value `v`
+/// (see [`ethereum_recovery_param`]).
+///
+/// Returns the raw 20 byte address, i.e. the last 20 bytes of the Keccak-256 hash of the
+/// uncompressed public key without its 0x04 prefix.
+pub fn ethereum_recover_address(
+    message_hash: &[u8],
+    signature: &[u8],
+    v: u64,
+) -> CryptoResult<[u8; ETHEREUM_ADDRESS_LEN]> {
+    let recovery_param = ethereum_recovery_param(v)?;
+    let pubkey = secp256k1_recover_pubkey(message_hash, signature, recovery_param)?;
+    // Uncompressed public key: 0x04 prefix, 32 bytes x, 32 bytes y
+    let hash = keccak256(&pubkey[1..]);
+    let mut address = [0u8; ETHEREUM_ADDRESS_LEN];
+    address.copy_from_slice(&hash[hash.len() - ETHEREUM_ADDRESS_LEN..]);
+    Ok(address)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex_literal::hex;
+
+    // Signed text from contracts/crypto-verify.
+    // The hash is Keccak-256 of "\x19Ethereum Signed Message:\n22connect all the things".
+    const TEXT_HASH: [u8; 32] =
+        hex!("858dc8f47a065dee00b28dc0064215b7f0a56c005fa8e823bbb4ce58bdb288cc");
+    const TEXT_SIGNATURE: [u8; 64] = hex!("dada130255a447ecf434a2df9193e6fbba663e4546c35c075cd6eea21d8c7cb1714b9b65a4f7f604ff6aad55fba73f8c36514a512bbbba03709b37069194f8a4");
+    const TEXT_V: u64 = 0x1b;
+    const TEXT_SIGNER: [u8; 20] = hex!("12890d2cce102216644c59dae5baed380d84830c");
+
+    // Rinkeby transaction 0x3b87faa3410f33284124a6898fac1001673f0f7c3682d18f55bdff0031cce9ce
+    // from contracts/crypto-verify. The hash is Keccak-256 of the unsigned EIP-155 serialization.
+    const TX_HASH: [u8; 32] =
+        hex!("1ff5c235b3c317d054b80b4bf0a8038bd727d180872d2491a7edef4f949c4135");
+    const TX_SIGNATURE: [u8; 64] = hex!("b9299dab50b3cddcaecd64b29bfbd5cd30fac1a1adea1b359a13c4e5171492a6573059c66d894684488f92e7ce1f91b158ca57b0235485625b576a3b98c480ac");
+    const TX_V: u64 = 0x2b; // chain ID 4
+    const TX_SIGNER: [u8; 20] = hex!("0a65766695a712af41b5cfecaad217b1a11cb22a");
+
+    #[test]
+    fn ethereum_recovery_param_works() {
+        assert_eq!(ethereum_recovery_param(27).unwrap(), 0);
+        assert_eq!(ethereum_recovery_param(28).unwrap(), 1);
+        // Ethereum mainnet (chain ID 1)
+        assert_eq!(ethereum_recovery_param(37).unwrap(), 0);
+        assert_eq!(ethereum_recovery_param(38).unwrap(), 1);
+        // Rinkeby (chain ID 4)
+        assert_eq!(ethereum_recovery_param(43).unwrap(), 0);
+        assert_eq!(ethereum_recovery_param(44).unwrap(), 1);
+        // chain ID 0
+        assert_eq!(ethereum_recovery_param(35).unwrap(), 0);
+        assert_eq!(ethereum_recovery_param(36).unwrap(), 1);
+
+        for v in [0, 1, 2, 26, 29, 34] {
+            match ethereum_recovery_param(v).unwrap_err() {
+                CryptoError::InvalidRecoveryParam { .. } => {}
+                err => panic!("Unexpected error: {:?}", err),
+            }
+        }
+    }
+
+    #[test]
+    fn ethereum_recover_address_works() {
+        let address = ethereum_recover_address(&TEXT_HASH, &TEXT_SIGNATURE, TEXT_V).unwrap();
+        assert_eq!(address, TEXT_SIGNER);
+
+        let address = ethereum_recover_address(&TX_HASH, &TX_SIGNATURE, TX_V).unwrap();
+        assert_eq!(address, TX_SIGNER);
+    }
+
+    #[test]
+    fn ethereum_recover_address_returns_other_address_for_wrong_v() {
+        let address = ethereum_recover_address(&TEXT_HASH, &TEXT_SIGNATURE, 28).unwrap();
+        assert_ne!(address, TEXT_SIGNER);
+    }
+
+    #[test]
+    fn ethereum_recover_address_errors() {
+        match ethereum_recover_address(&TEXT_HASH[..31], &TEXT_SIGNATURE, TEXT_V).unwrap_err() {
+            CryptoError::InvalidHashFormat { .. } => {}
+            err => panic!("Unexpected error: {:?}", err),
+        }
+        match ethereum_recover_address(&TEXT_HASH, &TEXT_SIGNATURE[..63], TEXT_V).unwrap_err() {
+            CryptoError::InvalidSignatureFormat { .. } => {}
+            err => panic!("Unexpected error: {:?}", err),
+        }
+        match ethereum_recover_address(&TEXT_HASH, &TEXT_SIGNATURE, 1).unwrap_err() {
+            CryptoError::InvalidRecoveryParam { .. } => {}
+            err => panic!("Unexpected error: {:?}", err),
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use blake2::digest::{Update, VariableOutput};
+use blake2::VarBlake2b;
+use digest::Digest;
+use sha2::Sha256;
+use sha3::Keccak256;
+
+/// Length of the digests produced by the hash functions in this module
+pub const HASH_LEN: usize = 32;
+
+/// SHA-256 as specified in FIPS 180-4.
+pub fn sha256(data: &[u8]) -> [u8; HASH_LEN] {
+    Sha256::digest(data).into()
+}
+
+/// Keccak-256 as used by Ethereum.
+///
+/// This is the original Keccak submission with a 256 bit output, which differs from
+/// the standardized SHA3-256 in its padding.
+pub fn keccak256(data: &[u8]) -> [u8; HASH_LEN] {
+    Keccak256::digest(data).into()
+}
+
+/// Unkeyed BLAKE2b with a 256 bit output, as used by Substrate (`blake2_256`).
+pub fn blake2b_256(data: &[u8]) -> [u8; HASH_LEN] {
+    let mut hasher = VarBlake2b::new(HASH_LEN).unwrap();
+    hasher.update(data);
+    let mut out = [0u8; HASH_LEN];
+    hasher.finalize_variable(|digest| out.copy_from_slice(digest));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex_literal::hex;
+
+    #[test]
+    fn sha256_works() {
+        assert_eq!(
+            sha256(b""),
+            hex!("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855")
+        );
+        assert_eq!(
+            sha256(b"abc"),
+            hex!("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+        );
+    }
+
+    #[test]
+    fn keccak256_works() {
+        assert_eq!(
+            keccak256(b""),
+            hex!("c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470")
+        );
+        assert_eq!(
+            keccak256(b"abc"),
+            hex!("4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45")
+        );
+    }
+
+    #[test]
+    fn blake2b_256_works() {
+        assert_eq!(
+            blake2b_256(b""),
+            hex!("0e5751c026e543b2e8ab2eb06099daa1d1e5df47778f7787faab45cdf12fe3a8")
+        );
+        assert_eq!(
+            blake2b_256(b"abc"),
+            hex!("bddd813c634239723171ef3fee98579b94964e3bb1cb3e427262c8c068d52319")
+        );
+    }
+}
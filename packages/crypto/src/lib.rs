@@ -4,11 +4,20 @@
 //! This crate does not adhere to semantic versioning.
 #![cfg_attr(feature = "backtraces", feature(backtrace))]
 
+mod bls;
 mod ed25519;
 mod errors;
+mod ethereum;
+mod hashing;
 mod identity_digest;
 mod secp256k1;
+mod secp256r1;
+mod sr25519;
 
+#[doc(hidden)]
+pub use crate::bls::bls12_381_aggregate_verify;
+#[doc(hidden)]
+pub use crate::bls::{BLS12_381_DST, BLS12_381_PUBKEY_LEN, BLS12_381_SIGNATURE_LEN};
 #[doc(hidden)]
 pub use crate::ed25519::EDDSA_PUBKEY_LEN;
 #[doc(hidden)]
@@ -16,6 +25,18 @@ pub use crate::ed25519::{ed25519_batch_verify, ed25519_verify};
 #[doc(hidden)]
 pub use crate::errors::{CryptoError, CryptoResult};
 #[doc(hidden)]
+pub use crate::ethereum::{
+    ethereum_recover_address, ethereum_recovery_param, ETHEREUM_ADDRESS_LEN,
+};
+#[doc(hidden)]
+pub use crate::hashing::{blake2b_256, keccak256, sha256, HASH_LEN};
+#[doc(hidden)]
 pub use crate::secp256k1::{secp256k1_recover_pubkey, secp256k1_verify};
 #[doc(hidden)]
 pub use crate::secp256k1::{ECDSA_PUBKEY_MAX_LEN, ECDSA_SIGNATURE_LEN, MESSAGE_HASH_MAX_LEN};
+#[doc(hidden)]
+pub use crate::secp256r1::secp256r1_verify;
+#[doc(hidden)]
+pub use crate::sr25519::sr25519_verify;
+#[doc(hidden)]
+pub use crate::sr25519::{SR25519_PUBKEY_LEN, SR25519_SIGNATURE_LEN, SR25519_SIGNING_CONTEXT};
@@ -0,0 +1,171 @@
+use digest::Digest; // trait
+use p256::{
+    ecdsa::signature::{DigestVerifier, Signature as _}, // traits
+    ecdsa::{Signature, VerifyingKey},                   // type aliases
+};
+
+use crate::errors::{CryptoError, CryptoResult};
+use crate::identity_digest::Identity256;
+use crate::secp256k1::{ECDSA_PUBKEY_MAX_LEN, ECDSA_SIGNATURE_LEN, MESSAGE_HASH_MAX_LEN};
+
+/// Length of a serialized compressed public key
+const ECDSA_COMPRESSED_PUBKEY_LEN: usize = 33;
+
+/// ECDSA secp256r1 (NIST P-256) implementation.
+///
+/// This function verifies message hashes (typically, hashed using SHA-256) against a signature,
+/// with the public key of the signer, using the secp256r1 elliptic curve digital signature
+/// parametrization / algorithm. This is the curve used by WebAuthn / passkeys.
+///
+/// The signature and public key are in the same format as for [`secp256k1_verify`]:
+/// - signature:  Serialized "compact" signature (64 bytes).
+/// - public key: Serialized according to SEC 1 (33 or 65 bytes).
+///
+/// Unlike for secp256k1, signatures with a high S value are accepted, since WebAuthn
+/// authenticators do not normalize them.
+///
+/// [`secp256k1_verify`]: crate::secp256k1_verify
+pub fn secp256r1_verify(
+    message_hash: &[u8],
+    signature: &[u8],
+    public_key: &[u8],
+) -> CryptoResult<bool> {
+    if message_hash.len() != MESSAGE_HASH_MAX_LEN {
+        return Err(CryptoError::invalid_hash_format());
+    }
+    if signature.len() != ECDSA_SIGNATURE_LEN {
+        return Err(CryptoError::invalid_signature_format());
+    }
+    let pubkey_ok = match public_key.first() {
+        Some(0x02) | Some(0x03) => public_key.len() == ECDSA_COMPRESSED_PUBKEY_LEN,
+        Some(0x04) => public_key.len() == ECDSA_PUBKEY_MAX_LEN,
+        _ => false,
+    };
+    if !pubkey_ok {
+        return Err(CryptoError::invalid_pubkey_format());
+    }
+
+    // Already hashed, just build Digest container
+    let message_digest = Identity256::new().chain(message_hash);
+
+    let signature =
+        Signature::from_bytes(signature).map_err(|e| CryptoError::generic_err(e.to_string()))?;
+    let public_key = VerifyingKey::from_sec1_bytes(public_key)
+        .map_err(|e| CryptoError::generic_err(e.to_string()))?;
+
+    match public_key.verify_digest(message_digest, &signature) {
+        Ok(()) => Ok(true),
+        Err(_) => Ok(false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use hex_literal::hex;
+    use p256::{
+        ecdsa::signature::DigestSigner, // trait
+        ecdsa::SigningKey,              // type alias
+        elliptic_curve::rand_core::OsRng,
+    };
+    use sha2::Sha256;
+
+    // For generic signature verification
+    const MSG: &str = "Hello World!";
+
+    // Test vector from https://www.rfc-editor.org/rfc/rfc6979#appendix-A.2.5
+    // (P-256, SHA-256, message "sample"). The S value of this signature is high.
+    const RFC6979_MSG: &str = "sample";
+    const RFC6979_PUBKEY: [u8; 65] = hex!("0460fed4ba255a9d31c961eb74c6356d68c049b8923b61fa6ce669622e60f29fb67903fe1008b8bc99a41ae9e95628bc64f2f1b20c2d7e9f5177a3c294d4462299");
+    const RFC6979_PUBKEY_COMPRESSED: [u8; 33] =
+        hex!("0360fed4ba255a9d31c961eb74c6356d68c049b8923b61fa6ce669622e60f29fb6");
+    const RFC6979_SIGNATURE: [u8; 64] = hex!("efd48b2aacb6a8fd1140dd9cd45e81d69d2c877b56aaf991c34d0ea84eaf3716f7cb1c942d657c41d436c7a1b6e29f65f3e900dbb9aff4064dc4ab2f843acda8");
+
+    #[test]
+    fn test_secp256r1_verify() {
+        // Explicit / external hashing
+        let message_digest = Sha256::new().chain(MSG);
+        let message_hash = message_digest.clone().finalize();
+
+        // Signing
+        let secret_key = SigningKey::random(&mut OsRng);
+        let signature: Signature = secret_key.sign_digest(message_digest);
+        let public_key = VerifyingKey::from(&secret_key);
+
+        // Verification (uncompressed public key)
+        assert!(secp256r1_verify(
+            &message_hash,
+            signature.as_bytes(),
+            public_key.to_encoded_point(false).as_bytes()
+        )
+        .unwrap());
+
+        // Verification (compressed public key)
+        assert!(secp256r1_verify(
+            &message_hash,
+            signature.as_bytes(),
+            public_key.to_encoded_point(true).as_bytes()
+        )
+        .unwrap());
+
+        // Wrong message fails
+        let bad_message_hash = Sha256::new().chain(MSG).chain("\0").finalize();
+        assert!(!secp256r1_verify(
+            &bad_message_hash,
+            signature.as_bytes(),
+            public_key.to_encoded_point(false).as_bytes()
+        )
+        .unwrap());
+
+        // Other pubkey fails
+        let other_secret_key = SigningKey::random(&mut OsRng);
+        let other_public_key = VerifyingKey::from(&other_secret_key);
+        assert!(!secp256r1_verify(
+            &message_hash,
+            signature.as_bytes(),
+            other_public_key.to_encoded_point(false).as_bytes()
+        )
+        .unwrap());
+    }
+
+    #[test]
+    fn test_rfc6979_secp256r1_verify() {
+        let message_hash = Sha256::digest(RFC6979_MSG.as_bytes());
+
+        assert!(secp256r1_verify(&message_hash, &RFC6979_SIGNATURE, &RFC6979_PUBKEY).unwrap());
+        assert!(secp256r1_verify(
+            &message_hash,
+            &RFC6979_SIGNATURE,
+            &RFC6979_PUBKEY_COMPRESSED
+        )
+        .unwrap());
+
+        let other_hash = Sha256::digest(b"test");
+        assert!(!secp256r1_verify(&other_hash, &RFC6979_SIGNATURE, &RFC6979_PUBKEY).unwrap());
+    }
+
+    #[test]
+    fn secp256r1_verify_errors_for_malformed_input() {
+        let message_hash = Sha256::digest(RFC6979_MSG.as_bytes());
+
+        match secp256r1_verify(&message_hash[..31], &RFC6979_SIGNATURE, &RFC6979_PUBKEY)
+            .unwrap_err()
+        {
+            CryptoError::InvalidHashFormat { .. } => {}
+            err => panic!("Unexpected error: {:?}", err),
+        }
+        match secp256r1_verify(&message_hash, &RFC6979_SIGNATURE[..63], &RFC6979_PUBKEY)
+            .unwrap_err()
+        {
+            CryptoError::InvalidSignatureFormat { .. } => {}
+            err => panic!("Unexpected error: {:?}", err),
+        }
+        match secp256r1_verify(&message_hash, &RFC6979_SIGNATURE, &RFC6979_PUBKEY[..64])
+            .unwrap_err()
+        {
+            CryptoError::InvalidPubkeyFormat { .. } => {}
+            err => panic!("Unexpected error: {:?}", err),
+        }
+    }
+}
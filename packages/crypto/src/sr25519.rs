@@ -0,0 +1,129 @@
+use schnorrkel::{PublicKey, Signature};
+
+use crate::errors::{CryptoError, CryptoResult};
+
+/// Length of a serialized public key
+pub const SR25519_PUBKEY_LEN: usize = 32;
+
+/// Length of a serialized signature
+pub const SR25519_SIGNATURE_LEN: usize = 64;
+
+/// The signing context used by Substrate for account signatures
+pub const SR25519_SIGNING_CONTEXT: &[u8] = b"substrate";
+
+/// Schnorr sr25519 implementation.
+///
+/// This function verifies messages against a signature, with the public key of the signer,
+/// using Schnorr signatures over the Ristretto group of Curve25519 as implemented by schnorrkel.
+/// Messages are signed in the [`SR25519_SIGNING_CONTEXT`] like in Substrate.
+///
+/// The signature and public key are in the format used by Substrate:
+/// - signature: raw sr25519 signature with the schnorrkel marker bit set (64 bytes).
+/// - public key: raw compressed Ristretto point (32 bytes).
+pub fn sr25519_verify(message: &[u8], signature: &[u8], public_key: &[u8]) -> CryptoResult<bool> {
+    // Validation
+    if signature.len() != SR25519_SIGNATURE_LEN {
+        return Err(CryptoError::invalid_signature_format());
+    }
+    if public_key.len() != SR25519_PUBKEY_LEN {
+        return Err(CryptoError::invalid_pubkey_format());
+    }
+    let signature =
+        Signature::from_bytes(signature).map_err(|_| CryptoError::invalid_signature_format())?;
+    let pubkey =
+        PublicKey::from_bytes(public_key).map_err(|_| CryptoError::invalid_pubkey_format())?;
+
+    // Verification
+    Ok(pubkey
+        .verify_simple(SR25519_SIGNING_CONTEXT, message, &signature)
+        .is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schnorrkel::{ExpansionMode, Keypair, MiniSecretKey};
+
+    const MSG: &str = "Hello World!";
+
+    fn keypair_from_seed(seed: u8) -> Keypair {
+        MiniSecretKey::from_bytes(&[seed; 32])
+            .unwrap()
+            .expand_to_keypair(ExpansionMode::Ed25519)
+    }
+
+    #[test]
+    fn test_sr25519_verify() {
+        let message = MSG.as_bytes();
+        let keypair = keypair_from_seed(1);
+        let signature = keypair.sign_simple(SR25519_SIGNING_CONTEXT, message);
+
+        let signature_bytes = signature.to_bytes();
+        let public_key_bytes = keypair.public.to_bytes();
+
+        // Verification
+        assert!(sr25519_verify(message, &signature_bytes, &public_key_bytes).unwrap());
+
+        // Wrong message fails
+        let bad_message = [message, b"\0"].concat();
+        assert!(!sr25519_verify(&bad_message, &signature_bytes, &public_key_bytes).unwrap());
+
+        // Other pubkey fails
+        let other_public_key_bytes = keypair_from_seed(2).public.to_bytes();
+        assert!(!sr25519_verify(message, &signature_bytes, &other_public_key_bytes).unwrap());
+
+        // Other signing context fails
+        let other_signature = keypair.sign_simple(b"other context", message);
+        assert!(!sr25519_verify(message, &other_signature.to_bytes(), &public_key_bytes).unwrap());
+    }
+
+    #[test]
+    fn test_sr25519_verify_errors_for_invalid_signature_format() {
+        let message = MSG.as_bytes();
+        let keypair = keypair_from_seed(1);
+        let signature_bytes = keypair
+            .sign_simple(SR25519_SIGNING_CONTEXT, message)
+            .to_bytes();
+        let public_key_bytes = keypair.public.to_bytes();
+
+        // Wrong length
+        let result = sr25519_verify(message, &signature_bytes[..63], &public_key_bytes);
+        assert!(matches!(
+            result.unwrap_err(),
+            CryptoError::InvalidSignatureFormat { .. }
+        ));
+
+        // Missing schnorrkel marker
+        let mut unmarked = signature_bytes;
+        unmarked[63] &= 0x7f;
+        let result = sr25519_verify(message, &unmarked, &public_key_bytes);
+        assert!(matches!(
+            result.unwrap_err(),
+            CryptoError::InvalidSignatureFormat { .. }
+        ));
+    }
+
+    #[test]
+    fn test_sr25519_verify_errors_for_invalid_pubkey_format() {
+        let message = MSG.as_bytes();
+        let keypair = keypair_from_seed(1);
+        let signature_bytes = keypair
+            .sign_simple(SR25519_SIGNING_CONTEXT, message)
+            .to_bytes();
+        let public_key_bytes = keypair.public.to_bytes();
+
+        // Wrong length
+        let result = sr25519_verify(message, &signature_bytes, &public_key_bytes[..31]);
+        assert!(matches!(
+            result.unwrap_err(),
+            CryptoError::InvalidPubkeyFormat { .. }
+        ));
+
+        // Not a valid Ristretto point
+        let result = sr25519_verify(message, &signature_bytes, &[0xff; 32]);
+        assert!(matches!(
+            result.unwrap_err(),
+            CryptoError::InvalidPubkeyFormat { .. }
+        ));
+    }
+}
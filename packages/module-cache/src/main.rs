@@ -0,0 +1,168 @@
+use std::process::exit;
+
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+
+use cosmwasm_vm::testing::{MockApi, MockQuerier, MockStorage};
+use cosmwasm_vm::{
+    features_from_csv, Cache, CacheOptions, Checksum, GasConfig, GatekeeperPolicy, Size, VmResult,
+    WasmLimits,
+};
+
+const DEFAULT_SUPPORTED_FEATURES: &str = "iterator,staking,stargate";
+const MEMORY_CACHE_SIZE: Size = Size::mebi(0);
+const INSTANCE_MEMORY_LIMIT: Size = Size::mebi(32);
+
+type ModuleCache = Cache<MockApi, MockStorage, MockQuerier>;
+
+pub fn main() {
+    let matches = App::new("Module cache")
+        .version("0.1.0")
+        .long_about("Precompiles, lists, verifies, exports and imports the compiled modules of a cache directory, e.g. to bootstrap nodes of the same architecture.")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .arg(
+            Arg::with_name("BASE_DIR")
+                .long("base-dir")
+                .value_name("BASE_DIR")
+                .help("Sets the base directory of the cache")
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("FEATURES")
+                .long("supported-features")
+                .value_name("FEATURES")
+                .help("Sets the supported features that the desired target chain supports")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("HMAC_KEY")
+                .long("hmac-key")
+                .value_name("HMAC_KEY")
+                .help("Sets the hex encoded key of the module digests, if the node uses one")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("POLICY")
+                .long("gatekeeper-policy")
+                .value_name("POLICY")
+                .help("Sets the Gatekeeper policy preset of the node (strict, bulk-memory, reference-types or simd)")
+                .takes_value(true),
+        )
+        .subcommand(
+            SubCommand::with_name("precompile")
+                .about("Stores and compiles all *.wasm files of a directory")
+                .arg(Arg::with_name("DIR").required(true).index(1)),
+        )
+        .subcommand(SubCommand::with_name("list").about("Lists the checksums of all stored Wasm"))
+        .subcommand(
+            SubCommand::with_name("verify")
+                .about("Verifies the stored Wasm and modules, all if no checksum is given")
+                .arg(Arg::with_name("CHECKSUM").multiple(true).index(1)),
+        )
+        .subcommand(
+            SubCommand::with_name("export")
+                .about("Exports Wasm and modules into a directory, all if no checksum is given")
+                .arg(Arg::with_name("DIR").required(true).index(1))
+                .arg(Arg::with_name("CHECKSUM").multiple(true).index(2)),
+        )
+        .subcommand(
+            SubCommand::with_name("import")
+                .about("Imports Wasm and modules exported by a trusted node")
+                .arg(Arg::with_name("DIR").required(true).index(1)),
+        )
+        .get_matches();
+
+    let supported_features = features_from_csv(
+        matches
+            .value_of("FEATURES")
+            .unwrap_or(DEFAULT_SUPPORTED_FEATURES),
+    );
+    let fs_cache_hmac_key = matches
+        .value_of("HMAC_KEY")
+        .map(|key| hex::decode(key).expect("Error decoding HMAC key"));
+    let gatekeeper_policy = match matches.value_of("POLICY") {
+        Some(name) => GatekeeperPolicy::preset(name).expect("Error reading Gatekeeper policy"),
+        None => GatekeeperPolicy::default(),
+    };
+    let options = CacheOptions {
+        base_dir: matches.value_of("BASE_DIR").unwrap().into(),
+        supported_features,
+        memory_cache_size: MEMORY_CACHE_SIZE,
+        fs_cache_size: None,
+        fs_cache_hmac_key,
+        instance_memory_limit: INSTANCE_MEMORY_LIMIT,
+        gas_config: GasConfig::default(),
+        gatekeeper_policy,
+        wasm_limits: WasmLimits::default(),
+    };
+    let cache: ModuleCache = unsafe { Cache::new(options).unwrap() };
+
+    let result = match matches.subcommand() {
+        ("precompile", Some(sub_matches)) => precompile(&cache, sub_matches),
+        ("list", Some(_)) => list(&cache),
+        ("verify", Some(sub_matches)) => verify(&cache, sub_matches),
+        ("export", Some(sub_matches)) => export(&cache, sub_matches),
+        ("import", Some(sub_matches)) => import(&cache, sub_matches),
+        _ => unreachable!(),
+    };
+    if let Err(err) = result {
+        eprintln!("Error: {}", err);
+        exit(1);
+    }
+}
+
+/// Returns the checksums given as arguments, or all stored ones if none is given
+fn checksums_arg(cache: &ModuleCache, matches: &ArgMatches) -> VmResult<Vec<Checksum>> {
+    match matches.values_of("CHECKSUM") {
+        Some(values) => values.map(Checksum::from_hex).collect(),
+        None => cache.checksums(),
+    }
+}
+
+fn precompile(cache: &ModuleCache, matches: &ArgMatches) -> VmResult<()> {
+    for checksum in cache.precompile_dir(matches.value_of("DIR").unwrap())? {
+        println!("{}", checksum);
+    }
+    Ok(())
+}
+
+fn list(cache: &ModuleCache) -> VmResult<()> {
+    for checksum in cache.checksums()? {
+        println!("{}", checksum);
+    }
+    Ok(())
+}
+
+fn verify(cache: &ModuleCache, matches: &ArgMatches) -> VmResult<()> {
+    let mut failures = 0;
+    for checksum in checksums_arg(cache, matches)? {
+        match cache.verify(&checksum) {
+            Ok(true) => println!("{}: ok", checksum),
+            Ok(false) => println!("{}: ok (not compiled)", checksum),
+            Err(err) => {
+                println!("{}: {}", checksum, err);
+                failures += 1;
+            }
+        }
+    }
+    if failures > 0 {
+        exit(1);
+    }
+    Ok(())
+}
+
+fn export(cache: &ModuleCache, matches: &ArgMatches) -> VmResult<()> {
+    let checksums = checksums_arg(cache, matches)?;
+    cache.export(&checksums, matches.value_of("DIR").unwrap())?;
+    println!("Exported {} modules", checksums.len());
+    Ok(())
+}
+
+fn import(cache: &ModuleCache, matches: &ArgMatches) -> VmResult<()> {
+    // The export is trusted by the operator running this command
+    let checksums = unsafe { cache.import(matches.value_of("DIR").unwrap())? };
+    for checksum in checksums {
+        println!("{}", checksum);
+    }
+    Ok(())
+}
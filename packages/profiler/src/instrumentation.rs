@@ -5,7 +5,7 @@ use std::{
 
 use cosmwasm_vm::{
     testing::{MockApi, MockQuerier, MockStorage},
-    Backend, Instance,
+    Backend, GasConfig, Instance,
 };
 use loupe::MemoryUsage;
 use wasmer::{
@@ -84,6 +84,7 @@ impl<'d> Module<'d> {
             backend,
             999999999,
             false,
+            GasConfig::default(),
             Some(vec![("profiling", fns_to_import)].into_iter().collect()),
         )
         .unwrap();
@@ -0,0 +1,347 @@
+use bech32::{FromBase32, ToBase32, Variant};
+use blake2::{Blake2b, Digest};
+
+use crate::errors::{StdError, StdResult};
+
+/// Length of an Ethereum style address in bytes
+const HEX_ADDRESS_LEN: usize = 20;
+/// Maximum length of a bech32 encoded address, as enforced by the Cosmos SDK
+const BECH32_ADDRESS_MAX_LEN: usize = 255;
+/// Length of a Substrate account ID in bytes
+const SS58_ACCOUNT_ID_LEN: usize = 32;
+/// Length of the SS58 checksum for 32 byte account IDs
+const SS58_CHECKSUM_LEN: usize = 2;
+/// The largest network ID that can be encoded in SS58
+const SS58_MAX_NETWORK_ID: u16 = 16_383;
+const SS58_CHECKSUM_PREFIX: &[u8] = b"SS58PRE";
+
+/// A real world address format that [`MockApi`] can be configured with, such that tests
+/// exercise the same address validation the chain performs.
+///
+/// [`MockApi`]: crate::testing::MockApi
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AddressCodec {
+    /// Bech32 with the given human readable prefix, e.g. `cosmos`, as used by Cosmos SDK chains.
+    /// The canonical address is the decoded data of 1 to 255 bytes.
+    /// Addresses are normalized to lowercase.
+    Bech32 { prefix: &'static str },
+    /// 20 bytes of `0x` prefixed hex, as used by Ethereum. Mixed case input must have a valid
+    /// [EIP-55](https://eips.ethereum.org/EIPS/eip-55) checksum.
+    /// Addresses are normalized to the checksummed form.
+    Hex,
+    /// SS58 with the given network ID, e.g. 42 for generic Substrate, as used by Substrate chains.
+    /// The canonical address is the 32 byte account ID.
+    Ss58 { network_id: u16 },
+}
+
+impl AddressCodec {
+    /// Decodes a human readable address into its canonical bytes
+    pub fn canonicalize(&self, human: &str) -> StdResult<Vec<u8>> {
+        match *self {
+            AddressCodec::Bech32 { prefix } => bech32_decode(prefix, human),
+            AddressCodec::Hex => hex_decode(human),
+            AddressCodec::Ss58 { network_id } => ss58_decode(network_id, human),
+        }
+    }
+
+    /// Encodes canonical bytes into the normalized human readable address
+    pub fn humanize(&self, canonical: &[u8]) -> StdResult<String> {
+        match *self {
+            AddressCodec::Bech32 { prefix } => bech32_encode(prefix, canonical),
+            AddressCodec::Hex => hex_encode(canonical),
+            AddressCodec::Ss58 { network_id } => ss58_encode(network_id, canonical),
+        }
+    }
+}
+
+fn bech32_decode(prefix: &str, human: &str) -> StdResult<Vec<u8>> {
+    let (hrp, data, variant) = bech32::decode(human)
+        .map_err(|err| StdError::generic_err(format!("Invalid input: {}", err)))?;
+    if hrp != prefix {
+        return Err(StdError::generic_err(format!(
+            "Invalid input: wrong bech32 prefix {}, expected {}",
+            hrp, prefix
+        )));
+    }
+    if variant != Variant::Bech32 {
+        return Err(StdError::generic_err(
+            "Invalid input: bech32m addresses are not supported",
+        ));
+    }
+    let canonical = Vec::<u8>::from_base32(&data)
+        .map_err(|err| StdError::generic_err(format!("Invalid input: {}", err)))?;
+    check_bech32_length(&canonical)?;
+    Ok(canonical)
+}
+
+fn bech32_encode(prefix: &str, canonical: &[u8]) -> StdResult<String> {
+    check_bech32_length(canonical)?;
+    bech32::encode(prefix, canonical.to_base32(), Variant::Bech32)
+        .map_err(|err| StdError::generic_err(format!("Invalid input: {}", err)))
+}
+
+fn check_bech32_length(canonical: &[u8]) -> StdResult<()> {
+    if canonical.is_empty() || canonical.len() > BECH32_ADDRESS_MAX_LEN {
+        return Err(StdError::generic_err(
+            "Invalid input: canonical address length not correct",
+        ));
+    }
+    Ok(())
+}
+
+fn hex_decode(human: &str) -> StdResult<Vec<u8>> {
+    let digits = human
+        .strip_prefix("0x")
+        .ok_or_else(|| StdError::generic_err("Invalid input: address must start with 0x"))?;
+    let canonical = hex::decode(digits)
+        .map_err(|err| StdError::generic_err(format!("Invalid input: {}", err)))?;
+    if canonical.len() != HEX_ADDRESS_LEN {
+        return Err(StdError::generic_err(
+            "Invalid input: address must be 20 bytes long",
+        ));
+    }
+    // All lowercase or all uppercase input carries no checksum
+    let is_mixed_case = digits.chars().any(|c| c.is_ascii_lowercase())
+        && digits.chars().any(|c| c.is_ascii_uppercase());
+    if is_mixed_case && hex_encode(&canonical)? != human {
+        return Err(StdError::generic_err(
+            "Invalid input: wrong EIP-55 checksum",
+        ));
+    }
+    Ok(canonical)
+}
+
+fn hex_encode(canonical: &[u8]) -> StdResult<String> {
+    if canonical.len() != HEX_ADDRESS_LEN {
+        return Err(StdError::generic_err(
+            "Invalid input: canonical address length not correct",
+        ));
+    }
+    let lowercase = hex::encode(canonical);
+    let hash = cosmwasm_crypto::keccak256(lowercase.as_bytes());
+    // A letter is uppercase if the corresponding nibble of the hash is 8 or greater
+    let checksummed: String = lowercase
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let nibble = (hash[i / 2] >> (4 * (1 - i % 2))) & 0x0f;
+            if nibble >= 8 {
+                c.to_ascii_uppercase()
+            } else {
+                c
+            }
+        })
+        .collect();
+    Ok(format!("0x{}", checksummed))
+}
+
+fn ss58_decode(network_id: u16, human: &str) -> StdResult<Vec<u8>> {
+    let data = bs58::decode(human)
+        .into_vec()
+        .map_err(|err| StdError::generic_err(format!("Invalid input: {}", err)))?;
+    let (prefix_len, decoded_network_id) = match data.first() {
+        Some(&first @ 0..=63) => (1, first as u16),
+        Some(&first @ 64..=127) if data.len() > 1 => {
+            let lower = (first << 2) | (data[1] >> 6);
+            let upper = data[1] & 0b0011_1111;
+            (2, (lower as u16) | ((upper as u16) << 8))
+        }
+        _ => return Err(StdError::generic_err("Invalid input: invalid SS58 prefix")),
+    };
+    if data.len() != prefix_len + SS58_ACCOUNT_ID_LEN + SS58_CHECKSUM_LEN {
+        return Err(StdError::generic_err(
+            "Invalid input: address must contain a 32 byte account ID",
+        ));
+    }
+    if decoded_network_id != network_id {
+        return Err(StdError::generic_err(format!(
+            "Invalid input: wrong SS58 network ID {}, expected {}",
+            decoded_network_id, network_id
+        )));
+    }
+    let (payload, checksum) = data.split_at(prefix_len + SS58_ACCOUNT_ID_LEN);
+    if ss58_checksum(payload) != checksum {
+        return Err(StdError::generic_err("Invalid input: wrong SS58 checksum"));
+    }
+    Ok(payload[prefix_len..].to_vec())
+}
+
+fn ss58_encode(network_id: u16, canonical: &[u8]) -> StdResult<String> {
+    if canonical.len() != SS58_ACCOUNT_ID_LEN {
+        return Err(StdError::generic_err(
+            "Invalid input: canonical address length not correct",
+        ));
+    }
+    let mut data = match network_id {
+        0..=63 => vec![network_id as u8],
+        64..=SS58_MAX_NETWORK_ID => {
+            let first = ((network_id & 0b0000_0000_1111_1100) as u8) >> 2;
+            let second =
+                ((network_id >> 8) as u8) | (((network_id & 0b0000_0000_0000_0011) as u8) << 6);
+            vec![first | 0b0100_0000, second]
+        }
+        _ => {
+            return Err(StdError::generic_err(format!(
+                "SS58 network ID {} is out of range",
+                network_id
+            )))
+        }
+    };
+    data.extend_from_slice(canonical);
+    let checksum = ss58_checksum(&data);
+    data.extend_from_slice(&checksum);
+    Ok(bs58::encode(data).into_string())
+}
+
+fn ss58_checksum(payload: &[u8]) -> [u8; SS58_CHECKSUM_LEN] {
+    let hash = Blake2b::new()
+        .chain(SS58_CHECKSUM_PREFIX)
+        .chain(payload)
+        .finalize();
+    [hash[0], hash[1]]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex_literal::hex;
+
+    // The well known development account "Alice"
+    const ALICE: [u8; 32] =
+        hex!("d43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d");
+
+    #[test]
+    fn bech32_works() {
+        let codec = AddressCodec::Bech32 { prefix: "juno" };
+        let human = "juno16s6e83c4lhf3ccg5r27sf2vl66pzep2ca6ju5y";
+        let canonical = codec.canonicalize(human).unwrap();
+        assert_eq!(canonical, ALICE[..20]);
+        assert_eq!(codec.humanize(&canonical).unwrap(), human);
+
+        // 32 byte addresses as used for contracts
+        let codec = AddressCodec::Bech32 { prefix: "cosmos" };
+        let human = "cosmos16s6e83c4lhf3ccg5r27sf2vl66pzep2cs4xvmcu626zw0ftd5f7suwmddn";
+        assert_eq!(codec.canonicalize(human).unwrap(), ALICE);
+        assert_eq!(codec.humanize(&ALICE).unwrap(), human);
+
+        // Uppercase input is decoded but humanizes to lowercase
+        let canonical = codec
+            .canonicalize("COSMOS1QQQSYQCYQ5RQWZQFPG9SCRGWPUGPZYSNRK363E")
+            .unwrap();
+        assert_eq!(
+            codec.humanize(&canonical).unwrap(),
+            "cosmos1qqqsyqcyq5rqwzqfpg9scrgwpugpzysnrk363e"
+        );
+    }
+
+    #[test]
+    fn bech32_rejects_invalid_addresses() {
+        let codec = AddressCodec::Bech32 { prefix: "cosmos" };
+        // Wrong prefix
+        codec
+            .canonicalize("juno16s6e83c4lhf3ccg5r27sf2vl66pzep2ca6ju5y")
+            .unwrap_err();
+        // Wrong checksum
+        codec
+            .canonicalize("cosmos1qqqsyqcyq5rqwzqfpg9scrgwpugpzysnrk363f")
+            .unwrap_err();
+        // Mixed case
+        codec
+            .canonicalize("cosmos1qqqsyqcyq5rqwzqfpg9scrgwpugpzysnrk363E")
+            .unwrap_err();
+        // Not bech32 at all
+        codec.canonicalize("cosmos2contract").unwrap_err();
+        codec.humanize(&[]).unwrap_err();
+    }
+
+    #[test]
+    fn hex_works() {
+        let codec = AddressCodec::Hex;
+        // Test vectors from EIP-55
+        for human in [
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+            "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+        ] {
+            let canonical = codec.canonicalize(human).unwrap();
+            assert_eq!(canonical.len(), 20);
+            assert_eq!(codec.humanize(&canonical).unwrap(), human);
+        }
+
+        // Input without checksum humanizes to the checksummed form
+        let canonical = codec
+            .canonicalize("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed")
+            .unwrap();
+        assert_eq!(canonical, hex!("5aaeb6053f3e94c9b9a09f33669435e7ef1beaed"));
+        assert_eq!(
+            codec.humanize(&canonical).unwrap(),
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"
+        );
+        codec
+            .canonicalize("0x5AAEB6053F3E94C9B9A09F33669435E7EF1BEAED")
+            .unwrap();
+    }
+
+    #[test]
+    fn hex_rejects_invalid_addresses() {
+        let codec = AddressCodec::Hex;
+        // Wrong checksum
+        codec
+            .canonicalize("0x5AAeb6053F3E94C9b9A09f33669435E7Ef1BeAed")
+            .unwrap_err();
+        // Missing prefix
+        codec
+            .canonicalize("5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed")
+            .unwrap_err();
+        // Wrong length
+        codec
+            .canonicalize("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeA")
+            .unwrap_err();
+        // Not hex
+        codec
+            .canonicalize("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeg")
+            .unwrap_err();
+        codec.humanize(&ALICE).unwrap_err();
+    }
+
+    #[test]
+    fn ss58_works() {
+        let codec = AddressCodec::Ss58 { network_id: 42 };
+        let human = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+        assert_eq!(codec.canonicalize(human).unwrap(), ALICE);
+        assert_eq!(codec.humanize(&ALICE).unwrap(), human);
+
+        // Polkadot
+        let codec = AddressCodec::Ss58 { network_id: 0 };
+        let human = "15oF4uVJwmo4TdGW7VfQxNLavjCXviqxT9S1MgbjMNHr6Sp5";
+        assert_eq!(codec.canonicalize(human).unwrap(), ALICE);
+        assert_eq!(codec.humanize(&ALICE).unwrap(), human);
+
+        // Two byte network ID (Moonbeam)
+        let codec = AddressCodec::Ss58 { network_id: 1284 };
+        let human = "VdvKmYJfD4VXA9fzz1SbmCo2eYHSzUFbaDCZSuaNKJAe8YNg6";
+        assert_eq!(codec.canonicalize(human).unwrap(), ALICE);
+        assert_eq!(codec.humanize(&ALICE).unwrap(), human);
+    }
+
+    #[test]
+    fn ss58_rejects_invalid_addresses() {
+        let codec = AddressCodec::Ss58 { network_id: 42 };
+        // Wrong network ID
+        codec
+            .canonicalize("15oF4uVJwmo4TdGW7VfQxNLavjCXviqxT9S1MgbjMNHr6Sp5")
+            .unwrap_err();
+        // Wrong checksum
+        codec
+            .canonicalize("5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQZ")
+            .unwrap_err();
+        // Not base58
+        codec
+            .canonicalize("0GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY")
+            .unwrap_err();
+        codec.humanize(&ALICE[..20]).unwrap_err();
+
+        let codec = AddressCodec::Ss58 { network_id: 16_384 };
+        codec.humanize(&ALICE).unwrap_err();
+    }
+}
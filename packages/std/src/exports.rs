@@ -41,6 +41,30 @@ extern "C" fn requires_staking() -> () {}
 #[no_mangle]
 extern "C" fn requires_stargate() -> () {}
 
+#[cfg(feature = "floats")]
+#[no_mangle]
+extern "C" fn requires_floats() -> () {}
+
+#[cfg(feature = "sr25519")]
+#[no_mangle]
+extern "C" fn requires_sr25519() -> () {}
+
+#[cfg(feature = "hashes")]
+#[no_mangle]
+extern "C" fn requires_hashes() -> () {}
+
+#[cfg(feature = "secp256r1")]
+#[no_mangle]
+extern "C" fn requires_secp256r1() -> () {}
+
+#[cfg(feature = "ethereum")]
+#[no_mangle]
+extern "C" fn requires_ethereum() -> () {}
+
+#[cfg(feature = "bls12_381")]
+#[no_mangle]
+extern "C" fn requires_bls12_381() -> () {}
+
 /// interface_version_* exports mark which Wasm VM interface level this contract is compiled for.
 /// They can be checked by cosmwasm_vm.
 /// Update this whenever the Wasm VM interface breaks.
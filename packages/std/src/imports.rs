@@ -55,6 +55,20 @@ extern "C" {
         recovery_param: u32,
     ) -> u64;
 
+    /// Verifies message hashes against a signature with a public key, using the
+    /// secp256r1 (NIST P-256) ECDSA parametrization.
+    /// Returns 0 on verification success, 1 on verification failure, and values
+    /// greater than 1 in case of error.
+    #[cfg(feature = "secp256r1")]
+    fn secp256r1_verify(message_hash_ptr: u32, signature_ptr: u32, public_key_ptr: u32) -> u32;
+
+    /// Recovers the Ethereum address of the signer from a message hash, a signature (r and s)
+    /// and the Ethereum style value `v`, which includes the chain ID when EIP-155 is used.
+    /// Returns the error code in the 32 higher bits and a pointer to a Region containing
+    /// the 20 byte address in the 32 lower bits.
+    #[cfg(feature = "ethereum")]
+    fn ethereum_recover_address(message_hash_ptr: u32, signature_ptr: u32, v: u64) -> u64;
+
     /// Verifies a message against a signature with a public key, using the
     /// ed25519 EdDSA scheme.
     /// Returns 0 on verification success, 1 on verification failure, and values
@@ -67,6 +81,39 @@ extern "C" {
     /// greater than 1 in case of error.
     fn ed25519_batch_verify(messages_ptr: u32, signatures_ptr: u32, public_keys_ptr: u32) -> u32;
 
+    /// Verifies a message against a signature with a public key, using the
+    /// sr25519 Schnorr scheme in the "substrate" signing context.
+    /// Returns 0 on verification success, 1 on verification failure, and values
+    /// greater than 1 in case of error.
+    #[cfg(feature = "sr25519")]
+    fn sr25519_verify(message_ptr: u32, signature_ptr: u32, public_key_ptr: u32) -> u32;
+
+    /// Verifies an aggregate signature of one or more messages with the public keys of the
+    /// signers, using the BLS12-381 proof of possession scheme with signatures in G2.
+    /// Returns 0 on verification success, 1 on verification failure, and values
+    /// greater than 1 in case of error.
+    #[cfg(feature = "bls12_381")]
+    fn bls12_381_aggregate_verify(
+        messages_ptr: u32,
+        signature_ptr: u32,
+        public_keys_ptr: u32,
+    ) -> u32;
+
+    /// Hashes the data with SHA-256.
+    /// Returns a pointer to a Region containing the 32 byte hash.
+    #[cfg(feature = "hashes")]
+    fn sha256(data_ptr: u32) -> u32;
+
+    /// Hashes the data with Keccak-256 as used by Ethereum.
+    /// Returns a pointer to a Region containing the 32 byte hash.
+    #[cfg(feature = "hashes")]
+    fn keccak256(data_ptr: u32) -> u32;
+
+    /// Hashes the data with BLAKE2b-256 as used by Substrate.
+    /// Returns a pointer to a Region containing the 32 byte hash.
+    #[cfg(feature = "hashes")]
+    fn blake2b_256(data_ptr: u32) -> u32;
+
     /// Writes a debug message (UFT-8 encoded) to the host for debugging purposes.
     /// The host is free to log or process this in any way it considers appropriate.
     /// In production environments it is expected that those messages are discarded.
@@ -299,6 +346,61 @@ impl Api for ExternalApi {
         }
     }
 
+    #[cfg(feature = "secp256r1")]
+    fn secp256r1_verify(
+        &self,
+        message_hash: &[u8],
+        signature: &[u8],
+        public_key: &[u8],
+    ) -> Result<bool, VerificationError> {
+        let hash_send = build_region(message_hash);
+        let hash_send_ptr = &*hash_send as *const Region as u32;
+        let sig_send = build_region(signature);
+        let sig_send_ptr = &*sig_send as *const Region as u32;
+        let pubkey_send = build_region(public_key);
+        let pubkey_send_ptr = &*pubkey_send as *const Region as u32;
+
+        let result = unsafe { secp256r1_verify(hash_send_ptr, sig_send_ptr, pubkey_send_ptr) };
+        match result {
+            0 => Ok(true),
+            1 => Ok(false),
+            3 => Err(VerificationError::InvalidHashFormat),
+            4 => Err(VerificationError::InvalidSignatureFormat),
+            5 => Err(VerificationError::InvalidPubkeyFormat),
+            10 => Err(VerificationError::GenericErr),
+            error_code => Err(VerificationError::unknown_err(error_code)),
+        }
+    }
+
+    #[cfg(feature = "ethereum")]
+    fn ethereum_recover_address(
+        &self,
+        message_hash: &[u8],
+        signature: &[u8],
+        v: u64,
+    ) -> Result<[u8; 20], RecoverPubkeyError> {
+        let hash_send = build_region(message_hash);
+        let hash_send_ptr = &*hash_send as *const Region as u32;
+        let sig_send = build_region(signature);
+        let sig_send_ptr = &*sig_send as *const Region as u32;
+
+        let result = unsafe { ethereum_recover_address(hash_send_ptr, sig_send_ptr, v) };
+        let error_code = from_high_half(result);
+        let address_ptr = from_low_half(result);
+        match error_code {
+            0 => {
+                let address = unsafe { consume_region(address_ptr as *mut Region) };
+                Ok(address
+                    .try_into()
+                    .expect("Address must be 20 bytes long. This is a bug in the VM."))
+            }
+            3 => Err(RecoverPubkeyError::InvalidHashFormat),
+            4 => Err(RecoverPubkeyError::InvalidSignatureFormat),
+            6 => Err(RecoverPubkeyError::InvalidRecoveryParam),
+            error_code => Err(RecoverPubkeyError::unknown_err(error_code)),
+        }
+    }
+
     fn ed25519_verify(
         &self,
         message: &[u8],
@@ -357,6 +459,77 @@ impl Api for ExternalApi {
         }
     }
 
+    #[cfg(feature = "sr25519")]
+    fn sr25519_verify(
+        &self,
+        message: &[u8],
+        signature: &[u8],
+        public_key: &[u8],
+    ) -> Result<bool, VerificationError> {
+        let msg_send = build_region(message);
+        let msg_send_ptr = &*msg_send as *const Region as u32;
+        let sig_send = build_region(signature);
+        let sig_send_ptr = &*sig_send as *const Region as u32;
+        let pubkey_send = build_region(public_key);
+        let pubkey_send_ptr = &*pubkey_send as *const Region as u32;
+
+        let result = unsafe { sr25519_verify(msg_send_ptr, sig_send_ptr, pubkey_send_ptr) };
+        match result {
+            0 => Ok(true),
+            1 => Ok(false),
+            4 => Err(VerificationError::InvalidSignatureFormat),
+            5 => Err(VerificationError::InvalidPubkeyFormat),
+            10 => Err(VerificationError::GenericErr),
+            error_code => Err(VerificationError::unknown_err(error_code)),
+        }
+    }
+
+    #[cfg(feature = "bls12_381")]
+    fn bls12_381_aggregate_verify(
+        &self,
+        messages: &[&[u8]],
+        signature: &[u8],
+        public_keys: &[&[u8]],
+    ) -> Result<bool, VerificationError> {
+        let msgs_encoded = encode_sections(messages);
+        let msgs_send = build_region(&msgs_encoded);
+        let msgs_send_ptr = &*msgs_send as *const Region as u32;
+
+        let sig_send = build_region(signature);
+        let sig_send_ptr = &*sig_send as *const Region as u32;
+
+        let pubkeys_encoded = encode_sections(public_keys);
+        let pubkeys_send = build_region(&pubkeys_encoded);
+        let pubkeys_send_ptr = &*pubkeys_send as *const Region as u32;
+
+        let result =
+            unsafe { bls12_381_aggregate_verify(msgs_send_ptr, sig_send_ptr, pubkeys_send_ptr) };
+        match result {
+            0 => Ok(true),
+            1 => Ok(false),
+            4 => Err(VerificationError::InvalidSignatureFormat),
+            5 => Err(VerificationError::InvalidPubkeyFormat),
+            7 => Err(VerificationError::BatchErr),
+            10 => Err(VerificationError::GenericErr),
+            error_code => Err(VerificationError::unknown_err(error_code)),
+        }
+    }
+
+    #[cfg(feature = "hashes")]
+    fn sha256(&self, data: &[u8]) -> [u8; 32] {
+        unsafe { call_hash_import(sha256, data) }
+    }
+
+    #[cfg(feature = "hashes")]
+    fn keccak256(&self, data: &[u8]) -> [u8; 32] {
+        unsafe { call_hash_import(keccak256, data) }
+    }
+
+    #[cfg(feature = "hashes")]
+    fn blake2b_256(&self, data: &[u8]) -> [u8; 32] {
+        unsafe { call_hash_import(blake2b_256, data) }
+    }
+
     fn debug(&self, message: &str) {
         // keep the boxes in scope, so we free it at the end (don't cast to pointers same line as build_region)
         let region = build_region(message.as_bytes());
@@ -365,6 +538,18 @@ impl Api for ExternalApi {
     }
 }
 
+/// Calls one of the hashing imports with the given data and reads the 32 byte hash.
+#[cfg(feature = "hashes")]
+unsafe fn call_hash_import(import: unsafe extern "C" fn(u32) -> u32, data: &[u8]) -> [u8; 32] {
+    // keep the box in scope, so we free it at the end (don't cast to pointers same line as build_region)
+    let data_send = build_region(data);
+    let data_send_ptr = &*data_send as *const Region as u32;
+    let hash_ptr = import(data_send_ptr);
+    let hash = consume_region(hash_ptr as *mut Region);
+    hash.try_into()
+        .expect("Hash must be 32 bytes long. This is a bug in the VM.")
+}
+
 /// Takes a pointer to a Region and reads the data into a String.
 /// This is for trusted string sources only.
 unsafe fn consume_string_region_written_by_vm(from: *mut Region) -> String {
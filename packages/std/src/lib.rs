@@ -96,10 +96,13 @@ pub use crate::imports::{ExternalApi, ExternalQuerier, ExternalStorage};
 // Exposed for testing only
 // Both unit tests and integration tests are compiled to native code, so everything in here does not need to compile to Wasm.
 
+#[cfg(not(target_arch = "wasm32"))]
+mod address_codec;
 #[cfg(not(target_arch = "wasm32"))]
 mod mock;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod testing {
+    pub use crate::address_codec::AddressCodec;
     #[cfg(feature = "staking")]
     pub use crate::mock::StakingQuerier;
     pub use crate::mock::{
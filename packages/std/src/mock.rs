@@ -4,6 +4,7 @@ use serde::Serialize;
 use std::collections::HashMap;
 use std::marker::PhantomData;
 
+use crate::address_codec::AddressCodec;
 use crate::addresses::{Addr, CanonicalAddr};
 use crate::binary::Binary;
 use crate::coins::Coin;
@@ -83,17 +84,42 @@ const SHUFFLES_DECODE: usize = 2;
 // MockPrecompiles zero pads all human addresses to make them fit the canonical_length
 // it trims off zeros for the reverse operation.
 // not really smart, but allows us to see a difference (and consistent length for canonical adddresses)
+//
+// When configured with an address codec, the codec is used instead.
 #[derive(Copy, Clone)]
 pub struct MockApi {
     /// Length of canonical addresses created with this API. Contracts should not make any assumtions
     /// what this value is.
     canonical_length: usize,
+    /// A real world address format to use instead of the fake one described above
+    address_codec: Option<AddressCodec>,
+}
+
+impl MockApi {
+    /// Uses the given address format for validation, canonicalization and humanization,
+    /// such that tests accept exactly the addresses the chain accepts.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use cosmwasm_std::Api;
+    /// # use cosmwasm_std::testing::{AddressCodec, MockApi};
+    /// let api = MockApi::default().with_address_codec(AddressCodec::Hex);
+    /// api.addr_validate("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed").unwrap();
+    /// api.addr_validate("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed").unwrap_err();
+    /// api.addr_validate("creator").unwrap_err();
+    /// ```
+    pub fn with_address_codec(mut self, codec: AddressCodec) -> Self {
+        self.address_codec = Some(codec);
+        self
+    }
 }
 
 impl Default for MockApi {
     fn default() -> Self {
         MockApi {
             canonical_length: CANONICAL_LENGTH,
+            address_codec: None,
         }
     }
 }
@@ -112,6 +138,10 @@ impl Api for MockApi {
     }
 
     fn addr_canonicalize(&self, input: &str) -> StdResult<CanonicalAddr> {
+        if let Some(codec) = self.address_codec {
+            return codec.canonicalize(input).map(CanonicalAddr::from);
+        }
+
         // Dummy input validation. This is more sophisticated for formats like bech32, where format and checksum are validated.
         if input.len() < 3 {
             return Err(StdError::generic_err(
@@ -142,6 +172,10 @@ impl Api for MockApi {
     }
 
     fn addr_humanize(&self, canonical: &CanonicalAddr) -> StdResult<Addr> {
+        if let Some(codec) = self.address_codec {
+            return codec.humanize(canonical.as_slice()).map(Addr::unchecked);
+        }
+
         if canonical.len() != self.canonical_length {
             return Err(StdError::generic_err(
                 "Invalid input: canonical address length not correct",
@@ -187,6 +221,34 @@ impl Api for MockApi {
         Ok(pubkey.to_vec())
     }
 
+    #[cfg(feature = "secp256r1")]
+    fn secp256r1_verify(
+        &self,
+        message_hash: &[u8],
+        signature: &[u8],
+        public_key: &[u8],
+    ) -> Result<bool, VerificationError> {
+        Ok(cosmwasm_crypto::secp256r1_verify(
+            message_hash,
+            signature,
+            public_key,
+        )?)
+    }
+
+    #[cfg(feature = "ethereum")]
+    fn ethereum_recover_address(
+        &self,
+        message_hash: &[u8],
+        signature: &[u8],
+        v: u64,
+    ) -> Result<[u8; 20], RecoverPubkeyError> {
+        Ok(cosmwasm_crypto::ethereum_recover_address(
+            message_hash,
+            signature,
+            v,
+        )?)
+    }
+
     fn ed25519_verify(
         &self,
         message: &[u8],
@@ -211,6 +273,47 @@ impl Api for MockApi {
         )?)
     }
 
+    #[cfg(feature = "sr25519")]
+    fn sr25519_verify(
+        &self,
+        message: &[u8],
+        signature: &[u8],
+        public_key: &[u8],
+    ) -> Result<bool, VerificationError> {
+        Ok(cosmwasm_crypto::sr25519_verify(
+            message, signature, public_key,
+        )?)
+    }
+
+    #[cfg(feature = "bls12_381")]
+    fn bls12_381_aggregate_verify(
+        &self,
+        messages: &[&[u8]],
+        signature: &[u8],
+        public_keys: &[&[u8]],
+    ) -> Result<bool, VerificationError> {
+        Ok(cosmwasm_crypto::bls12_381_aggregate_verify(
+            messages,
+            signature,
+            public_keys,
+        )?)
+    }
+
+    #[cfg(feature = "hashes")]
+    fn sha256(&self, data: &[u8]) -> [u8; 32] {
+        cosmwasm_crypto::sha256(data)
+    }
+
+    #[cfg(feature = "hashes")]
+    fn keccak256(&self, data: &[u8]) -> [u8; 32] {
+        cosmwasm_crypto::keccak256(data)
+    }
+
+    #[cfg(feature = "hashes")]
+    fn blake2b_256(&self, data: &[u8]) -> [u8; 32] {
+        cosmwasm_crypto::blake2b_256(data)
+    }
+
     fn debug(&self, message: &str) {
         println!("{}", message);
     }
@@ -855,6 +958,49 @@ mod tests {
         api.addr_humanize(&input).unwrap();
     }
 
+    // Basic "works" test. Exhaustive tests on the codec's side (packages/std/src/address_codec.rs)
+    #[test]
+    fn addr_validate_works_with_address_codec() {
+        let api = MockApi::default().with_address_codec(AddressCodec::Bech32 { prefix: "juno" });
+        let addr = api
+            .addr_validate("juno16s6e83c4lhf3ccg5r27sf2vl66pzep2ca6ju5y")
+            .unwrap();
+        assert_eq!(addr, "juno16s6e83c4lhf3ccg5r27sf2vl66pzep2ca6ju5y");
+        // invalid: not normalized
+        api.addr_validate("JUNO16S6E83C4LHF3CCG5R27SF2VL66PZEP2CA6JU5Y")
+            .unwrap_err();
+        // invalid: accepted by the default MockApi
+        api.addr_validate("foobar123").unwrap_err();
+
+        let api = MockApi::default().with_address_codec(AddressCodec::Hex);
+        api.addr_validate("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed")
+            .unwrap();
+        // invalid: not normalized
+        api.addr_validate("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed")
+            .unwrap_err();
+
+        let api = MockApi::default().with_address_codec(AddressCodec::Ss58 { network_id: 42 });
+        api.addr_validate("5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY")
+            .unwrap();
+        // invalid: other network
+        api.addr_validate("15oF4uVJwmo4TdGW7VfQxNLavjCXviqxT9S1MgbjMNHr6Sp5")
+            .unwrap_err();
+    }
+
+    #[test]
+    fn canonicalize_and_humanize_restores_original_with_address_codec() {
+        let api = MockApi::default().with_address_codec(AddressCodec::Ss58 { network_id: 0 });
+
+        let original = String::from("15oF4uVJwmo4TdGW7VfQxNLavjCXviqxT9S1MgbjMNHr6Sp5");
+        let canonical = api.addr_canonicalize(&original).unwrap();
+        assert_eq!(
+            canonical.as_slice(),
+            hex!("d43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d")
+        );
+        let recovered = api.addr_humanize(&canonical).unwrap();
+        assert_eq!(recovered, original);
+    }
+
     // Basic "works" test. Exhaustive tests on VM's side (packages/vm/src/imports.rs)
     #[test]
     fn secp256k1_verify_works() {
@@ -966,6 +1112,53 @@ mod tests {
         }
     }
 
+    // Basic "works" test. Exhaustive tests on crypto's side (packages/crypto/src/ethereum.rs)
+    #[cfg(feature = "ethereum")]
+    #[test]
+    fn ethereum_recover_address_works() {
+        let api = MockApi::default();
+
+        // Signed text "connect all the things" from contracts/crypto-verify
+        let hash = hex!("858dc8f47a065dee00b28dc0064215b7f0a56c005fa8e823bbb4ce58bdb288cc");
+        let signature = hex!("dada130255a447ecf434a2df9193e6fbba663e4546c35c075cd6eea21d8c7cb1714b9b65a4f7f604ff6aad55fba73f8c36514a512bbbba03709b37069194f8a4");
+        let expected = hex!("12890d2cce102216644c59dae5baed380d84830c");
+
+        let address = api.ethereum_recover_address(&hash, &signature, 27).unwrap();
+        assert_eq!(address, expected);
+
+        // Invalid v leads to error
+        let result = api.ethereum_recover_address(&hash, &signature, 29);
+        match result.unwrap_err() {
+            RecoverPubkeyError::InvalidRecoveryParam => {}
+            err => panic!("Unexpected error: {:?}", err),
+        }
+    }
+
+    // Basic "works" test. Exhaustive tests on crypto's side (packages/crypto/src/secp256r1.rs)
+    #[cfg(feature = "secp256r1")]
+    #[test]
+    fn secp256r1_verify_works() {
+        let api = MockApi::default();
+
+        // Test vector from RFC 6979, A.2.5 (P-256, SHA-256, message "sample")
+        let hash = hex!("af2bdbe1aa9b6ec1e2ade1d694f41fc71a831d0268e9891562113d8a62add1bf");
+        let signature = hex!("efd48b2aacb6a8fd1140dd9cd45e81d69d2c877b56aaf991c34d0ea84eaf3716f7cb1c942d657c41d436c7a1b6e29f65f3e900dbb9aff4064dc4ab2f843acda8");
+        let public_key = hex!("0460fed4ba255a9d31c961eb74c6356d68c049b8923b61fa6ce669622e60f29fb67903fe1008b8bc99a41ae9e95628bc64f2f1b20c2d7e9f5177a3c294d4462299");
+
+        assert!(api
+            .secp256r1_verify(&hash, &signature, &public_key)
+            .unwrap());
+
+        // SHA-256 of "test"
+        let other_hash = hex!("9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08");
+        assert!(!api
+            .secp256r1_verify(&other_hash, &signature, &public_key)
+            .unwrap());
+
+        let res = api.secp256r1_verify(&hash, &signature, &public_key[..64]);
+        assert_eq!(res.unwrap_err(), VerificationError::InvalidPubkeyFormat);
+    }
+
     // Basic "works" test. Exhaustive tests on VM's side (packages/vm/src/imports.rs)
     #[test]
     fn ed25519_verify_works() {
@@ -1005,6 +1198,23 @@ mod tests {
         assert_eq!(res.unwrap_err(), VerificationError::InvalidPubkeyFormat);
     }
 
+    // Basic "errors" test. Exhaustive tests on VM's side (packages/vm/src/imports.rs)
+    #[cfg(feature = "sr25519")]
+    #[test]
+    fn sr25519_verify_errs() {
+        let api = MockApi::default();
+
+        let msg = b"Picasso says hello";
+        let signature = [0u8; 64];
+        let public_key = vec![];
+
+        let res = api.sr25519_verify(msg, &signature, &public_key);
+        assert_eq!(res.unwrap_err(), VerificationError::InvalidPubkeyFormat);
+
+        let res = api.sr25519_verify(msg, &signature[..63], &[0u8; 32]);
+        assert_eq!(res.unwrap_err(), VerificationError::InvalidSignatureFormat);
+    }
+
     // Basic "works" test.
     #[test]
     fn ed25519_batch_verify_works() {
@@ -1060,6 +1270,48 @@ mod tests {
         assert_eq!(res.unwrap_err(), VerificationError::InvalidPubkeyFormat);
     }
 
+    // Basic "errors" test. Exhaustive tests on crypto's side (packages/crypto/src/bls.rs)
+    #[cfg(feature = "bls12_381")]
+    #[test]
+    fn bls12_381_aggregate_verify_errs() {
+        let api = MockApi::default();
+
+        let msg: &[u8] = b"block root";
+        let signature = [0u8; 96];
+        let public_key = [0u8; 48];
+
+        // no public keys
+        let res = api.bls12_381_aggregate_verify(&[msg], &signature, &[]);
+        assert_eq!(res.unwrap_err(), VerificationError::BatchErr);
+
+        // two messages for one public key
+        let res = api.bls12_381_aggregate_verify(&[msg, msg], &signature, &[&public_key]);
+        assert_eq!(res.unwrap_err(), VerificationError::BatchErr);
+
+        let res = api.bls12_381_aggregate_verify(&[msg], &signature[..95], &[&public_key]);
+        assert_eq!(res.unwrap_err(), VerificationError::InvalidSignatureFormat);
+    }
+
+    // Basic "works" test. Exhaustive tests on crypto's side (packages/crypto/src/hashing.rs)
+    #[cfg(feature = "hashes")]
+    #[test]
+    fn hashing_works() {
+        let api = MockApi::default();
+
+        assert_eq!(
+            api.sha256(b"abc"),
+            hex!("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+        );
+        assert_eq!(
+            api.keccak256(b"abc"),
+            hex!("4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45")
+        );
+        assert_eq!(
+            api.blake2b_256(b"abc"),
+            hex!("bddd813c634239723171ef3fee98579b94964e3bb1cb3e427262c8c068d52319")
+        );
+    }
+
     #[test]
     fn bank_querier_all_balances() {
         let addr = String::from("foobar");
@@ -115,6 +115,36 @@ pub trait Api {
         recovery_param: u8,
     ) -> Result<Vec<u8>, RecoverPubkeyError>;
 
+    /// Verifies a message hash against a secp256r1 (NIST P-256) signature with a public key,
+    /// e.g. from a WebAuthn / passkey authenticator.
+    ///
+    /// The signature (64 bytes, `r` and `s`) and public key (33 or 65 bytes, SEC 1 encoded) use
+    /// the same formats as in [`secp256k1_verify`]. Signatures with a high S value are accepted.
+    ///
+    /// [`secp256k1_verify`]: Api::secp256k1_verify
+    #[cfg(feature = "secp256r1")]
+    fn secp256r1_verify(
+        &self,
+        message_hash: &[u8],
+        signature: &[u8],
+        public_key: &[u8],
+    ) -> Result<bool, VerificationError>;
+
+    /// Recovers the 20 byte Ethereum address of the signer from a message hash and a signature,
+    /// like `ecrecover` in the EVM.
+    ///
+    /// The signature consists of `r` and `s` (64 bytes) and the Ethereum style value `v`,
+    /// which is 27 or 28, or `chain_id * 2 + 35` or `chain_id * 2 + 36` as described in
+    /// [EIP-155](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-155.md).
+    /// Contracts that require a specific chain ID need to check `v` themselves.
+    #[cfg(feature = "ethereum")]
+    fn ethereum_recover_address(
+        &self,
+        message_hash: &[u8],
+        signature: &[u8],
+        v: u64,
+    ) -> Result<[u8; 20], RecoverPubkeyError>;
+
     fn ed25519_verify(
         &self,
         message: &[u8],
@@ -129,6 +159,43 @@ pub trait Api {
         public_keys: &[&[u8]],
     ) -> Result<bool, VerificationError>;
 
+    /// Verifies a message against a Substrate sr25519 signature with a public key.
+    /// The signature is expected in the "substrate" signing context.
+    #[cfg(feature = "sr25519")]
+    fn sr25519_verify(
+        &self,
+        message: &[u8],
+        signature: &[u8],
+        public_key: &[u8],
+    ) -> Result<bool, VerificationError>;
+
+    /// Verifies a BLS12-381 aggregate signature (96 bytes, compressed G2 point) with the public
+    /// keys of the signers (48 bytes each, compressed G1 points), like Ethereum consensus clients.
+    ///
+    /// Either every public key signed its own message, in which case there are as many messages
+    /// as public keys, or all public keys signed the same message, which is then passed alone.
+    /// The latter is only secure for public keys whose proof of possession was verified before,
+    /// since it is vulnerable to rogue key attacks otherwise.
+    #[cfg(feature = "bls12_381")]
+    fn bls12_381_aggregate_verify(
+        &self,
+        messages: &[&[u8]],
+        signature: &[u8],
+        public_keys: &[&[u8]],
+    ) -> Result<bool, VerificationError>;
+
+    /// Computes the SHA-256 hash of the data natively in the host.
+    #[cfg(feature = "hashes")]
+    fn sha256(&self, data: &[u8]) -> [u8; 32];
+
+    /// Computes the Keccak-256 hash of the data as used by Ethereum, natively in the host.
+    #[cfg(feature = "hashes")]
+    fn keccak256(&self, data: &[u8]) -> [u8; 32];
+
+    /// Computes the BLAKE2b-256 hash of the data as used by Substrate, natively in the host.
+    #[cfg(feature = "hashes")]
+    fn blake2b_256(&self, data: &[u8]) -> [u8; 32];
+
     /// Emits a debugging message that is handled depending on the environment (typically printed to console or ignored).
     /// Those messages are not persisted to chain.
     fn debug(&self, message: &str);
@@ -11,8 +11,8 @@ use cosmwasm_vm::testing::{
     mock_backend, mock_env, mock_info, mock_instance_options, MockApi, MockQuerier, MockStorage,
 };
 use cosmwasm_vm::{
-    call_execute, call_instantiate, features_from_csv, Cache, CacheOptions, Checksum, Instance,
-    InstanceOptions, Size,
+    call_execute, call_instantiate, features_from_csv, Cache, CacheOptions, Checksum, GasConfig,
+    GatekeeperPolicy, Instance, InstanceOptions, Size, WasmLimits,
 };
 
 // Instance
@@ -21,6 +21,7 @@ const DEFAULT_GAS_LIMIT: u64 = 1_000_000_000_000; // ~1ms
 const DEFAULT_INSTANCE_OPTIONS: InstanceOptions = InstanceOptions {
     gas_limit: DEFAULT_GAS_LIMIT,
     print_debug: false,
+    gas_config: None,
 };
 const HIGH_GAS_LIMIT: u64 = 20_000_000_000_000_000; // ~20s, allows many calls on one instance
 
@@ -125,7 +126,12 @@ fn bench_cache(c: &mut Criterion) {
         base_dir: TempDir::new().unwrap().into_path(),
         supported_features: features_from_csv("iterator,staking"),
         memory_cache_size: MEMORY_CACHE_SIZE,
+        fs_cache_size: None,
+        fs_cache_hmac_key: None,
         instance_memory_limit: DEFAULT_MEMORY_LIMIT,
+        gas_config: GasConfig::default(),
+        gatekeeper_policy: GatekeeperPolicy::default(),
+        wasm_limits: WasmLimits::default(),
     };
 
     group.bench_function("save wasm", |b| {
@@ -165,7 +171,12 @@ fn bench_cache(c: &mut Criterion) {
             base_dir: TempDir::new().unwrap().into_path(),
             supported_features: features_from_csv("iterator,staking"),
             memory_cache_size: Size(0),
+            fs_cache_size: None,
+            fs_cache_hmac_key: None,
             instance_memory_limit: DEFAULT_MEMORY_LIMIT,
+            gas_config: GasConfig::default(),
+            gatekeeper_policy: GatekeeperPolicy::default(),
+            wasm_limits: WasmLimits::default(),
         };
         let cache: Cache<MockApi, MockStorage, MockQuerier> =
             unsafe { Cache::new(non_memcache).unwrap() };
@@ -231,7 +242,12 @@ pub fn bench_instance_threads(c: &mut Criterion) {
             base_dir: TempDir::new().unwrap().into_path(),
             supported_features: features_from_csv("iterator,staking"),
             memory_cache_size: MEMORY_CACHE_SIZE,
+            fs_cache_size: None,
+            fs_cache_hmac_key: None,
             instance_memory_limit: DEFAULT_MEMORY_LIMIT,
+            gas_config: GasConfig::default(),
+            gatekeeper_policy: GatekeeperPolicy::default(),
+            wasm_limits: WasmLimits::default(),
         };
 
         let cache: Cache<MockApi, MockStorage, MockQuerier> =
@@ -5,7 +5,8 @@ use tempfile::TempDir;
 use cosmwasm_std::{coins, Empty};
 use cosmwasm_vm::testing::{mock_backend, mock_env, mock_info, MockApi, MockQuerier, MockStorage};
 use cosmwasm_vm::{
-    call_execute, call_instantiate, features_from_csv, Cache, CacheOptions, InstanceOptions, Size,
+    call_execute, call_instantiate, features_from_csv, Cache, CacheOptions, GasConfig,
+    GatekeeperPolicy, InstanceOptions, Size, WasmLimits,
 };
 
 // Instance
@@ -14,6 +15,7 @@ const DEFAULT_GAS_LIMIT: u64 = 400_000 * 150_000;
 const DEFAULT_INSTANCE_OPTIONS: InstanceOptions = InstanceOptions {
     gas_limit: DEFAULT_GAS_LIMIT,
     print_debug: false,
+    gas_config: None,
 };
 // Cache
 const MEMORY_CACHE_SIZE: Size = Size::mebi(200);
@@ -29,7 +31,12 @@ pub fn main() {
         base_dir: TempDir::new().unwrap().into_path(),
         supported_features: features_from_csv("iterator,staking"),
         memory_cache_size: MEMORY_CACHE_SIZE,
+        fs_cache_size: None,
+        fs_cache_hmac_key: None,
         instance_memory_limit: DEFAULT_MEMORY_LIMIT,
+        gas_config: GasConfig::default(),
+        gatekeeper_policy: GatekeeperPolicy::default(),
+        wasm_limits: WasmLimits::default(),
     };
 
     let cache: Cache<MockApi, MockStorage, MockQuerier> = unsafe { Cache::new(options).unwrap() };
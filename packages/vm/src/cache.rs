@@ -1,20 +1,28 @@
 use std::collections::HashSet;
-use std::fs::{create_dir_all, File, OpenOptions};
-use std::io::{Read, Write};
+use std::fs::{self, create_dir_all, remove_file, File, OpenOptions};
+use std::io::{ErrorKind, Read, Write};
 use std::marker::PhantomData;
 use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 
+use serde::{Deserialize, Serialize};
+use wasmer::Target;
+
 use crate::backend::{Backend, BackendApi, Querier, Storage};
 use crate::checksum::Checksum;
-use crate::compatibility::check_wasm;
+use crate::compatibility::{check_wasm_with_limits, WasmLimits};
+use crate::environment::GasConfig;
 use crate::errors::{VmError, VmResult};
 use crate::features::required_features_from_module;
 use crate::instance::{Instance, InstanceOptions};
-use crate::modules::{FileSystemCache, InMemoryCache, PinnedMemoryCache};
+use crate::modules::{artifact_digest, FileSystemCache, InMemoryCache, PinnedMemoryCache};
+use crate::serde::{from_slice, to_vec};
 use crate::size::Size;
-use crate::static_analysis::{deserialize_wasm, has_ibc_entry_points};
-use crate::wasm_backend::{compile, make_runtime_store};
+use crate::static_analysis::{
+    deserialize_wasm, entry_points, function_count, has_floats, has_ibc_entry_points, import_names,
+    interface_version, memory_limits, table_limits, Limits,
+};
+use crate::wasm_backend::{compile_with_policy, make_runtime_store, GatekeeperPolicy};
 
 const STATE_DIR: &str = "state";
 // Things related to the state of the blockchain.
@@ -24,11 +32,19 @@ const CACHE_DIR: &str = "cache";
 // Cacheable things.
 const MODULES_DIR: &str = "modules";
 
+/// File name of the manifest in export directories
+const EXPORT_MANIFEST: &str = "manifest.json";
+/// Maximum size of an export manifest in bytes
+const MAX_EXPORT_MANIFEST_LENGTH: usize = 16 * 1024 * 1024;
+
 #[derive(Debug, Default, Clone, Copy)]
 pub struct Stats {
     pub hits_pinned_memory_cache: u32,
     pub hits_memory_cache: u32,
     pub hits_fs_cache: u32,
+    /// Number of modules that failed verification when loading them from the file system cache.
+    /// Those are recompiled from the Wasm, which is also counted as a miss.
+    pub corrupted_fs_cache: u32,
     pub misses: u32,
 }
 
@@ -39,6 +55,8 @@ pub struct Metrics {
     pub elements_memory_cache: usize,
     pub size_pinned_memory_cache: usize,
     pub size_memory_cache: usize,
+    pub elements_fs_cache: usize,
+    pub size_fs_cache: usize,
 }
 
 #[derive(Clone, Debug)]
@@ -46,9 +64,24 @@ pub struct CacheOptions {
     pub base_dir: PathBuf,
     pub supported_features: HashSet<String>,
     pub memory_cache_size: Size,
+    /// Maximum size of the compiled modules stored on disk. When exceeded, the least recently
+    /// used modules are deleted. Unbounded if `None`.
+    pub fs_cache_size: Option<Size>,
+    /// Secret key used to authenticate the compiled modules stored on disk.
+    /// When `None`, modules are only protected against accidental corruption.
+    pub fs_cache_hmac_key: Option<Vec<u8>>,
     /// Memory limit for instances, in bytes. Use a value that is divisible by the Wasm page size 65536,
     /// e.g. full MiBs.
     pub instance_memory_limit: Size,
+    /// Gas schedule of the imports, used by all instances created by this cache
+    /// unless `InstanceOptions::gas_config` is set
+    pub gas_config: GasConfig,
+    /// Wasm features accepted when compiling contracts. Modules compiled under different
+    /// policies are stored separately on disk. Deterministic floats are enabled
+    /// automatically if `supported_features` contains the "floats" capability.
+    pub gatekeeper_policy: GatekeeperPolicy,
+    /// Limits on the complexity of contracts, checked when storing Wasm
+    pub wasm_limits: WasmLimits,
 }
 
 pub struct CacheInner {
@@ -62,10 +95,36 @@ pub struct CacheInner {
     stats: Stats,
 }
 
+impl CacheInner {
+    /// Loads a module from the file system cache.
+    ///
+    /// Corrupted modules are deleted by the file system cache and reported as not found,
+    /// such that the caller recompiles them from the Wasm.
+    fn load_from_fs_cache(
+        &mut self,
+        checksum: &Checksum,
+        store: &wasmer::Store,
+    ) -> VmResult<Option<wasmer::Module>> {
+        match self.fs_cache.load(checksum, store) {
+            Err(VmError::IntegrityErr { .. }) => {
+                self.stats.corrupted_fs_cache += 1;
+                Ok(None)
+            }
+            result => result,
+        }
+    }
+}
+
 pub struct Cache<A: BackendApi, S: Storage, Q: Querier> {
     /// Supported features are immutable for the lifetime of the cache,
     /// i.e. any number of read-only references is allowed to access it concurrently.
     supported_features: HashSet<String>,
+    /// The gas schedule is immutable for the lifetime of the cache.
+    gas_config: GasConfig,
+    /// The Gatekeeper policy is immutable for the lifetime of the cache.
+    gatekeeper_policy: GatekeeperPolicy,
+    /// The Wasm limits are immutable for the lifetime of the cache.
+    wasm_limits: WasmLimits,
     inner: Mutex<CacheInner>,
     // Those two don't store data but only fix type information
     type_api: PhantomData<A>,
@@ -75,10 +134,44 @@ pub struct Cache<A: BackendApi, S: Storage, Q: Querier> {
     instantiation_lock: Mutex<()>,
 }
 
+/// Describes the Wasm blobs and compiled modules in a directory created by [`Cache::export`]
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct ExportManifest {
+    /// Version of the modules, see `FileSystemCache::module_version`
+    module_version: String,
+    /// Target triple of the node that compiled the modules
+    target: String,
+    modules: Vec<ExportedModule>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct ExportedModule {
+    /// Hex encoded checksum of the Wasm blob
+    checksum: String,
+    /// Hex encoded unkeyed digest of the module, see `artifact_digest`
+    digest: String,
+}
+
 #[derive(PartialEq, Debug)]
 pub struct AnalysisReport {
     pub has_ibc_entry_points: bool,
     pub required_features: HashSet<String>,
+    /// The entry points exported by the contract, e.g. "instantiate" or "ibc_channel_open"
+    pub entry_points: HashSet<String>,
+    /// Full names of the imports used by the contract, e.g. "env.db_read"
+    pub imports: HashSet<String>,
+    /// The version of the interface_version_* marker export, if there is exactly one
+    pub interface_version: Option<u32>,
+    /// Limits of the memory defined by the contract, in Wasm pages
+    pub memory_limits: Option<Limits>,
+    /// Limits of the table defined by the contract, in elements
+    pub table_limits: Option<Limits>,
+    /// Number of functions defined by the contract, not counting imports
+    pub function_count: usize,
+    /// Size of the Wasm bytecode in bytes
+    pub code_size: usize,
+    /// True if the contract uses float types or operations anywhere
+    pub has_floats: bool,
 }
 
 impl<A, S, Q> Cache<A, S, Q>
@@ -99,7 +192,12 @@ where
             base_dir,
             supported_features,
             memory_cache_size,
+            fs_cache_size,
+            fs_cache_hmac_key,
             instance_memory_limit,
+            gas_config,
+            gatekeeper_policy,
+            wasm_limits,
         } = options;
 
         let state_path = base_dir.join(STATE_DIR);
@@ -118,10 +216,19 @@ where
             })?;
         }
 
-        let fs_cache = FileSystemCache::new(cache_path.join(MODULES_DIR))
-            .map_err(|e| VmError::cache_err(format!("Error file system cache: {}", e)))?;
+        let gatekeeper_policy = gatekeeper_policy.with_features(&supported_features);
+        let fs_cache = FileSystemCache::new(
+            cache_path.join(MODULES_DIR),
+            fs_cache_size,
+            fs_cache_hmac_key,
+            gatekeeper_policy,
+        )
+        .map_err(|e| VmError::cache_err(format!("Error file system cache: {}", e)))?;
         Ok(Cache {
             supported_features,
+            gas_config,
+            gatekeeper_policy,
+            wasm_limits,
             inner: Mutex::new(CacheInner {
                 wasm_path,
                 instance_memory_limit,
@@ -149,12 +256,14 @@ where
             elements_memory_cache: cache.memory_cache.len(),
             size_pinned_memory_cache: cache.pinned_memory_cache.size(),
             size_memory_cache: cache.memory_cache.size(),
+            elements_fs_cache: cache.fs_cache.len(),
+            size_fs_cache: cache.fs_cache.size(),
         }
     }
 
     pub fn save_wasm(&self, wasm: &[u8]) -> VmResult<Checksum> {
-        check_wasm(wasm, &self.supported_features)?;
-        let module = compile(wasm, None, &[])?;
+        check_wasm_with_limits(wasm, &self.supported_features, &self.wasm_limits)?;
+        let module = compile_with_policy(wasm, None, &[], self.gatekeeper_policy)?;
 
         let mut cache = self.inner.lock().unwrap();
         let checksum = save_wasm_to_disk(&cache.wasm_path, wasm)?;
@@ -181,6 +290,187 @@ where
         }
     }
 
+    /// Removes a Wasm blob that was previously stored via save_wasm, along with its compiled
+    /// module in all caches (including the pinned one).
+    ///
+    /// Not found IDs are silently ignored. Callers must ensure the code is not used anymore,
+    /// since it cannot be instantiated after removal.
+    pub fn remove_wasm(&self, checksum: &Checksum) -> VmResult<()> {
+        let mut cache = self.inner.lock().unwrap();
+        cache.pinned_memory_cache.remove(checksum)?;
+        cache.memory_cache.remove(checksum)?;
+        cache.fs_cache.remove(checksum)?;
+        remove_wasm_from_disk(&cache.wasm_path, checksum)
+    }
+
+    /// Deletes compiled modules of other module serialization or Wasmer versions from disk.
+    ///
+    /// Those are left behind by node upgrades and can never be loaded by this cache.
+    /// Returns the number of deleted version directories.
+    pub fn remove_stale_modules(&self) -> VmResult<usize> {
+        self.inner.lock().unwrap().fs_cache.remove_stale_versions()
+    }
+
+    /// Stores and compiles all Wasm files (`*.wasm`) in the given directory, like
+    /// [`Cache::save_wasm`] does for a single one. This allows compiling the contracts of a
+    /// chain ahead of time, e.g. when bootstrapping a node.
+    ///
+    /// Returns the checksums in the order of the file names.
+    pub fn precompile_dir(&self, dir: impl AsRef<Path>) -> VmResult<Vec<Checksum>> {
+        let dir = dir.as_ref();
+        let mut paths = Vec::new();
+        for dir_entry in read_dir(dir)? {
+            let path = dir_entry?;
+            if path.is_file() && path.extension().map_or(false, |ext| ext == "wasm") {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+
+        paths
+            .iter()
+            .map(|path| self.save_wasm(&read_file(path)?))
+            .collect()
+    }
+
+    /// Returns the checksums of all stored Wasm blobs in ascending order.
+    pub fn checksums(&self) -> VmResult<Vec<Checksum>> {
+        let cache = self.inner.lock().unwrap();
+        let mut checksums = Vec::new();
+        for dir_entry in read_dir(&cache.wasm_path)? {
+            let path = dir_entry?;
+            // Ignore files not named after a checksum
+            if let Some(checksum) = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| Checksum::from_hex(name).ok())
+            {
+                checksums.push(checksum);
+            }
+        }
+        checksums.sort_by_key(|checksum| checksum.to_hex());
+        Ok(checksums)
+    }
+
+    /// Verifies a stored Wasm blob and its compiled module on disk without loading the module.
+    ///
+    /// Returns true if a valid module is stored and false if the module still needs to be
+    /// compiled. Errors if the Wasm blob is not found or if the Wasm blob or the module are
+    /// corrupted.
+    pub fn verify(&self, checksum: &Checksum) -> VmResult<bool> {
+        let cache = self.inner.lock().unwrap();
+        self.load_wasm_with_path(&cache.wasm_path, checksum)?;
+        Ok(cache.fs_cache.load_artifact(checksum)?.is_some())
+    }
+
+    /// Exports stored Wasm blobs along with their compiled modules into the directory `dir`,
+    /// such that they can be imported via [`Cache::import`] by nodes of the same architecture.
+    /// Modules that are not compiled yet are compiled first.
+    ///
+    /// The directory is created if it doesn't exist. It contains a `manifest.json` as well as
+    /// a `wasm` and a `modules` directory.
+    pub fn export(&self, checksums: &[Checksum], dir: impl AsRef<Path>) -> VmResult<()> {
+        let dir = dir.as_ref();
+        let wasm_dir = dir.join(WASM_DIR);
+        let modules_dir = dir.join(MODULES_DIR);
+        for path in [&wasm_dir, &modules_dir] {
+            create_dir_all(path).map_err(|e| {
+                VmError::cache_err(format!(
+                    "Error creating directory {}: {}",
+                    path.display(),
+                    e
+                ))
+            })?;
+        }
+
+        let mut cache = self.inner.lock().unwrap();
+        let mut modules = Vec::with_capacity(checksums.len());
+        for checksum in checksums {
+            let wasm = self.load_wasm_with_path(&cache.wasm_path, checksum)?;
+            let artifact = match cache.fs_cache.load_artifact(checksum) {
+                Ok(Some(artifact)) => artifact,
+                Ok(None) | Err(VmError::IntegrityErr { .. }) => {
+                    let module = compile_with_policy(
+                        &wasm,
+                        Some(cache.instance_memory_limit),
+                        &[],
+                        self.gatekeeper_policy,
+                    )?;
+                    cache.fs_cache.store(checksum, &module)?;
+                    cache.fs_cache.load_artifact(checksum)?.ok_or_else(|| {
+                        VmError::cache_err("Module not found in file system cache after storing")
+                    })?
+                }
+                Err(err) => return Err(err),
+            };
+            write_file(&wasm_dir.join(checksum.to_hex()), &wasm)?;
+            write_file(&modules_dir.join(checksum.to_hex()), &artifact)?;
+            modules.push(ExportedModule {
+                checksum: checksum.to_hex(),
+                digest: hex::encode(artifact_digest(None, checksum, &artifact)),
+            });
+        }
+
+        let manifest = ExportManifest {
+            module_version: cache.fs_cache.module_version(),
+            target: host_target(),
+            modules,
+        };
+        write_file(&dir.join(EXPORT_MANIFEST), &to_vec(&manifest)?)
+    }
+
+    /// Imports Wasm blobs and compiled modules from a directory created by [`Cache::export`]
+    /// and returns their checksums. The Wasm blobs are checked like in [`Cache::save_wasm`],
+    /// but the modules are stored as they are instead of being compiled.
+    ///
+    /// # Safety
+    ///
+    /// The imported modules are deserialized without further checks when loading them, so the
+    /// export must come from a trusted node. Exports of other module versions or targets are
+    /// rejected.
+    pub unsafe fn import(&self, dir: impl AsRef<Path>) -> VmResult<Vec<Checksum>> {
+        let dir = dir.as_ref();
+        let manifest: ExportManifest = from_slice(
+            &read_file(&dir.join(EXPORT_MANIFEST))?,
+            MAX_EXPORT_MANIFEST_LENGTH,
+        )?;
+
+        let mut cache = self.inner.lock().unwrap();
+        let module_version = cache.fs_cache.module_version();
+        if manifest.module_version != module_version {
+            return Err(VmError::cache_err(format!(
+                "Export contains modules of version {} but this cache uses {}",
+                manifest.module_version, module_version
+            )));
+        }
+        let target = host_target();
+        if manifest.target != target {
+            return Err(VmError::cache_err(format!(
+                "Export contains modules for target {} but this node is {}",
+                manifest.target, target
+            )));
+        }
+
+        let mut checksums = Vec::with_capacity(manifest.modules.len());
+        for exported in &manifest.modules {
+            let checksum = Checksum::from_hex(&exported.checksum)?;
+            let wasm = read_file(&dir.join(WASM_DIR).join(checksum.to_hex()))?;
+            if Checksum::generate(&wasm) != checksum {
+                return Err(VmError::integrity_err());
+            }
+            check_wasm_with_limits(&wasm, &self.supported_features, &self.wasm_limits)?;
+            let artifact = read_file(&dir.join(MODULES_DIR).join(checksum.to_hex()))?;
+            if hex::encode(artifact_digest(None, &checksum, &artifact)) != exported.digest {
+                return Err(VmError::integrity_err());
+            }
+
+            save_wasm_to_disk(&cache.wasm_path, &wasm)?;
+            cache.fs_cache.store_artifact(&checksum, &artifact)?;
+            checksums.push(checksum);
+        }
+        Ok(checksums)
+    }
+
     /// Performs static anlyzation on this Wasm without compiling or instantiating it.
     ///
     /// Once the contract was stored via [`save_wasm`], this can be called at any point in time.
@@ -192,6 +482,14 @@ where
         Ok(AnalysisReport {
             has_ibc_entry_points: has_ibc_entry_points(&module),
             required_features: required_features_from_module(&module),
+            entry_points: entry_points(&module),
+            imports: import_names(&module),
+            interface_version: interface_version(&module),
+            memory_limits: memory_limits(&module),
+            table_limits: table_limits(&module),
+            function_count: function_count(&module),
+            code_size: wasm.len(),
+            has_floats: has_floats(&wasm)?,
         })
     }
 
@@ -217,7 +515,7 @@ where
 
         // Try to get module from file system cache
         let store = make_runtime_store(Some(cache.instance_memory_limit));
-        if let Some(module) = cache.fs_cache.load(checksum, &store)? {
+        if let Some(module) = cache.load_from_fs_cache(checksum, &store)? {
             cache.stats.hits_fs_cache += 1;
             let module_size = loupe::size_of_val(&module);
             return cache
@@ -227,7 +525,12 @@ where
 
         // Re-compile from original Wasm bytecode
         let code = self.load_wasm_with_path(&cache.wasm_path, checksum)?;
-        let module = compile(&code, Some(cache.instance_memory_limit), &[])?;
+        let module = compile_with_policy(
+            &code,
+            Some(cache.instance_memory_limit),
+            &[],
+            self.gatekeeper_policy,
+        )?;
         // Store into the fs cache too
         cache.fs_cache.store(checksum, &module)?;
         let module_size = loupe::size_of_val(&module);
@@ -263,6 +566,7 @@ where
             backend,
             options.gas_limit,
             options.print_debug,
+            options.gas_config.unwrap_or(self.gas_config),
             None,
             Some(&self.instantiation_lock),
         )?;
@@ -288,7 +592,7 @@ where
 
         // Get module from file system cache
         let store = make_runtime_store(Some(cache.instance_memory_limit));
-        if let Some(module) = cache.fs_cache.load(checksum, &store)? {
+        if let Some(module) = cache.load_from_fs_cache(checksum, &store)? {
             cache.stats.hits_fs_cache += 1;
             let module_size = loupe::size_of_val(&module);
             cache
@@ -304,7 +608,12 @@ where
         // stored the old module format.
         let wasm = self.load_wasm_with_path(&cache.wasm_path, checksum)?;
         cache.stats.misses += 1;
-        let module = compile(&wasm, Some(cache.instance_memory_limit), &[])?;
+        let module = compile_with_policy(
+            &wasm,
+            Some(cache.instance_memory_limit),
+            &[],
+            self.gatekeeper_policy,
+        )?;
         cache.fs_cache.store(checksum, &module)?;
         let module_size = loupe::size_of_val(&module);
         cache
@@ -330,6 +639,33 @@ where
 {
 }
 
+/// The target triple of this node, e.g. `x86_64-unknown-linux-gnu`
+fn host_target() -> String {
+    Target::default().triple().to_string()
+}
+
+/// Returns the paths of all entries of a directory
+fn read_dir(dir: &Path) -> VmResult<impl Iterator<Item = VmResult<PathBuf>>> {
+    let dir_entries = fs::read_dir(dir).map_err(|e| {
+        VmError::cache_err(format!("Error reading directory {}: {}", dir.display(), e))
+    })?;
+    Ok(dir_entries.map(|dir_entry| {
+        dir_entry
+            .map(|dir_entry| dir_entry.path())
+            .map_err(|e| VmError::cache_err(format!("Error reading directory entry: {}", e)))
+    }))
+}
+
+fn read_file(path: &Path) -> VmResult<Vec<u8>> {
+    fs::read(path)
+        .map_err(|e| VmError::cache_err(format!("Error reading {}: {}", path.display(), e)))
+}
+
+fn write_file(path: &Path, data: &[u8]) -> VmResult<()> {
+    fs::write(path, data)
+        .map_err(|e| VmError::cache_err(format!("Error writing {}: {}", path.display(), e)))
+}
+
 /// save stores the wasm code in the given directory and returns an ID for lookup.
 /// It will create the directory if it doesn't exist.
 /// Saving the same byte code multiple times is allowed.
@@ -365,13 +701,27 @@ fn load_wasm_from_disk(dir: impl Into<PathBuf>, checksum: &Checksum) -> VmResult
     Ok(wasm)
 }
 
+fn remove_wasm_from_disk(dir: impl Into<PathBuf>, checksum: &Checksum) -> VmResult<()> {
+    let path = dir.into().join(checksum.to_hex());
+    match remove_file(path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(VmError::cache_err(format!(
+            "Error deleting Wasm file: {}",
+            e
+        ))),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::calls::{call_execute, call_instantiate};
     use crate::errors::VmError;
     use crate::features::features_from_csv;
+    use crate::static_analysis::REQUIRED_IBC_EXPORTS;
     use crate::testing::{mock_backend, mock_env, mock_info, MockApi, MockQuerier, MockStorage};
+    use crate::wasm_backend::FLOATS_FEATURE;
     use cosmwasm_std::{coins, Empty};
     use std::fs::OpenOptions;
     use std::io::Write;
@@ -382,11 +732,13 @@ mod tests {
     const TESTING_OPTIONS: InstanceOptions = InstanceOptions {
         gas_limit: TESTING_GAS_LIMIT,
         print_debug: false,
+        gas_config: None,
     };
     const TESTING_MEMORY_CACHE_SIZE: Size = Size::mebi(200);
 
     static CONTRACT: &[u8] = include_bytes!("../testdata/hackatom.wasm");
     static IBC_CONTRACT: &[u8] = include_bytes!("../testdata/ibc_reflect.wasm");
+    static FLOATY_CONTRACT: &[u8] = include_bytes!("../testdata/floaty.wasm");
 
     fn default_features() -> HashSet<String> {
         features_from_csv("iterator,staking")
@@ -397,7 +749,12 @@ mod tests {
             base_dir: TempDir::new().unwrap().into_path(),
             supported_features: default_features(),
             memory_cache_size: TESTING_MEMORY_CACHE_SIZE,
+            fs_cache_size: None,
+            fs_cache_hmac_key: None,
             instance_memory_limit: TESTING_MEMORY_LIMIT,
+            gas_config: GasConfig::default(),
+            gatekeeper_policy: GatekeeperPolicy::default(),
+            wasm_limits: WasmLimits::default(),
         }
     }
 
@@ -408,7 +765,12 @@ mod tests {
             base_dir: TempDir::new().unwrap().into_path(),
             supported_features: feature,
             memory_cache_size: TESTING_MEMORY_CACHE_SIZE,
+            fs_cache_size: None,
+            fs_cache_hmac_key: None,
             instance_memory_limit: TESTING_MEMORY_LIMIT,
+            gas_config: GasConfig::default(),
+            gatekeeper_policy: GatekeeperPolicy::default(),
+            wasm_limits: WasmLimits::default(),
         }
     }
 
@@ -453,6 +815,89 @@ mod tests {
         }
     }
 
+    #[test]
+    fn save_wasm_applies_gatekeeper_policy() {
+        // A minimal contract using memory.copy from the bulk memory operations
+        let wasm = wat::parse_str(
+            r#"(module
+            (memory 3)
+            (export "memory" (memory 0))
+            (func (export "interface_version_8"))
+            (func (export "allocate") (param i32) (result i32) i32.const 0)
+            (func (export "deallocate") (param i32))
+            (func (export "instantiate") (param i32 i32 i32) (result i32)
+              local.get 0
+              local.get 1
+              local.get 2
+              memory.copy
+              i32.const 0))
+            "#,
+        )
+        .unwrap();
+
+        let cache: Cache<MockApi, MockStorage, MockQuerier> =
+            unsafe { Cache::new(make_testing_options()).unwrap() };
+        let err = cache.save_wasm(&wasm).unwrap_err();
+        assert!(err.to_string().contains("Bulk memory operation detected"));
+
+        let options = CacheOptions {
+            gatekeeper_policy: GatekeeperPolicy::preset("bulk-memory").unwrap(),
+            ..make_testing_options()
+        };
+        let modules_dir = options.base_dir.join(CACHE_DIR).join(MODULES_DIR);
+        let cache: Cache<MockApi, MockStorage, MockQuerier> =
+            unsafe { Cache::new(options).unwrap() };
+        let checksum = cache.save_wasm(&wasm).unwrap();
+        assert!(modules_dir
+            .join("v4-wasmer1-bulk-memory")
+            .join(checksum.to_hex())
+            .is_file());
+    }
+
+    #[test]
+    fn save_wasm_allows_floats_with_floats_capability() {
+        let cache: Cache<MockApi, MockStorage, MockQuerier> =
+            unsafe { Cache::new(make_testing_options()).unwrap() };
+        let err = cache.save_wasm(FLOATY_CONTRACT).unwrap_err();
+        assert!(err.to_string().contains("Float operator detected"));
+
+        let mut options = make_testing_options();
+        options
+            .supported_features
+            .insert(FLOATS_FEATURE.to_string());
+        let modules_dir = options.base_dir.join(CACHE_DIR).join(MODULES_DIR);
+        let cache: Cache<MockApi, MockStorage, MockQuerier> =
+            unsafe { Cache::new(options).unwrap() };
+        let checksum = cache.save_wasm(FLOATY_CONTRACT).unwrap();
+        assert!(modules_dir
+            .join("v4-wasmer1-floats")
+            .join(checksum.to_hex())
+            .is_file());
+        let backend = mock_backend(&[]);
+        cache
+            .get_instance(&checksum, backend, TESTING_OPTIONS)
+            .unwrap();
+    }
+
+    #[test]
+    fn save_wasm_applies_wasm_limits() {
+        let options = CacheOptions {
+            wasm_limits: WasmLimits {
+                max_functions: 10,
+                ..WasmLimits::default()
+            },
+            ..make_testing_options()
+        };
+        let cache: Cache<MockApi, MockStorage, MockQuerier> =
+            unsafe { Cache::new(options).unwrap() };
+        match cache.save_wasm(CONTRACT).unwrap_err() {
+            VmError::StaticValidationErr { msg, .. } => {
+                assert!(msg.ends_with("functions, which exceeds the limit of 10."))
+            }
+            e => panic!("Unexpected error {:?}", e),
+        }
+    }
+
     #[test]
     fn save_wasm_fills_file_system_but_not_memory_cache() {
         // Who knows if and when the uploaded contract will be executed. Don't pollute
@@ -491,7 +936,12 @@ mod tests {
                 base_dir: tmp_dir.path().to_path_buf(),
                 supported_features: default_features(),
                 memory_cache_size: TESTING_MEMORY_CACHE_SIZE,
+                fs_cache_size: None,
+                fs_cache_hmac_key: None,
                 instance_memory_limit: TESTING_MEMORY_LIMIT,
+                gas_config: GasConfig::default(),
+                gatekeeper_policy: GatekeeperPolicy::default(),
+                wasm_limits: WasmLimits::default(),
             };
             let cache1: Cache<MockApi, MockStorage, MockQuerier> =
                 unsafe { Cache::new(options1).unwrap() };
@@ -503,7 +953,12 @@ mod tests {
                 base_dir: tmp_dir.path().to_path_buf(),
                 supported_features: default_features(),
                 memory_cache_size: TESTING_MEMORY_CACHE_SIZE,
+                fs_cache_size: None,
+                fs_cache_hmac_key: None,
                 instance_memory_limit: TESTING_MEMORY_LIMIT,
+                gas_config: GasConfig::default(),
+                gatekeeper_policy: GatekeeperPolicy::default(),
+                wasm_limits: WasmLimits::default(),
             };
             let cache2: Cache<MockApi, MockStorage, MockQuerier> =
                 unsafe { Cache::new(options2).unwrap() };
@@ -537,7 +992,12 @@ mod tests {
             base_dir: tmp_dir.path().to_path_buf(),
             supported_features: default_features(),
             memory_cache_size: TESTING_MEMORY_CACHE_SIZE,
+            fs_cache_size: None,
+            fs_cache_hmac_key: None,
             instance_memory_limit: TESTING_MEMORY_LIMIT,
+            gas_config: GasConfig::default(),
+            gatekeeper_policy: GatekeeperPolicy::default(),
+            wasm_limits: WasmLimits::default(),
         };
         let cache: Cache<MockApi, MockStorage, MockQuerier> =
             unsafe { Cache::new(options).unwrap() };
@@ -897,6 +1357,7 @@ mod tests {
         let options = InstanceOptions {
             gas_limit: 10,
             print_debug: false,
+            gas_config: None,
         };
         let mut instance1 = cache.get_instance(&checksum, backend1, options).unwrap();
         assert_eq!(cache.stats().hits_fs_cache, 1);
@@ -917,6 +1378,7 @@ mod tests {
         let options = InstanceOptions {
             gas_limit: TESTING_GAS_LIMIT,
             print_debug: false,
+            gas_config: None,
         };
         let mut instance2 = cache.get_instance(&checksum, backend2, options).unwrap();
         assert_eq!(cache.stats().hits_pinned_memory_cache, 0);
@@ -982,27 +1444,39 @@ mod tests {
 
         let checksum1 = cache.save_wasm(CONTRACT).unwrap();
         let report1 = cache.analyze(&checksum1).unwrap();
+        assert!(!report1.has_ibc_entry_points);
+        assert_eq!(report1.required_features, HashSet::new());
         assert_eq!(
-            report1,
-            AnalysisReport {
-                has_ibc_entry_points: false,
-                required_features: HashSet::new(),
-            }
+            report1.entry_points,
+            HashSet::from_iter(
+                ["instantiate", "execute", "migrate", "query", "sudo"]
+                    .iter()
+                    .map(|name| name.to_string())
+            )
         );
+        assert!(report1.imports.contains("env.db_read"));
+        assert!(report1.imports.iter().all(|name| name.starts_with("env.")));
+        assert_eq!(report1.interface_version, Some(8));
+        assert_eq!(report1.memory_limits.unwrap().maximum, None);
+        assert!(report1.function_count > 0);
+        assert_eq!(report1.code_size, CONTRACT.len());
+        assert!(!report1.has_floats);
 
         let checksum2 = cache.save_wasm(IBC_CONTRACT).unwrap();
         let report2 = cache.analyze(&checksum2).unwrap();
+        assert!(report2.has_ibc_entry_points);
         assert_eq!(
-            report2,
-            AnalysisReport {
-                has_ibc_entry_points: true,
-                required_features: HashSet::from_iter(vec![
-                    "iterator".to_string(),
-                    "staking".to_string(),
-                    "stargate".to_string()
-                ]),
-            }
+            report2.required_features,
+            HashSet::from_iter(vec![
+                "iterator".to_string(),
+                "staking".to_string(),
+                "stargate".to_string()
+            ])
         );
+        for ibc_export in REQUIRED_IBC_EXPORTS {
+            assert!(report2.entry_points.contains(*ibc_export));
+        }
+        assert_eq!(report2.code_size, IBC_CONTRACT.len());
     }
 
     #[test]
@@ -1064,4 +1538,300 @@ mod tests {
         let non_id = Checksum::generate(b"non_existent");
         cache.unpin(&non_id).unwrap();
     }
+
+    #[test]
+    fn remove_wasm_works() {
+        let cache: Cache<MockApi, MockStorage, MockQuerier> =
+            unsafe { Cache::new(make_testing_options()).unwrap() };
+        let checksum = cache.save_wasm(CONTRACT).unwrap();
+        cache.pin(&checksum).unwrap();
+        assert_eq!(cache.metrics().elements_pinned_memory_cache, 1);
+        assert_eq!(cache.metrics().elements_fs_cache, 1);
+
+        cache.remove_wasm(&checksum).unwrap();
+        let metrics = cache.metrics();
+        assert_eq!(metrics.elements_pinned_memory_cache, 0);
+        assert_eq!(metrics.elements_memory_cache, 0);
+        assert_eq!(metrics.elements_fs_cache, 0);
+        assert_eq!(metrics.size_fs_cache, 0);
+
+        match cache.load_wasm(&checksum).unwrap_err() {
+            VmError::CacheErr { msg, .. } => {
+                assert!(msg.starts_with("Error opening Wasm file for reading"))
+            }
+            e => panic!("Unexpected error: {:?}", e),
+        }
+        let backend = mock_backend(&[]);
+        assert!(cache
+            .get_instance(&checksum, backend, TESTING_OPTIONS)
+            .is_err());
+
+        // removing again has no effect
+        cache.remove_wasm(&checksum).unwrap();
+    }
+
+    #[test]
+    fn remove_stale_modules_works() {
+        let tmp_dir = TempDir::new().unwrap();
+        let options = CacheOptions {
+            base_dir: tmp_dir.path().to_path_buf(),
+            supported_features: default_features(),
+            memory_cache_size: TESTING_MEMORY_CACHE_SIZE,
+            fs_cache_size: None,
+            fs_cache_hmac_key: None,
+            instance_memory_limit: TESTING_MEMORY_LIMIT,
+            gas_config: GasConfig::default(),
+            gatekeeper_policy: GatekeeperPolicy::default(),
+            wasm_limits: WasmLimits::default(),
+        };
+        let cache: Cache<MockApi, MockStorage, MockQuerier> =
+            unsafe { Cache::new(options).unwrap() };
+        let checksum = cache.save_wasm(CONTRACT).unwrap();
+
+        let stale = tmp_dir
+            .path()
+            .join(CACHE_DIR)
+            .join(MODULES_DIR)
+            .join("v2-wasmer1");
+        create_dir_all(&stale).unwrap();
+        assert_eq!(cache.remove_stale_modules().unwrap(), 1);
+        assert!(!stale.exists());
+
+        // modules of the current version are kept
+        let backend = mock_backend(&[]);
+        let _instance = cache
+            .get_instance(&checksum, backend, TESTING_OPTIONS)
+            .unwrap();
+        assert_eq!(cache.stats().hits_fs_cache, 1);
+        assert_eq!(cache.stats().misses, 0);
+    }
+
+    #[test]
+    fn get_instance_recompiles_corrupted_module() {
+        let tmp_dir = TempDir::new().unwrap();
+        let options = CacheOptions {
+            base_dir: tmp_dir.path().to_path_buf(),
+            supported_features: default_features(),
+            memory_cache_size: TESTING_MEMORY_CACHE_SIZE,
+            fs_cache_size: None,
+            fs_cache_hmac_key: Some(b"node key".to_vec()),
+            instance_memory_limit: TESTING_MEMORY_LIMIT,
+            gas_config: GasConfig::default(),
+            gatekeeper_policy: GatekeeperPolicy::default(),
+            wasm_limits: WasmLimits::default(),
+        };
+        let cache: Cache<MockApi, MockStorage, MockQuerier> =
+            unsafe { Cache::new(options.clone()).unwrap() };
+        let checksum = cache.save_wasm(CONTRACT).unwrap();
+
+        // Corrupt module file
+        let modules_dir = tmp_dir.path().join(CACHE_DIR).join(MODULES_DIR);
+        let version_dir = modules_dir
+            .read_dir()
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path();
+        let mut file = OpenOptions::new()
+            .write(true)
+            .open(version_dir.join(checksum.to_hex()))
+            .unwrap();
+        file.write_all(b"broken data").unwrap();
+
+        let backend = mock_backend(&[]);
+        let _instance = cache
+            .get_instance(&checksum, backend, TESTING_OPTIONS)
+            .unwrap();
+        assert_eq!(cache.stats().hits_fs_cache, 0);
+        assert_eq!(cache.stats().corrupted_fs_cache, 1);
+        assert_eq!(cache.stats().misses, 1);
+
+        // the recompiled module was stored again
+        let cache: Cache<MockApi, MockStorage, MockQuerier> =
+            unsafe { Cache::new(options).unwrap() };
+        let backend = mock_backend(&[]);
+        let _instance = cache
+            .get_instance(&checksum, backend, TESTING_OPTIONS)
+            .unwrap();
+        assert_eq!(cache.stats().hits_fs_cache, 1);
+        assert_eq!(cache.stats().corrupted_fs_cache, 0);
+    }
+
+    /// Returns the path of a module in the file system cache of the given base dir
+    fn fs_cache_module_path(base_dir: &Path, checksum: &Checksum) -> PathBuf {
+        let modules_dir = base_dir.join(CACHE_DIR).join(MODULES_DIR);
+        let version_dir = modules_dir
+            .read_dir()
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path();
+        version_dir.join(checksum.to_hex())
+    }
+
+    #[test]
+    fn precompile_dir_works() {
+        let options = make_stargate_testing_options();
+        let cache: Cache<MockApi, MockStorage, MockQuerier> =
+            unsafe { Cache::new(options).unwrap() };
+
+        let wasm_dir = TempDir::new().unwrap();
+        fs::write(wasm_dir.path().join("b_hackatom.wasm"), CONTRACT).unwrap();
+        fs::write(wasm_dir.path().join("a_ibc_reflect.wasm"), IBC_CONTRACT).unwrap();
+        // ignored
+        fs::write(wasm_dir.path().join("README.md"), b"not a contract").unwrap();
+        fs::create_dir(wasm_dir.path().join("nested.wasm")).unwrap();
+
+        let checksums = cache.precompile_dir(wasm_dir.path()).unwrap();
+        assert_eq!(
+            checksums,
+            vec![
+                Checksum::generate(IBC_CONTRACT),
+                Checksum::generate(CONTRACT)
+            ]
+        );
+        assert_eq!(cache.metrics().elements_fs_cache, 2);
+
+        let mut expected = checksums;
+        expected.sort_by_key(|checksum| checksum.to_hex());
+        assert_eq!(cache.checksums().unwrap(), expected);
+    }
+
+    #[test]
+    fn precompile_dir_fails_for_invalid_contract() {
+        let cache: Cache<MockApi, MockStorage, MockQuerier> =
+            unsafe { Cache::new(make_testing_options()).unwrap() };
+
+        let wasm_dir = TempDir::new().unwrap();
+        fs::write(wasm_dir.path().join("hackatom.wasm"), CONTRACT).unwrap();
+        // requires stargate
+        fs::write(wasm_dir.path().join("ibc_reflect.wasm"), IBC_CONTRACT).unwrap();
+
+        match cache.precompile_dir(wasm_dir.path()).unwrap_err() {
+            VmError::StaticValidationErr { .. } => {}
+            e => panic!("Unexpected error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn verify_works() {
+        let options = make_testing_options();
+        let base_dir = options.base_dir.clone();
+        let cache: Cache<MockApi, MockStorage, MockQuerier> =
+            unsafe { Cache::new(options).unwrap() };
+        let checksum = cache.save_wasm(CONTRACT).unwrap();
+        assert!(cache.verify(&checksum).unwrap());
+
+        // corrupted module
+        let module_path = fs_cache_module_path(&base_dir, &checksum);
+        fs::write(&module_path, b"broken data").unwrap();
+        match cache.verify(&checksum).unwrap_err() {
+            VmError::IntegrityErr { .. } => {}
+            e => panic!("Unexpected error: {:?}", e),
+        }
+
+        // missing module
+        fs::remove_file(&module_path).unwrap();
+        assert!(!cache.verify(&checksum).unwrap());
+
+        // corrupted Wasm
+        let wasm_path = base_dir
+            .join(STATE_DIR)
+            .join(WASM_DIR)
+            .join(checksum.to_hex());
+        fs::write(&wasm_path, b"broken data").unwrap();
+        match cache.verify(&checksum).unwrap_err() {
+            VmError::IntegrityErr { .. } => {}
+            e => panic!("Unexpected error: {:?}", e),
+        }
+
+        // missing Wasm
+        let unknown = Checksum::generate(b"unknown");
+        match cache.verify(&unknown).unwrap_err() {
+            VmError::CacheErr { msg, .. } => {
+                assert!(msg.starts_with("Error opening Wasm file for reading"))
+            }
+            e => panic!("Unexpected error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn export_import_works() {
+        let options = make_stargate_testing_options();
+        let base_dir = options.base_dir.clone();
+        let source: Cache<MockApi, MockStorage, MockQuerier> =
+            unsafe { Cache::new(options).unwrap() };
+        let checksum1 = source.save_wasm(CONTRACT).unwrap();
+        let checksum2 = source.save_wasm(IBC_CONTRACT).unwrap();
+        // modules that are not compiled are compiled on export
+        fs::remove_file(fs_cache_module_path(&base_dir, &checksum2)).unwrap();
+
+        let export_dir = TempDir::new().unwrap();
+        source
+            .export(&[checksum1, checksum2], export_dir.path())
+            .unwrap();
+        assert!(source.verify(&checksum2).unwrap());
+        assert!(export_dir.path().join("manifest.json").is_file());
+
+        let mut target_options = make_stargate_testing_options();
+        target_options.fs_cache_hmac_key = Some(b"target key".to_vec());
+        let target: Cache<MockApi, MockStorage, MockQuerier> =
+            unsafe { Cache::new(target_options).unwrap() };
+        let imported = unsafe { target.import(export_dir.path()).unwrap() };
+        assert_eq!(imported, vec![checksum1, checksum2]);
+        assert!(target.verify(&checksum1).unwrap());
+        assert!(target.verify(&checksum2).unwrap());
+        assert_eq!(target.load_wasm(&checksum1).unwrap(), CONTRACT);
+
+        // imported modules are used without compiling
+        let backend = mock_backend(&[]);
+        let _instance = target
+            .get_instance(&checksum1, backend, TESTING_OPTIONS)
+            .unwrap();
+        assert_eq!(target.stats().hits_fs_cache, 1);
+        assert_eq!(target.stats().misses, 0);
+    }
+
+    #[test]
+    fn import_rejects_tampered_exports() {
+        let source: Cache<MockApi, MockStorage, MockQuerier> =
+            unsafe { Cache::new(make_testing_options()).unwrap() };
+        let checksum = source.save_wasm(CONTRACT).unwrap();
+        let export_dir = TempDir::new().unwrap();
+        source.export(&[checksum], export_dir.path()).unwrap();
+
+        let target: Cache<MockApi, MockStorage, MockQuerier> =
+            unsafe { Cache::new(make_testing_options()).unwrap() };
+
+        // tampered module
+        let module_path = export_dir.path().join(MODULES_DIR).join(checksum.to_hex());
+        let mut artifact = fs::read(&module_path).unwrap();
+        let last = artifact.len() - 1;
+        artifact[last] ^= 0x01;
+        fs::write(&module_path, &artifact).unwrap();
+        match unsafe { target.import(export_dir.path()) }.unwrap_err() {
+            VmError::IntegrityErr { .. } => {}
+            e => panic!("Unexpected error: {:?}", e),
+        }
+        assert_eq!(target.checksums().unwrap(), vec![]);
+
+        // other module version
+        source.export(&[checksum], export_dir.path()).unwrap();
+        let manifest_path = export_dir.path().join(EXPORT_MANIFEST);
+        let mut manifest: ExportManifest = from_slice(
+            &fs::read(&manifest_path).unwrap(),
+            MAX_EXPORT_MANIFEST_LENGTH,
+        )
+        .unwrap();
+        manifest.module_version = "v2-wasmer1".to_string();
+        fs::write(&manifest_path, to_vec(&manifest).unwrap()).unwrap();
+        match unsafe { target.import(export_dir.path()) }.unwrap_err() {
+            VmError::CacheErr { msg, .. } => {
+                assert!(msg.starts_with("Export contains modules of version v2-wasmer1"))
+            }
+            e => panic!("Unexpected error: {:?}", e),
+        }
+    }
 }
@@ -23,6 +23,13 @@ impl Checksum {
     pub fn to_hex(self) -> String {
         self.to_string()
     }
+
+    /// Parses a hex encoded checksum, as created by [`Checksum::to_hex`].
+    pub fn from_hex(input: &str) -> Result<Self, VmError> {
+        let data = hex::decode(input)
+            .map_err(|e| VmError::cache_err(format!("Invalid checksum hex: {}", e)))?;
+        Checksum::try_from(data.as_slice())
+    }
 }
 
 impl fmt::Display for Checksum {
@@ -104,6 +111,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn from_hex_works() {
+        let checksum = Checksum::generate(&[0x68, 0x69, 0x6a]);
+        assert_eq!(Checksum::from_hex(&checksum.to_hex()).unwrap(), checksum);
+        assert_eq!(
+            Checksum::from_hex("722C8C993FD75A7627D69ED941344FE2A1423A3E75EFD3E6778A142884227104")
+                .unwrap(),
+            checksum
+        );
+
+        match Checksum::from_hex("722c8c").unwrap_err() {
+            VmError::CacheErr { msg, .. } => assert_eq!(msg, "Checksum not of length 32"),
+            e => panic!("Unexpected error: {:?}", e),
+        }
+        match Checksum::from_hex("not hex").unwrap_err() {
+            VmError::CacheErr { msg, .. } => assert!(msg.starts_with("Invalid checksum hex")),
+            e => panic!("Unexpected error: {:?}", e),
+        }
+    }
+
     #[test]
     fn into_vec_works() {
         let checksum = Checksum::generate(&[12u8; 17]);
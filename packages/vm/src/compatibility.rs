@@ -1,13 +1,16 @@
-use parity_wasm::elements::{External, ImportEntry, Module};
+use parity_wasm::elements::{External, ImportEntry, Module, Type};
 use std::collections::BTreeSet;
 use std::collections::HashSet;
 
 use crate::errors::{VmError, VmResult};
 use crate::features::required_features_from_module;
 use crate::limited::LimitedDisplay;
-use crate::static_analysis::{deserialize_wasm, ExportInfo};
+use crate::static_analysis::{
+    deserialize_wasm, full_import_name, ExportInfo, INTERFACE_VERSION_PREFIX,
+};
 
-/// Lists all imports we provide upon instantiating the instance in Instance::from_module()
+/// Lists all imports we provide upon instantiating the instance in Instance::from_module(),
+/// except for the ones in [`CAPABILITY_IMPORTS`].
 /// This should be updated when new imports are added
 const SUPPORTED_IMPORTS: &[&str] = &[
     "env.abort",
@@ -29,6 +32,18 @@ const SUPPORTED_IMPORTS: &[&str] = &[
     "env.db_next",
 ];
 
+/// Lists imports we provide to contracts only if the chain supports the capability next to them.
+/// A contract gets the capability in its required features when it uses the import via cosmwasm-std.
+const CAPABILITY_IMPORTS: &[(&str, &str)] = &[
+    ("env.secp256r1_verify", "secp256r1"),
+    ("env.ethereum_recover_address", "ethereum"),
+    ("env.sr25519_verify", "sr25519"),
+    ("env.bls12_381_aggregate_verify", "bls12_381"),
+    ("env.sha256", "hashes"),
+    ("env.keccak256", "hashes"),
+    ("env.blake2b_256", "hashes"),
+];
+
 /// Lists all entry points we expect to be present when calling a contract.
 /// Other optional exports exist, e.g. "execute", "migrate" and "query".
 /// The marker export interface_version_* is checked separately.
@@ -41,7 +56,6 @@ const REQUIRED_EXPORTS: &[&str] = &[
     "instantiate",
 ];
 
-const INTERFACE_VERSION_PREFIX: &str = "interface_version_";
 const SUPPORTED_INTERFACE_VERSIONS: &[&str] = &[
     "interface_version_8",
     #[cfg(feature = "allow_interface_version_7")]
@@ -50,14 +64,149 @@ const SUPPORTED_INTERFACE_VERSIONS: &[&str] = &[
 
 const MEMORY_LIMIT: u32 = 512; // in pages
 
+/// Limits on the complexity of a contract. Those protect the node from contracts that
+/// are expensive to compile, which happens before any gas can be charged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WasmLimits {
+    /// Maximum size of the Wasm bytecode in bytes
+    pub max_code_size: usize,
+    /// Maximum number of functions defined by the contract, not counting imports
+    pub max_functions: usize,
+    /// Maximum number of parameters of a function type
+    pub max_function_params: usize,
+    /// Maximum number of locals declared in a function body, not counting parameters
+    pub max_function_locals: u64,
+    /// Maximum initial and maximum size of a table, in elements
+    pub max_table_size: u32,
+    /// Maximum number of globals defined by the contract
+    pub max_globals: usize,
+}
+
+impl Default for WasmLimits {
+    /// Limits that are far above the values of typical contracts
+    fn default() -> Self {
+        Self {
+            max_code_size: 3 * 1024 * 1024,
+            max_functions: 20_000,
+            max_function_params: 100,
+            max_function_locals: 10_000,
+            max_table_size: 2_500,
+            max_globals: 256,
+        }
+    }
+}
+
 /// Checks if the data is valid wasm and compatibility with the CosmWasm API (imports and exports)
+/// using the default [`WasmLimits`].
 pub fn check_wasm(wasm_code: &[u8], supported_features: &HashSet<String>) -> VmResult<()> {
+    check_wasm_with_limits(wasm_code, supported_features, &WasmLimits::default())
+}
+
+/// Like [`check_wasm`] but with custom limits on the complexity of the contract
+pub fn check_wasm_with_limits(
+    wasm_code: &[u8],
+    supported_features: &HashSet<String>,
+    limits: &WasmLimits,
+) -> VmResult<()> {
+    // Checked before deserialization, which is already proportional to the size
+    if wasm_code.len() > limits.max_code_size {
+        return Err(VmError::static_validation_err(format!(
+            "Wasm contract size of {} bytes exceeds the limit of {} bytes.",
+            wasm_code.len(),
+            limits.max_code_size
+        )));
+    }
     let module = deserialize_wasm(wasm_code)?;
+    check_wasm_complexity(&module, limits)?;
     check_wasm_memories(&module)?;
     check_interface_version(&module)?;
     check_wasm_exports(&module)?;
-    check_wasm_imports(&module, SUPPORTED_IMPORTS)?;
+    // Checked before the imports to explain why capability imports are unsupported
     check_wasm_features(&module, supported_features)?;
+    check_wasm_imports(&module, &supported_imports(supported_features))?;
+    Ok(())
+}
+
+/// Returns [`SUPPORTED_IMPORTS`] plus the [`CAPABILITY_IMPORTS`] whose capability is supported
+fn supported_imports(supported_features: &HashSet<String>) -> Vec<&'static str> {
+    let capability_imports = CAPABILITY_IMPORTS
+        .iter()
+        .filter(|(_, capability)| supported_features.contains(*capability))
+        .map(|(import, _)| *import);
+    SUPPORTED_IMPORTS
+        .iter()
+        .copied()
+        .chain(capability_imports)
+        .collect()
+}
+
+fn check_wasm_complexity(module: &Module, limits: &WasmLimits) -> VmResult<()> {
+    let function_count = module
+        .function_section()
+        .map_or(0, |section| section.entries().len());
+    if function_count > limits.max_functions {
+        return Err(VmError::static_validation_err(format!(
+            "Wasm contract defines {} functions, which exceeds the limit of {}.",
+            function_count, limits.max_functions
+        )));
+    }
+
+    if let Some(section) = module.type_section() {
+        for (index, ty) in section.types().iter().enumerate() {
+            let Type::Function(func) = ty;
+            if func.params().len() > limits.max_function_params {
+                return Err(VmError::static_validation_err(format!(
+                    "Wasm contract function type {} has {} parameters, which exceeds the limit of {}.",
+                    index,
+                    func.params().len(),
+                    limits.max_function_params
+                )));
+            }
+        }
+    }
+
+    if let Some(section) = module.code_section() {
+        for (index, body) in section.bodies().iter().enumerate() {
+            let locals: u64 = body
+                .locals()
+                .iter()
+                .map(|local| u64::from(local.count()))
+                .sum();
+            if locals > limits.max_function_locals {
+                return Err(VmError::static_validation_err(format!(
+                    "Wasm contract function {} declares {} locals, which exceeds the limit of {}.",
+                    index, locals, limits.max_function_locals
+                )));
+            }
+        }
+    }
+
+    if let Some(section) = module.table_section() {
+        for table in section.entries() {
+            let table_limits = table.limits();
+            let size = std::cmp::max(
+                table_limits.initial(),
+                table_limits.maximum().unwrap_or_default(),
+            );
+            if size > limits.max_table_size {
+                return Err(VmError::static_validation_err(format!(
+                    "Wasm contract table size of {} elements exceeds the limit of {}.",
+                    size, limits.max_table_size
+                )));
+            }
+        }
+    }
+
+    let global_count = module
+        .global_section()
+        .map_or(0, |section| section.entries().len());
+    if global_count > limits.max_globals {
+        return Err(VmError::static_validation_err(format!(
+            "Wasm contract defines {} globals, which exceeds the limit of {}.",
+            global_count, limits.max_globals
+        )));
+    }
+
     Ok(())
 }
 
@@ -170,10 +319,6 @@ fn check_wasm_imports(module: &Module, supported_imports: &[&str]) -> VmResult<(
     Ok(())
 }
 
-fn full_import_name(ie: &ImportEntry) -> String {
-    format!("{}.{}", ie.module(), ie.field())
-}
-
 fn check_wasm_features(module: &Module, supported_features: &HashSet<String>) -> VmResult<()> {
     let required_features = required_features_from_module(module);
     if !required_features.is_subset(supported_features) {
@@ -191,6 +336,7 @@ fn check_wasm_features(module: &Module, supported_features: &HashSet<String>) ->
 mod tests {
     use super::*;
     use crate::errors::VmError;
+    use crate::features::features_from_csv;
 
     static CONTRACT_0_7: &[u8] = include_bytes!("../testdata/hackatom_0.7.wasm");
     static CONTRACT_0_12: &[u8] = include_bytes!("../testdata/hackatom_0.12.wasm");
@@ -247,6 +393,140 @@ mod tests {
         };
     }
 
+    #[test]
+    fn check_wasm_with_limits_checks_code_size() {
+        let limits = WasmLimits {
+            max_code_size: 1000,
+            ..WasmLimits::default()
+        };
+        match check_wasm_with_limits(CONTRACT, &default_features(), &limits) {
+            Err(VmError::StaticValidationErr { msg, .. }) => assert_eq!(
+                msg,
+                format!(
+                    "Wasm contract size of {} bytes exceeds the limit of 1000 bytes.",
+                    CONTRACT.len()
+                )
+            ),
+            Err(e) => panic!("Unexpected error {:?}", e),
+            Ok(_) => panic!("Didn't reject wasm exceeding the size limit"),
+        }
+    }
+
+    #[test]
+    fn check_wasm_complexity_ok() {
+        let module = deserialize_wasm(CONTRACT).unwrap();
+        check_wasm_complexity(&module, &WasmLimits::default()).unwrap();
+    }
+
+    #[test]
+    fn check_wasm_complexity_functions() {
+        let wasm = wat::parse_str("(module (func) (func) (func))").unwrap();
+        let module = deserialize_wasm(&wasm).unwrap();
+        let limits = WasmLimits {
+            max_functions: 3,
+            ..WasmLimits::default()
+        };
+        check_wasm_complexity(&module, &limits).unwrap();
+
+        let limits = WasmLimits {
+            max_functions: 2,
+            ..WasmLimits::default()
+        };
+        match check_wasm_complexity(&module, &limits).unwrap_err() {
+            VmError::StaticValidationErr { msg, .. } => assert_eq!(
+                msg,
+                "Wasm contract defines 3 functions, which exceeds the limit of 2."
+            ),
+            e => panic!("Unexpected error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn check_wasm_complexity_function_params() {
+        let wasm =
+            wat::parse_str("(module (type (func (param i32))) (type (func (param i32 i64 i32))))")
+                .unwrap();
+        let module = deserialize_wasm(&wasm).unwrap();
+        let limits = WasmLimits {
+            max_function_params: 2,
+            ..WasmLimits::default()
+        };
+        match check_wasm_complexity(&module, &limits).unwrap_err() {
+            VmError::StaticValidationErr { msg, .. } => assert_eq!(
+                msg,
+                "Wasm contract function type 1 has 3 parameters, which exceeds the limit of 2."
+            ),
+            e => panic!("Unexpected error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn check_wasm_complexity_function_locals() {
+        let wasm = wat::parse_str("(module (func (local i32)) (func (local i32 i64) (local i32)))")
+            .unwrap();
+        let module = deserialize_wasm(&wasm).unwrap();
+        let limits = WasmLimits {
+            max_function_locals: 2,
+            ..WasmLimits::default()
+        };
+        match check_wasm_complexity(&module, &limits).unwrap_err() {
+            VmError::StaticValidationErr { msg, .. } => assert_eq!(
+                msg,
+                "Wasm contract function 1 declares 3 locals, which exceeds the limit of 2."
+            ),
+            e => panic!("Unexpected error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn check_wasm_complexity_table_size() {
+        let limits = WasmLimits {
+            max_table_size: 100,
+            ..WasmLimits::default()
+        };
+
+        let wasm = wat::parse_str("(module (table 100 funcref))").unwrap();
+        check_wasm_complexity(&deserialize_wasm(&wasm).unwrap(), &limits).unwrap();
+
+        let wasm = wat::parse_str("(module (table 101 funcref))").unwrap();
+        match check_wasm_complexity(&deserialize_wasm(&wasm).unwrap(), &limits).unwrap_err() {
+            VmError::StaticValidationErr { msg, .. } => assert_eq!(
+                msg,
+                "Wasm contract table size of 101 elements exceeds the limit of 100."
+            ),
+            e => panic!("Unexpected error {:?}", e),
+        }
+
+        // maximum is checked too
+        let wasm = wat::parse_str("(module (table 10 200 funcref))").unwrap();
+        match check_wasm_complexity(&deserialize_wasm(&wasm).unwrap(), &limits).unwrap_err() {
+            VmError::StaticValidationErr { msg, .. } => assert_eq!(
+                msg,
+                "Wasm contract table size of 200 elements exceeds the limit of 100."
+            ),
+            e => panic!("Unexpected error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn check_wasm_complexity_globals() {
+        let wasm =
+            wat::parse_str("(module (global i32 (i32.const 1)) (global (mut i64) (i64.const 2)))")
+                .unwrap();
+        let module = deserialize_wasm(&wasm).unwrap();
+        let limits = WasmLimits {
+            max_globals: 1,
+            ..WasmLimits::default()
+        };
+        match check_wasm_complexity(&module, &limits).unwrap_err() {
+            VmError::StaticValidationErr { msg, .. } => assert_eq!(
+                msg,
+                "Wasm contract defines 2 globals, which exceeds the limit of 1."
+            ),
+            e => panic!("Unexpected error {:?}", e),
+        }
+    }
+
     #[test]
     fn check_wasm_memories_ok() {
         let wasm = wat::parse_str("(module (memory 1))").unwrap();
@@ -548,12 +828,52 @@ mod tests {
             (import "env" "addr_humanize" (func (param i32 i32) (result i32)))
             (import "env" "secp256k1_verify" (func (param i32 i32 i32) (result i32)))
             (import "env" "secp256k1_recover_pubkey" (func (param i32 i32 i32) (result i64)))
+            (import "env" "secp256r1_verify" (func (param i32 i32 i32) (result i32)))
+            (import "env" "ethereum_recover_address" (func (param i32 i32 i64) (result i64)))
             (import "env" "ed25519_verify" (func (param i32 i32 i32) (result i32)))
             (import "env" "ed25519_batch_verify" (func (param i32 i32 i32) (result i32)))
+            (import "env" "sr25519_verify" (func (param i32 i32 i32) (result i32)))
+            (import "env" "bls12_381_aggregate_verify" (func (param i32 i32 i32) (result i32)))
+            (import "env" "sha256" (func (param i32) (result i32)))
+            (import "env" "keccak256" (func (param i32) (result i32)))
+            (import "env" "blake2b_256" (func (param i32) (result i32)))
         )"#,
         )
         .unwrap();
-        check_wasm_imports(&deserialize_wasm(&wasm).unwrap(), SUPPORTED_IMPORTS).unwrap();
+        let supported_features = features_from_csv("secp256r1,ethereum,sr25519,bls12_381,hashes");
+        let supported_imports = supported_imports(&supported_features);
+        check_wasm_imports(&deserialize_wasm(&wasm).unwrap(), &supported_imports).unwrap();
+    }
+
+    #[test]
+    fn check_wasm_imports_requires_capability() {
+        // Does not export requires_sr25519, so this passes check_wasm_features
+        let wasm = wat::parse_str(
+            r#"(module
+            (import "env" "sr25519_verify" (func (param i32 i32 i32) (result i32)))
+            (type (func))
+            (func (type 0) nop)
+            (export "interface_version_8" (func 1))
+            (export "instantiate" (func 1))
+            (export "allocate" (func 1))
+            (export "deallocate" (func 1))
+            (memory 1)
+        )"#,
+        )
+        .unwrap();
+
+        let mut supported_features = default_features();
+        supported_features.insert("sr25519".to_string());
+        check_wasm(&wasm, &supported_features).unwrap();
+
+        match check_wasm(&wasm, &default_features()).unwrap_err() {
+            VmError::StaticValidationErr { msg, .. } => {
+                assert!(msg.starts_with(
+                    "Wasm contract requires unsupported import: \"env.sr25519_verify\""
+                ));
+            }
+            err => panic!("Unexpected error: {:?}", err),
+        }
     }
 
     #[test]
@@ -1,13 +1,17 @@
 //! Internal details to be used by instance.rs only
 use std::borrow::{Borrow, BorrowMut};
 use std::ptr::NonNull;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 
+use serde::{Deserialize, Serialize};
 use wasmer::{HostEnvInitError, Instance as WasmerInstance, Memory, Val, WasmerEnv};
 use wasmer_middlewares::metering::{get_remaining_points, set_remaining_points, MeteringPoints};
 
 use crate::backend::{BackendApi, GasInfo, Querier, Storage};
 use crate::errors::{VmError, VmResult};
+use crate::memory::region_length;
 
 /// Never can never be instantiated.
 /// Replace this with the [never primitive type](https://doc.rust-lang.org/std/primitive.never.html) when stable.
@@ -16,40 +20,171 @@ pub enum Never {}
 
 /** gas config data */
 
-#[derive(Clone, PartialEq, Debug)]
+/// Gas charged by the VM for a call to an import, on top of the gas reported by the backend.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub struct ImportGasCost {
+    /// Cost of every call
+    pub per_call: u64,
+    /// Cost of every byte passed between the contract and the import, in both directions
+    pub per_byte: u64,
+}
+
+impl ImportGasCost {
+    /// A cost of zero, for imports that are metered by the backend
+    pub const fn free() -> Self {
+        Self {
+            per_call: 0,
+            per_byte: 0,
+        }
+    }
+
+    pub fn total(&self, bytes: usize) -> u64 {
+        self.per_call
+            .saturating_add(self.per_byte.saturating_mul(bytes as u64))
+    }
+}
+
+/// The gas schedule of the VM.
+///
+/// The crypto imports are computed by the VM and always charged by the VM. Verifications of
+/// fixed size hashes cost a fixed amount per call, while those that hash messages of variable
+/// length are charged per byte of message as well. All other imports are charged the costs reported by the backend plus the `ImportGasCost`
+/// configured here, which is zero by default. Chains without a gas meter in the backend can
+/// use the latter to charge deterministically.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct GasConfig {
     /// Gas costs of VM (not Backend) provided functionality
     /// secp256k1 signature verification cost
     pub secp256k1_verify_cost: u64,
     /// secp256k1 public key recovery cost
     pub secp256k1_recover_pubkey_cost: u64,
-    /// ed25519 signature verification cost
-    pub ed25519_verify_cost: u64,
-    /// ed25519 batch signature verification cost
+    /// secp256r1 signature verification cost
+    pub secp256r1_verify_cost: u64,
+    /// Ethereum address recovery cost
+    pub ethereum_recover_address_cost: u64,
+    /// ed25519 signature verification cost, per byte of message
+    pub ed25519_verify_cost: ImportGasCost,
+    /// ed25519 batch signature verification cost, per signature.
+    /// The messages are charged with the per byte cost of `ed25519_verify_cost`.
     pub ed25519_batch_verify_cost: u64,
-    /// ed25519 batch signature verification cost (single public key)
+    /// ed25519 batch signature verification cost (single public key), per signature
     pub ed25519_batch_verify_one_pubkey_cost: u64,
+    /// BLS12-381 aggregate signature verification cost, per byte of all messages
+    pub bls12_381_aggregate_verify_cost: ImportGasCost,
+    /// BLS12-381 aggregate signature verification cost per message
+    pub bls12_381_aggregate_verify_per_message_cost: u64,
+    /// BLS12-381 aggregate signature verification cost per public key
+    pub bls12_381_aggregate_verify_per_pubkey_cost: u64,
+    /// sr25519 signature verification cost, per byte of message
+    pub sr25519_verify_cost: ImportGasCost,
+    /// Cost of `sha256`, per byte of input
+    pub sha256_cost: ImportGasCost,
+    /// Cost of `keccak256`, per byte of input
+    pub keccak256_cost: ImportGasCost,
+    /// Cost of `blake2b_256`, per byte of input
+    pub blake2b_256_cost: ImportGasCost,
+    /// Cost of `db_read`, per byte of key and value
+    pub db_read_cost: ImportGasCost,
+    /// Cost of `db_write`, per byte of key and value
+    pub db_write_cost: ImportGasCost,
+    /// Cost of `db_remove`, per byte of key
+    pub db_remove_cost: ImportGasCost,
+    /// Cost of `db_scan`, per byte of start and end
+    pub db_scan_cost: ImportGasCost,
+    /// Cost of `db_next`, per byte of key and value
+    pub db_next_cost: ImportGasCost,
+    /// Cost of `addr_validate`, per byte of input
+    pub addr_validate_cost: ImportGasCost,
+    /// Cost of `addr_canonicalize`, per byte of input
+    pub addr_canonicalize_cost: ImportGasCost,
+    /// Cost of `addr_humanize`, per byte of input
+    pub addr_humanize_cost: ImportGasCost,
+    /// Cost of `query_chain`, per byte of request and response
+    pub query_chain_cost: ImportGasCost,
+    /// Cost of `debug`, per byte of message
+    pub debug_cost: ImportGasCost,
+    /// Cost of `abort`, per byte of message
+    pub abort_cost: ImportGasCost,
 }
 
-impl Default for GasConfig {
-    fn default() -> Self {
+impl GasConfig {
+    /// The default gas schedule. Unlike `Default::default`, this can be used in a const context.
+    pub const fn new() -> Self {
         // Target is 10^12 per millisecond (see GAS.md), i.e. 10^9 gas per µ second.
         const GAS_PER_US: u64 = 1_000_000_000;
+        const GAS_PER_NS: u64 = GAS_PER_US / 1_000;
         Self {
             // ~154 us in crypto benchmarks
             secp256k1_verify_cost: 154 * GAS_PER_US,
             // ~162 us in crypto benchmarks
             secp256k1_recover_pubkey_cost: 162 * GAS_PER_US,
-            // ~63 us in crypto benchmarks
-            ed25519_verify_cost: 63 * GAS_PER_US,
+            // Comparable to secp256k1_verify (see secp256r1_verify in crypto benchmarks)
+            secp256r1_verify_cost: 154 * GAS_PER_US,
+            // Public key recovery plus a Keccak-256 hash of 64 bytes, which is negligible
+            ethereum_recover_address_cost: 162 * GAS_PER_US,
+            // ~63 us in crypto benchmarks, plus the SHA-512 hash of the message
+            ed25519_verify_cost: ImportGasCost {
+                per_call: 63 * GAS_PER_US,
+                per_byte: 4 * GAS_PER_NS,
+            },
             // Gas cost factors, relative to ed25519_verify cost
             // From https://docs.rs/ed25519-zebra/2.2.0/ed25519_zebra/batch/index.html
             ed25519_batch_verify_cost: 63 * GAS_PER_US / 2,
             ed25519_batch_verify_one_pubkey_cost: 63 * GAS_PER_US / 4,
+            // From the bls12_381_aggregate_verify crypto benchmarks: ~1.7 ms for the signature
+            // (decompression, Miller loop and final exponentiation), ~1.1 ms per message
+            // (hash to curve and Miller loop) and ~60 us per public key (decompression).
+            // Hashing the messages to the curve starts with a SHA-256 based expansion.
+            bls12_381_aggregate_verify_cost: ImportGasCost {
+                per_call: 1_700 * GAS_PER_US,
+                per_byte: 4 * GAS_PER_NS,
+            },
+            bls12_381_aggregate_verify_per_message_cost: 1_100 * GAS_PER_US,
+            bls12_381_aggregate_verify_per_pubkey_cost: 60 * GAS_PER_US,
+            // Comparable to ed25519_verify (see sr25519_verify in crypto benchmarks). The message
+            // is absorbed into a Merlin transcript.
+            sr25519_verify_cost: ImportGasCost {
+                per_call: 63 * GAS_PER_US,
+                per_byte: 4 * GAS_PER_NS,
+            },
+            // ~4 ns, ~3 ns and ~2 ns per byte in the 1 KiB crypto benchmarks, plus
+            // the overhead of the import call and the allocation of the result
+            sha256_cost: ImportGasCost {
+                per_call: GAS_PER_US,
+                per_byte: 4 * GAS_PER_NS,
+            },
+            keccak256_cost: ImportGasCost {
+                per_call: GAS_PER_US,
+                per_byte: 3 * GAS_PER_NS,
+            },
+            blake2b_256_cost: ImportGasCost {
+                per_call: GAS_PER_US,
+                per_byte: 2 * GAS_PER_NS,
+            },
+            // Metered by the backend
+            db_read_cost: ImportGasCost::free(),
+            db_write_cost: ImportGasCost::free(),
+            db_remove_cost: ImportGasCost::free(),
+            db_scan_cost: ImportGasCost::free(),
+            db_next_cost: ImportGasCost::free(),
+            addr_validate_cost: ImportGasCost::free(),
+            addr_canonicalize_cost: ImportGasCost::free(),
+            addr_humanize_cost: ImportGasCost::free(),
+            query_chain_cost: ImportGasCost::free(),
+            // Free since the output is never written to chain
+            debug_cost: ImportGasCost::free(),
+            abort_cost: ImportGasCost::free(),
         }
     }
 }
 
+impl Default for GasConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /** context data **/
 
 #[derive(Clone, PartialEq, Debug, Default)]
@@ -70,12 +205,32 @@ impl GasState {
     }
 }
 
+/// A single call from the contract to an import, as recorded in the import trace
+#[derive(Clone, PartialEq, Debug)]
+pub struct ImportCall {
+    /// The name of the import, e.g. "db_read"
+    pub name: &'static str,
+    /// The lengths of the Regions passed to the import, in argument order.
+    /// Unset optional Regions (like the bounds of `db_scan`) are reported as 0.
+    pub arg_sizes: Vec<usize>,
+    /// The amount of gas that was spend in this call and metered externally
+    pub used_externally: u64,
+    /// The amount of gas that was spend in this call and metered internally, i.e. the VM side
+    /// cost of the import plus Wasm executed on behalf of the import (e.g. `allocate`)
+    pub used_internally: u64,
+    /// The wall time spend in this call
+    pub duration: Duration,
+}
+
 /// A environment that provides access to the ContextData.
 /// The environment is clonable but clones access the same underlying data.
 pub struct Environment<A: BackendApi, S: Storage, Q: Querier> {
     pub api: A,
     pub print_debug: bool,
     pub gas_config: GasConfig,
+    /// Whether import calls are recorded. Kept outside of the context data such that
+    /// imports can check it without taking a lock.
+    import_tracing: Arc<AtomicBool>,
     data: Arc<RwLock<ContextData<S, Q>>>,
 }
 
@@ -88,7 +243,8 @@ impl<A: BackendApi, S: Storage, Q: Querier> Clone for Environment<A, S, Q> {
         Environment {
             api: self.api,
             print_debug: self.print_debug,
-            gas_config: self.gas_config.clone(),
+            gas_config: self.gas_config,
+            import_tracing: self.import_tracing.clone(),
             data: self.data.clone(),
         }
     }
@@ -106,6 +262,7 @@ impl<A: BackendApi, S: Storage, Q: Querier> Environment<A, S, Q> {
             api,
             print_debug,
             gas_config: GasConfig::default(),
+            import_tracing: Arc::new(AtomicBool::new(false)),
             data: Arc::new(RwLock::new(ContextData::new(gas_limit))),
         }
     }
@@ -292,6 +449,95 @@ impl<A: BackendApi, S: Storage, Q: Querier> Environment<A, S, Q> {
         .expect("Wasmer instance is not set. This is a bug in the lifecycle.")
     }
 
+    /// Enables or disables recording of import calls. Enabling starts a new, empty trace.
+    pub fn set_import_tracing(&self, enabled: bool) {
+        self.with_context_data_mut(|context_data| {
+            context_data.import_trace = if enabled { Some(Vec::new()) } else { None };
+        });
+        self.import_tracing.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Returns the import calls recorded since tracing was enabled or the trace was last taken.
+    /// Tracing stays enabled.
+    pub fn take_import_trace(&self) -> Vec<ImportCall> {
+        self.with_context_data_mut(|context_data| {
+            context_data
+                .import_trace
+                .as_mut()
+                .map(std::mem::take)
+                .unwrap_or_default()
+        })
+    }
+
+    /// Executes an import call and records it in the import trace if tracing is enabled.
+    /// `region_ptrs` are the pointers to the Regions passed to the import.
+    ///
+    /// Without tracing, this is a direct call of the import.
+    #[inline]
+    pub fn trace_import<C, R>(
+        &self,
+        name: &'static str,
+        region_ptrs: &[u32],
+        call: C,
+    ) -> VmResult<R>
+    where
+        C: FnOnce() -> VmResult<R>,
+    {
+        if self.import_tracing.load(Ordering::Relaxed) {
+            self.record_import_call(name, region_ptrs, call)
+        } else {
+            call()
+        }
+    }
+
+    fn record_import_call<C, R>(
+        &self,
+        name: &'static str,
+        region_ptrs: &[u32],
+        call: C,
+    ) -> VmResult<R>
+    where
+        C: FnOnce() -> VmResult<R>,
+    {
+        let memory = self.memory();
+        let arg_sizes = region_ptrs
+            .iter()
+            .map(|&ptr| {
+                if ptr == 0 {
+                    0
+                } else {
+                    // Invalid Regions are reported by the import itself
+                    region_length(&memory, ptr).unwrap_or(0)
+                }
+            })
+            .collect();
+        let gas_left_before = self.get_gas_left();
+        let externally_used_before = self.with_gas_state(|gas_state| gas_state.externally_used_gas);
+        let start = Instant::now();
+
+        let result = call();
+
+        let duration = start.elapsed();
+        let used = gas_left_before.saturating_sub(self.get_gas_left());
+        let used_externally = self
+            .with_gas_state(|gas_state| gas_state.externally_used_gas)
+            .saturating_sub(externally_used_before);
+        let record = ImportCall {
+            name,
+            arg_sizes,
+            used_externally,
+            used_internally: used.saturating_sub(used_externally),
+            duration,
+        };
+        self.with_context_data_mut(|context_data| {
+            // Tracing might have been disabled in the meantime
+            if let Some(trace) = context_data.import_trace.as_mut() {
+                trace.push(record);
+            }
+        });
+        result
+    }
+
     /// Moves owned instances of storage and querier into the env.
     /// Should be followed by exactly one call to move_out when the instance is finished.
     pub fn move_in(&self, storage: S, querier: Q) {
@@ -317,6 +563,8 @@ pub struct ContextData<S: Storage, Q: Querier> {
     querier: Option<Q>,
     /// A non-owning link to the wasmer instance
     wasmer_instance: Option<NonNull<WasmerInstance>>,
+    /// The recorded import calls. None if tracing is disabled.
+    import_trace: Option<Vec<ImportCall>>,
 }
 
 impl<S: Storage, Q: Querier> ContextData<S, Q> {
@@ -327,10 +575,25 @@ impl<S: Storage, Q: Querier> ContextData<S, Q> {
             storage_readonly: true,
             querier: None,
             wasmer_instance: None,
+            import_trace: None,
         }
     }
 }
 
+/// Charges the VM side cost of an import call that passed `bytes` bytes
+/// between the contract and the import.
+pub fn charge_import<A: BackendApi, S: Storage, Q: Querier>(
+    env: &Environment<A, S, Q>,
+    cost: ImportGasCost,
+    bytes: usize,
+) -> VmResult<()> {
+    let amount = cost.total(bytes);
+    if amount == 0 {
+        return Ok(());
+    }
+    process_gas_info(env, GasInfo::with_cost(amount))
+}
+
 pub fn process_gas_info<A: BackendApi, S: Storage, Q: Querier>(
     env: &Environment<A, S, Q>,
     info: GasInfo,
@@ -459,6 +722,111 @@ mod tests {
         assert!(endq.is_none());
     }
 
+    #[test]
+    fn trace_import_works() {
+        let (env, _instance) = make_instance(TESTING_GAS_LIMIT);
+
+        // not recorded when disabled
+        env.trace_import("db_read", &[], || env.decrease_gas_left(100))
+            .unwrap();
+        assert_eq!(env.take_import_trace(), vec![]);
+
+        env.set_import_tracing(true);
+        env.trace_import("db_read", &[0], || {
+            env.decrease_gas_left(100)?;
+            process_gas_info(&env, GasInfo::with_externally_used(40))
+        })
+        .unwrap();
+        let err = env
+            .trace_import("abort", &[], || -> VmResult<()> {
+                Err(VmError::aborted("boom"))
+            })
+            .unwrap_err();
+        assert!(matches!(err, VmError::Aborted { .. }));
+
+        let trace = env.take_import_trace();
+        assert_eq!(trace.len(), 2);
+        assert_eq!(trace[0].name, "db_read");
+        assert_eq!(trace[0].arg_sizes, vec![0]);
+        assert_eq!(trace[0].used_externally, 40);
+        assert_eq!(trace[0].used_internally, 100);
+        assert_eq!(trace[1].name, "abort");
+        assert_eq!(trace[1].arg_sizes, Vec::<usize>::new());
+        assert_eq!(trace[1].used_externally, 0);
+        assert_eq!(trace[1].used_internally, 0);
+
+        // taken
+        assert_eq!(env.take_import_trace(), vec![]);
+    }
+
+    #[test]
+    fn import_gas_cost_total_works() {
+        let cost = ImportGasCost {
+            per_call: 100,
+            per_byte: 3,
+        };
+        assert_eq!(cost.total(0), 100);
+        assert_eq!(cost.total(10), 130);
+        assert_eq!(ImportGasCost::default().total(10), 0);
+
+        let expensive = ImportGasCost {
+            per_call: 1,
+            per_byte: u64::MAX,
+        };
+        assert_eq!(expensive.total(2), u64::MAX);
+    }
+
+    #[test]
+    fn gas_config_serialization_works() {
+        let mut config = GasConfig::default();
+        config.db_write_cost = ImportGasCost {
+            per_call: 1_000,
+            per_byte: 30,
+        };
+        let serialized = serde_json::to_vec(&config).unwrap();
+        let deserialized: GasConfig = serde_json::from_slice(&serialized).unwrap();
+        assert_eq!(deserialized, config);
+
+        // missing fields fall back to the default
+        let partial: GasConfig =
+            serde_json::from_str(r#"{"db_read_cost":{"per_call":5,"per_byte":1}}"#).unwrap();
+        assert_eq!(
+            partial,
+            GasConfig {
+                db_read_cost: ImportGasCost {
+                    per_call: 5,
+                    per_byte: 1
+                },
+                ..GasConfig::default()
+            }
+        );
+    }
+
+    #[test]
+    fn charge_import_works() {
+        let (env, _instance) = make_instance(100);
+        let cost = ImportGasCost {
+            per_call: 10,
+            per_byte: 2,
+        };
+
+        charge_import(&env, cost, 5).unwrap();
+        assert_eq!(env.get_gas_left(), 80);
+        charge_import(&env, ImportGasCost::default(), 5).unwrap();
+        assert_eq!(env.get_gas_left(), 80);
+
+        match charge_import(&env, cost, 40).unwrap_err() {
+            VmError::GasDepletion { .. } => {}
+            err => panic!("unexpected error: {:?}", err),
+        }
+        assert_eq!(env.get_gas_left(), 0);
+        // charged gas is not externally used
+        assert_eq!(
+            env.with_gas_state(|gas_state| gas_state.externally_used_gas),
+            0
+        );
+    }
+
     #[test]
     fn process_gas_info_works_for_cost() {
         let (env, _instance) = make_instance(100);
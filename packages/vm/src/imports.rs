@@ -2,11 +2,14 @@
 
 use std::cmp::max;
 
+use cosmwasm_crypto::{blake2b_256, keccak256, sha256};
 use cosmwasm_crypto::{
-    ed25519_batch_verify, ed25519_verify, secp256k1_recover_pubkey, secp256k1_verify, CryptoError,
+    bls12_381_aggregate_verify, ed25519_batch_verify, ed25519_verify, ethereum_recover_address,
+    secp256k1_recover_pubkey, secp256k1_verify, secp256r1_verify, sr25519_verify, CryptoError,
 };
 use cosmwasm_crypto::{
-    ECDSA_PUBKEY_MAX_LEN, ECDSA_SIGNATURE_LEN, EDDSA_PUBKEY_LEN, MESSAGE_HASH_MAX_LEN,
+    BLS12_381_PUBKEY_LEN, BLS12_381_SIGNATURE_LEN, ECDSA_PUBKEY_MAX_LEN, ECDSA_SIGNATURE_LEN,
+    EDDSA_PUBKEY_LEN, HASH_LEN, MESSAGE_HASH_MAX_LEN, SR25519_PUBKEY_LEN, SR25519_SIGNATURE_LEN,
 };
 
 #[cfg(feature = "iterator")]
@@ -14,7 +17,7 @@ use cosmwasm_std::Order;
 
 use crate::backend::{BackendApi, BackendError, Querier, Storage};
 use crate::conversion::{ref_to_u32, to_u32};
-use crate::environment::{process_gas_info, Environment};
+use crate::environment::{charge_import, process_gas_info, Environment, ImportGasCost};
 use crate::errors::{CommunicationError, VmError, VmResult};
 #[cfg(feature = "iterator")]
 use crate::memory::maybe_read_region;
@@ -50,6 +53,19 @@ const MAX_LENGTH_ED25519_MESSAGE: usize = 128 * 1024;
 /// This is an arbitrary value, for performance / memory contraints. If you need to batch-verify a
 /// larger number of signatures, let us know.
 const MAX_COUNT_ED25519_BATCH: usize = 256;
+/// Max length of a sr25519 message in bytes.
+/// Same as for Ed25519, for performance / memory contraints.
+const MAX_LENGTH_SR25519_MESSAGE: usize = 128 * 1024;
+/// Max length of a BLS12-381 message in bytes.
+/// Messages are typically 32 byte signing roots. Hashing them to the curve is not charged per
+/// byte, so this is kept small.
+const MAX_LENGTH_BLS12_381_MESSAGE: usize = 4 * KI;
+/// Max number of BLS12-381 messages / public keys in an aggregate verification.
+/// This is the size of an Ethereum sync committee.
+const MAX_COUNT_BLS12_381_AGGREGATE: usize = 512;
+/// Max length of the data hashed by sha256/keccak256/blake2b_256 in bytes.
+/// Same as for Ed25519 messages, for performance / memory contraints.
+const MAX_LENGTH_HASH_DATA: usize = 128 * 1024;
 
 /// Max length for a debug message
 const MAX_LENGTH_DEBUG: usize = 2 * MI;
@@ -75,6 +91,9 @@ pub fn do_db_read<A: BackendApi, S: Storage, Q: Querier>(
     process_gas_info::<A, S, Q>(env, gas_info)?;
     let value = result?;
 
+    let value_len = value.as_ref().map(|data| data.len()).unwrap_or_default();
+    charge_import(env, env.gas_config.db_read_cost, key.len() + value_len)?;
+
     let out_data = match value {
         Some(data) => data,
         None => return Ok(0),
@@ -94,6 +113,7 @@ pub fn do_db_write<A: BackendApi, S: Storage, Q: Querier>(
 
     let key = read_region(&env.memory(), key_ptr, MAX_LENGTH_DB_KEY)?;
     let value = read_region(&env.memory(), value_ptr, MAX_LENGTH_DB_VALUE)?;
+    charge_import(env, env.gas_config.db_write_cost, key.len() + value.len())?;
 
     let (result, gas_info) =
         env.with_storage_from_context::<_, _>(|store| Ok(store.set(&key, &value)))?;
@@ -112,6 +132,7 @@ pub fn do_db_remove<A: BackendApi, S: Storage, Q: Querier>(
     }
 
     let key = read_region(&env.memory(), key_ptr, MAX_LENGTH_DB_KEY)?;
+    charge_import(env, env.gas_config.db_remove_cost, key.len())?;
 
     let (result, gas_info) =
         env.with_storage_from_context::<_, _>(|store| Ok(store.remove(&key)))?;
@@ -126,6 +147,7 @@ pub fn do_addr_validate<A: BackendApi, S: Storage, Q: Querier>(
     source_ptr: u32,
 ) -> VmResult<u32> {
     let source_data = read_region(&env.memory(), source_ptr, MAX_LENGTH_HUMAN_ADDRESS)?;
+    charge_import(env, env.gas_config.addr_validate_cost, source_data.len())?;
     if source_data.is_empty() {
         return write_to_contract::<A, S, Q>(env, b"Input is empty");
     }
@@ -168,6 +190,11 @@ pub fn do_addr_canonicalize<A: BackendApi, S: Storage, Q: Querier>(
     destination_ptr: u32,
 ) -> VmResult<u32> {
     let source_data = read_region(&env.memory(), source_ptr, MAX_LENGTH_HUMAN_ADDRESS)?;
+    charge_import(
+        env,
+        env.gas_config.addr_canonicalize_cost,
+        source_data.len(),
+    )?;
     if source_data.is_empty() {
         return write_to_contract::<A, S, Q>(env, b"Input is empty");
     }
@@ -197,6 +224,7 @@ pub fn do_addr_humanize<A: BackendApi, S: Storage, Q: Querier>(
     destination_ptr: u32,
 ) -> VmResult<u32> {
     let canonical = read_region(&env.memory(), source_ptr, MAX_LENGTH_CANONICAL_ADDRESS)?;
+    charge_import(env, env.gas_config.addr_humanize_cost, canonical.len())?;
 
     let (result, gas_info) = env.api.human_address(&canonical);
     process_gas_info::<A, S, Q>(env, gas_info)?;
@@ -272,6 +300,64 @@ pub fn do_secp256k1_recover_pubkey<A: BackendApi, S: Storage, Q: Querier>(
     }
 }
 
+pub fn do_secp256r1_verify<A: BackendApi, S: Storage, Q: Querier>(
+    env: &Environment<A, S, Q>,
+    hash_ptr: u32,
+    signature_ptr: u32,
+    pubkey_ptr: u32,
+) -> VmResult<u32> {
+    let hash = read_region(&env.memory(), hash_ptr, MESSAGE_HASH_MAX_LEN)?;
+    let signature = read_region(&env.memory(), signature_ptr, ECDSA_SIGNATURE_LEN)?;
+    let pubkey = read_region(&env.memory(), pubkey_ptr, ECDSA_PUBKEY_MAX_LEN)?;
+
+    let result = secp256r1_verify(&hash, &signature, &pubkey);
+    let gas_info = GasInfo::with_cost(env.gas_config.secp256r1_verify_cost);
+    process_gas_info::<A, S, Q>(env, gas_info)?;
+    Ok(result.map_or_else(
+        |err| match err {
+            CryptoError::InvalidHashFormat { .. }
+            | CryptoError::InvalidPubkeyFormat { .. }
+            | CryptoError::InvalidSignatureFormat { .. }
+            | CryptoError::GenericErr { .. } => err.code(),
+            CryptoError::BatchErr { .. } | CryptoError::InvalidRecoveryParam { .. } => {
+                panic!("Error must not happen for this call")
+            }
+        },
+        |valid| if valid { 0 } else { 1 },
+    ))
+}
+
+/// Recovers the Ethereum address of the signer from a message hash, a signature (r and s)
+/// and the Ethereum style value `v`, which includes the chain ID when EIP-155 is used.
+pub fn do_ethereum_recover_address<A: BackendApi, S: Storage, Q: Querier>(
+    env: &Environment<A, S, Q>,
+    hash_ptr: u32,
+    signature_ptr: u32,
+    v: u64,
+) -> VmResult<u64> {
+    let hash = read_region(&env.memory(), hash_ptr, MESSAGE_HASH_MAX_LEN)?;
+    let signature = read_region(&env.memory(), signature_ptr, ECDSA_SIGNATURE_LEN)?;
+
+    let result = ethereum_recover_address(&hash, &signature, v);
+    let gas_info = GasInfo::with_cost(env.gas_config.ethereum_recover_address_cost);
+    process_gas_info::<A, S, Q>(env, gas_info)?;
+    match result {
+        Ok(address) => {
+            let address_ptr = write_to_contract::<A, S, Q>(env, &address)?;
+            Ok(to_low_half(address_ptr))
+        }
+        Err(err) => match err {
+            CryptoError::InvalidHashFormat { .. }
+            | CryptoError::InvalidSignatureFormat { .. }
+            | CryptoError::InvalidRecoveryParam { .. }
+            | CryptoError::GenericErr { .. } => Ok(to_high_half(err.code())),
+            CryptoError::BatchErr { .. } | CryptoError::InvalidPubkeyFormat { .. } => {
+                panic!("Error must not happen for this call")
+            }
+        },
+    }
+}
+
 pub fn do_ed25519_verify<A: BackendApi, S: Storage, Q: Querier>(
     env: &Environment<A, S, Q>,
     message_ptr: u32,
@@ -283,7 +369,7 @@ pub fn do_ed25519_verify<A: BackendApi, S: Storage, Q: Querier>(
     let pubkey = read_region(&env.memory(), pubkey_ptr, EDDSA_PUBKEY_LEN)?;
 
     let result = ed25519_verify(&message, &signature, &pubkey);
-    let gas_info = GasInfo::with_cost(env.gas_config.ed25519_verify_cost);
+    let gas_info = GasInfo::with_cost(env.gas_config.ed25519_verify_cost.total(message.len()));
     process_gas_info::<A, S, Q>(env, gas_info)?;
     Ok(result.map_or_else(
         |err| match err {
@@ -300,6 +386,72 @@ pub fn do_ed25519_verify<A: BackendApi, S: Storage, Q: Querier>(
     ))
 }
 
+pub fn do_sr25519_verify<A: BackendApi, S: Storage, Q: Querier>(
+    env: &Environment<A, S, Q>,
+    message_ptr: u32,
+    signature_ptr: u32,
+    pubkey_ptr: u32,
+) -> VmResult<u32> {
+    let message = read_region(&env.memory(), message_ptr, MAX_LENGTH_SR25519_MESSAGE)?;
+    let signature = read_region(&env.memory(), signature_ptr, SR25519_SIGNATURE_LEN)?;
+    let pubkey = read_region(&env.memory(), pubkey_ptr, SR25519_PUBKEY_LEN)?;
+
+    let result = sr25519_verify(&message, &signature, &pubkey);
+    let gas_info = GasInfo::with_cost(env.gas_config.sr25519_verify_cost.total(message.len()));
+    process_gas_info::<A, S, Q>(env, gas_info)?;
+    Ok(result.map_or_else(
+        |err| match err {
+            CryptoError::InvalidPubkeyFormat { .. }
+            | CryptoError::InvalidSignatureFormat { .. }
+            | CryptoError::GenericErr { .. } => err.code(),
+            CryptoError::BatchErr { .. }
+            | CryptoError::InvalidHashFormat { .. }
+            | CryptoError::InvalidRecoveryParam { .. } => {
+                panic!("Error must not happen for this call")
+            }
+        },
+        |valid| if valid { 0 } else { 1 },
+    ))
+}
+
+/// Computes the SHA-256 hash of the given data and returns a pointer to a region
+/// containing the 32 byte hash.
+pub fn do_sha256<A: BackendApi, S: Storage, Q: Querier>(
+    env: &Environment<A, S, Q>,
+    data_ptr: u32,
+) -> VmResult<u32> {
+    do_hash(env, data_ptr, env.gas_config.sha256_cost, sha256)
+}
+
+/// Computes the Keccak-256 hash of the given data and returns a pointer to a region
+/// containing the 32 byte hash.
+pub fn do_keccak256<A: BackendApi, S: Storage, Q: Querier>(
+    env: &Environment<A, S, Q>,
+    data_ptr: u32,
+) -> VmResult<u32> {
+    do_hash(env, data_ptr, env.gas_config.keccak256_cost, keccak256)
+}
+
+/// Computes the BLAKE2b-256 hash of the given data and returns a pointer to a region
+/// containing the 32 byte hash.
+pub fn do_blake2b_256<A: BackendApi, S: Storage, Q: Querier>(
+    env: &Environment<A, S, Q>,
+    data_ptr: u32,
+) -> VmResult<u32> {
+    do_hash(env, data_ptr, env.gas_config.blake2b_256_cost, blake2b_256)
+}
+
+fn do_hash<A: BackendApi, S: Storage, Q: Querier>(
+    env: &Environment<A, S, Q>,
+    data_ptr: u32,
+    cost: ImportGasCost,
+    hash: fn(&[u8]) -> [u8; HASH_LEN],
+) -> VmResult<u32> {
+    let data = read_region(&env.memory(), data_ptr, MAX_LENGTH_HASH_DATA)?;
+    charge_import(env, cost, data.len())?;
+    write_to_contract::<A, S, Q>(env, &hash(&data))
+}
+
 pub fn do_ed25519_batch_verify<A: BackendApi, S: Storage, Q: Querier>(
     env: &Environment<A, S, Q>,
     messages_ptr: u32,
@@ -327,12 +479,76 @@ pub fn do_ed25519_batch_verify<A: BackendApi, S: Storage, Q: Querier>(
     let public_keys = decode_sections(&public_keys);
 
     let result = ed25519_batch_verify(&messages, &signatures, &public_keys);
+    let messages_len: usize = messages.iter().map(|message| message.len()).sum();
     let gas_cost = if public_keys.len() == 1 {
         env.gas_config.ed25519_batch_verify_one_pubkey_cost
     } else {
         env.gas_config.ed25519_batch_verify_cost
-    } * signatures.len() as u64;
-    let gas_info = GasInfo::with_cost(max(gas_cost, env.gas_config.ed25519_verify_cost));
+    }
+    .saturating_mul(signatures.len() as u64)
+    .saturating_add(
+        env.gas_config
+            .ed25519_verify_cost
+            .per_byte
+            .saturating_mul(messages_len as u64),
+    );
+    let gas_info = GasInfo::with_cost(max(
+        gas_cost,
+        env.gas_config.ed25519_verify_cost.total(messages_len),
+    ));
+    process_gas_info::<A, S, Q>(env, gas_info)?;
+    Ok(result.map_or_else(
+        |err| match err {
+            CryptoError::BatchErr { .. }
+            | CryptoError::InvalidPubkeyFormat { .. }
+            | CryptoError::InvalidSignatureFormat { .. }
+            | CryptoError::GenericErr { .. } => err.code(),
+            CryptoError::InvalidHashFormat { .. } | CryptoError::InvalidRecoveryParam { .. } => {
+                panic!("Error must not happen for this call")
+            }
+        },
+        |valid| (!valid).into(),
+    ))
+}
+
+pub fn do_bls12_381_aggregate_verify<A: BackendApi, S: Storage, Q: Querier>(
+    env: &Environment<A, S, Q>,
+    messages_ptr: u32,
+    signature_ptr: u32,
+    public_keys_ptr: u32,
+) -> VmResult<u32> {
+    let messages = read_region(
+        &env.memory(),
+        messages_ptr,
+        (MAX_LENGTH_BLS12_381_MESSAGE + 4) * MAX_COUNT_BLS12_381_AGGREGATE,
+    )?;
+    let signature = read_region(&env.memory(), signature_ptr, BLS12_381_SIGNATURE_LEN)?;
+    let public_keys = read_region(
+        &env.memory(),
+        public_keys_ptr,
+        (BLS12_381_PUBKEY_LEN + 4) * MAX_COUNT_BLS12_381_AGGREGATE,
+    )?;
+
+    let messages = decode_sections(&messages);
+    let public_keys = decode_sections(&public_keys);
+
+    let result = bls12_381_aggregate_verify(&messages, &signature, &public_keys);
+    let messages_len: usize = messages.iter().map(|message| message.len()).sum();
+    let gas_cost = env
+        .gas_config
+        .bls12_381_aggregate_verify_cost
+        .total(messages_len)
+        .saturating_add(
+            env.gas_config
+                .bls12_381_aggregate_verify_per_message_cost
+                .saturating_mul(messages.len() as u64),
+        )
+        .saturating_add(
+            env.gas_config
+                .bls12_381_aggregate_verify_per_pubkey_cost
+                .saturating_mul(public_keys.len() as u64),
+        );
+    let gas_info = GasInfo::with_cost(gas_cost);
     process_gas_info::<A, S, Q>(env, gas_info)?;
     Ok(result.map_or_else(
         |err| match err {
@@ -349,15 +565,22 @@ pub fn do_ed25519_batch_verify<A: BackendApi, S: Storage, Q: Querier>(
 }
 
 /// Prints a debug message to console.
-/// This does not charge gas, so debug printing should be disabled when used in a blockchain module.
+/// This does not charge gas by default, so debug printing should be disabled when used in a blockchain module.
 pub fn do_debug<A: BackendApi, S: Storage, Q: Querier>(
     env: &Environment<A, S, Q>,
     message_ptr: u32,
 ) -> VmResult<()> {
-    if env.print_debug {
+    let cost = env.gas_config.debug_cost;
+    // The message is only read if needed, such that the charged gas does not depend on print_debug
+    if env.print_debug || cost.per_byte != 0 {
         let message_data = read_region(&env.memory(), message_ptr, MAX_LENGTH_DEBUG)?;
-        let msg = String::from_utf8_lossy(&message_data);
-        println!("{}", msg);
+        charge_import(env, cost, message_data.len())?;
+        if env.print_debug {
+            let msg = String::from_utf8_lossy(&message_data);
+            println!("{}", msg);
+        }
+    } else {
+        charge_import(env, cost, 0)?;
     }
     Ok(())
 }
@@ -368,6 +591,7 @@ pub fn do_abort<A: BackendApi, S: Storage, Q: Querier>(
     message_ptr: u32,
 ) -> VmResult<()> {
     let message_data = read_region(&env.memory(), message_ptr, MAX_LENGTH_ABORT)?;
+    charge_import(env, env.gas_config.abort_cost, message_data.len())?;
     let msg = String::from_utf8_lossy(&message_data);
     Err(VmError::aborted(msg))
 }
@@ -399,6 +623,11 @@ pub fn do_query_chain<A: BackendApi, S: Storage, Q: Querier>(
     })?;
     process_gas_info::<A, S, Q>(env, gas_info)?;
     let serialized = to_vec(&result?)?;
+    charge_import(
+        env,
+        env.gas_config.query_chain_cost,
+        request.len() + serialized.len(),
+    )?;
     write_to_contract::<A, S, Q>(env, &serialized)
 }
 
@@ -414,6 +643,9 @@ pub fn do_db_scan<A: BackendApi, S: Storage, Q: Querier>(
     let order: Order = order
         .try_into()
         .map_err(|_| CommunicationError::invalid_order(order))?;
+    let bounds_len = start.as_ref().map(|s| s.len()).unwrap_or_default()
+        + end.as_ref().map(|e| e.len()).unwrap_or_default();
+    charge_import(env, env.gas_config.db_scan_cost, bounds_len)?;
 
     let (result, gas_info) = env.with_storage_from_context::<_, _>(|store| {
         Ok(store.scan(start.as_deref(), end.as_deref(), order))
@@ -434,6 +666,7 @@ pub fn do_db_next<A: BackendApi, S: Storage, Q: Querier>(
 
     // Empty key will later be treated as _no more element_.
     let (key, value) = result?.unwrap_or_else(|| (Vec::<u8>::new(), Vec::<u8>::new()));
+    charge_import(env, env.gas_config.db_next_cost, key.len() + value.len())?;
 
     let out_data = encode_sections(&[key, value])?;
     write_to_contract::<A, S, Q>(env, &out_data)
@@ -462,15 +695,21 @@ fn to_low_half(data: u32) -> u64 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use bls12_381::hash_to_curve::{ExpandMsgXmd, HashToCurve};
+    use bls12_381::{G1Affine, G2Affine, G2Projective, Scalar};
+    use cosmwasm_crypto::BLS12_381_DST;
     use cosmwasm_std::{
         coins, from_binary, AllBalanceResponse, BankQuery, Binary, Empty, QueryRequest,
         SystemError, SystemResult, WasmQuery,
     };
     use hex_literal::hex;
+    use schnorrkel::{ExpansionMode, MiniSecretKey};
+    use sha2::{Digest, Sha256};
     use std::ptr::NonNull;
     use wasmer::{imports, Function, Instance as WasmerInstance};
 
     use crate::backend::{BackendError, Storage};
+    use crate::environment::{GasConfig, ImportGasCost};
     use crate::size::Size;
     use crate::testing::{MockApi, MockQuerier, MockStorage};
     use crate::wasm_backend::compile;
@@ -619,6 +858,99 @@ mod tests {
         }
     }
 
+    /// Returns the gas used by `do_db_read` for KEY1 with the given `db_read_cost`
+    fn db_read_gas_used(cost: ImportGasCost) -> u64 {
+        let api = MockApi::default();
+        let (mut env, _instance) = make_instance(api);
+        env.gas_config.db_read_cost = cost;
+        leave_default_data(&env);
+
+        let key_ptr = write_data(&env, KEY1);
+        let gas_before = env.get_gas_left();
+        do_db_read(&env, key_ptr).unwrap();
+        gas_before - env.get_gas_left()
+    }
+
+    #[test]
+    fn do_db_read_charges_configured_gas() {
+        let backend_only = db_read_gas_used(ImportGasCost::default());
+        let cost = ImportGasCost {
+            per_call: 1_000,
+            per_byte: 10,
+        };
+        let expected = 1_000 + 10 * (KEY1.len() + VALUE1.len()) as u64;
+        assert_eq!(db_read_gas_used(cost), backend_only + expected);
+    }
+
+    #[test]
+    fn do_db_write_charges_configured_gas() {
+        let api = MockApi::default();
+        let (mut env, _instance) = make_instance(api);
+        env.gas_config.db_write_cost = ImportGasCost {
+            per_call: 1_000,
+            per_byte: 10,
+        };
+
+        let key_ptr = write_data(&env, b"new storage key");
+        let value_ptr = write_data(&env, b"new value");
+        leave_default_data(&env);
+
+        let gas_before = env.get_gas_left();
+        let externally_before = env.with_gas_state(|gas_state| gas_state.externally_used_gas);
+        do_db_write(&env, key_ptr, value_ptr).unwrap();
+        let externally_used =
+            env.with_gas_state(|gas_state| gas_state.externally_used_gas) - externally_before;
+        assert_eq!(
+            gas_before - env.get_gas_left(),
+            externally_used + 1_000 + 10 * 24
+        );
+    }
+
+    #[test]
+    fn do_db_write_fails_when_configured_gas_exceeds_limit() {
+        let api = MockApi::default();
+        let (mut env, _instance) = make_instance(api);
+        env.gas_config.db_write_cost = ImportGasCost {
+            per_call: TESTING_GAS_LIMIT + 1,
+            per_byte: 0,
+        };
+
+        let key_ptr = write_data(&env, b"new storage key");
+        let value_ptr = write_data(&env, b"new value");
+        leave_default_data(&env);
+
+        match do_db_write(&env, key_ptr, value_ptr).unwrap_err() {
+            VmError::GasDepletion { .. } => {}
+            e => panic!("Unexpected error: {:?}", e),
+        }
+        // nothing was written
+        let val = env
+            .with_storage_from_context::<_, _>(|store| {
+                Ok(store
+                    .get(b"new storage key")
+                    .0
+                    .expect("error getting value"))
+            })
+            .unwrap();
+        assert_eq!(val, None);
+    }
+
+    #[test]
+    fn do_debug_charges_configured_gas() {
+        let api = MockApi::default();
+        let (mut env, _instance) = make_instance(api);
+        env.gas_config.debug_cost = ImportGasCost {
+            per_call: 100,
+            per_byte: 1,
+        };
+
+        let message_ptr = write_data(&env, b"Hello, world");
+        let gas_before = env.get_gas_left();
+        do_debug(&env, message_ptr).unwrap();
+        // charged even though print_debug is disabled
+        assert_eq!(gas_before - env.get_gas_left(), 112);
+    }
+
     #[test]
     fn do_db_write_works() {
         let api = MockApi::default();
@@ -1444,6 +1776,136 @@ mod tests {
         assert_eq!(force_read(&env, pubkey_ptr), expected);
     }
 
+    #[test]
+    fn do_ethereum_recover_address_works() {
+        let api = MockApi::default();
+        let (env, mut _instance) = make_instance(api);
+
+        // Signed text "connect all the things" from contracts/crypto-verify
+        let hash = hex!("858dc8f47a065dee00b28dc0064215b7f0a56c005fa8e823bbb4ce58bdb288cc");
+        let sig = hex!("dada130255a447ecf434a2df9193e6fbba663e4546c35c075cd6eea21d8c7cb1714b9b65a4f7f604ff6aad55fba73f8c36514a512bbbba03709b37069194f8a4");
+        let v = 27;
+        let expected = hex!("12890d2cce102216644c59dae5baed380d84830c");
+
+        let hash_ptr = write_data(&env, &hash);
+        let sig_ptr = write_data(&env, &sig);
+        let result = do_ethereum_recover_address(&env, hash_ptr, sig_ptr, v).unwrap();
+        let error = result >> 32;
+        let address_ptr: u32 = (result & 0xFFFFFFFF).try_into().unwrap();
+        assert_eq!(error, 0);
+        assert_eq!(force_read(&env, address_ptr), expected);
+
+        // This includes the gas the contract uses to allocate the region for the address
+        let gas_used = |cost: u64| {
+            let (mut env, _instance) = make_instance(MockApi::default());
+            env.gas_config.ethereum_recover_address_cost = cost;
+            let hash_ptr = write_data(&env, &hash);
+            let sig_ptr = write_data(&env, &sig);
+            let gas_before = env.get_gas_left();
+            do_ethereum_recover_address(&env, hash_ptr, sig_ptr, v).unwrap();
+            gas_before - env.get_gas_left()
+        };
+        let cost = GasConfig::default().ethereum_recover_address_cost;
+        assert_eq!(gas_used(cost) - gas_used(0), cost);
+    }
+
+    #[test]
+    fn do_ethereum_recover_address_works_with_chain_id() {
+        let api = MockApi::default();
+        let (env, mut _instance) = make_instance(api);
+
+        // Rinkeby transaction 0x3b87faa3410f33284124a6898fac1001673f0f7c3682d18f55bdff0031cce9ce
+        // from contracts/crypto-verify, hash of the unsigned EIP-155 serialization
+        let hash = hex!("1ff5c235b3c317d054b80b4bf0a8038bd727d180872d2491a7edef4f949c4135");
+        let sig = hex!("b9299dab50b3cddcaecd64b29bfbd5cd30fac1a1adea1b359a13c4e5171492a6573059c66d894684488f92e7ce1f91b158ca57b0235485625b576a3b98c480ac");
+        let v = 0x2b; // chain ID 4
+        let expected = hex!("0a65766695a712af41b5cfecaad217b1a11cb22a");
+
+        let hash_ptr = write_data(&env, &hash);
+        let sig_ptr = write_data(&env, &sig);
+        let result = do_ethereum_recover_address(&env, hash_ptr, sig_ptr, v).unwrap();
+        let error = result >> 32;
+        let address_ptr: u32 = (result & 0xFFFFFFFF).try_into().unwrap();
+        assert_eq!(error, 0);
+        assert_eq!(force_read(&env, address_ptr), expected);
+    }
+
+    #[test]
+    fn do_ethereum_recover_address_fails_for_invalid_v() {
+        let api = MockApi::default();
+        let (env, mut _instance) = make_instance(api);
+
+        let hash = hex!("858dc8f47a065dee00b28dc0064215b7f0a56c005fa8e823bbb4ce58bdb288cc");
+        let sig = hex!("dada130255a447ecf434a2df9193e6fbba663e4546c35c075cd6eea21d8c7cb1714b9b65a4f7f604ff6aad55fba73f8c36514a512bbbba03709b37069194f8a4");
+
+        let hash_ptr = write_data(&env, &hash);
+        let sig_ptr = write_data(&env, &sig);
+        let result = do_ethereum_recover_address(&env, hash_ptr, sig_ptr, 29).unwrap();
+        assert_eq!(result >> 32, 6); // mapped InvalidRecoveryParam
+        assert_eq!(result & 0xFFFFFFFF, 0);
+    }
+
+    // Test vector from https://www.rfc-editor.org/rfc/rfc6979#appendix-A.2.5 (P-256, SHA-256)
+    const SECP256R1_MSG: &[u8] = b"sample";
+    const SECP256R1_SIG: [u8; 64] = hex!("efd48b2aacb6a8fd1140dd9cd45e81d69d2c877b56aaf991c34d0ea84eaf3716f7cb1c942d657c41d436c7a1b6e29f65f3e900dbb9aff4064dc4ab2f843acda8");
+    const SECP256R1_PUBKEY: [u8; 65] = hex!("0460fed4ba255a9d31c961eb74c6356d68c049b8923b61fa6ce669622e60f29fb67903fe1008b8bc99a41ae9e95628bc64f2f1b20c2d7e9f5177a3c294d4462299");
+
+    #[test]
+    fn do_secp256r1_verify_works() {
+        let api = MockApi::default();
+        let (env, mut _instance) = make_instance(api);
+
+        let hash = Sha256::digest(SECP256R1_MSG);
+        let hash_ptr = write_data(&env, &hash);
+        let sig_ptr = write_data(&env, &SECP256R1_SIG);
+        let pubkey_ptr = write_data(&env, &SECP256R1_PUBKEY);
+
+        let gas_before = env.get_gas_left();
+        assert_eq!(
+            do_secp256r1_verify(&env, hash_ptr, sig_ptr, pubkey_ptr).unwrap(),
+            0
+        );
+        assert_eq!(
+            gas_before - env.get_gas_left(),
+            GasConfig::default().secp256r1_verify_cost
+        );
+    }
+
+    #[test]
+    fn do_secp256r1_verify_wrong_hash_verify_fails() {
+        let api = MockApi::default();
+        let (env, mut _instance) = make_instance(api);
+
+        let mut hash = Sha256::digest(SECP256R1_MSG);
+        // alter hash
+        hash[0] ^= 0x01;
+        let hash_ptr = write_data(&env, &hash);
+        let sig_ptr = write_data(&env, &SECP256R1_SIG);
+        let pubkey_ptr = write_data(&env, &SECP256R1_PUBKEY);
+
+        assert_eq!(
+            do_secp256r1_verify(&env, hash_ptr, sig_ptr, pubkey_ptr).unwrap(),
+            1
+        );
+    }
+
+    #[test]
+    fn do_secp256r1_verify_shorter_pubkey_fails() {
+        let api = MockApi::default();
+        let (env, mut _instance) = make_instance(api);
+
+        let hash = Sha256::digest(SECP256R1_MSG);
+        let hash_ptr = write_data(&env, &hash);
+        let sig_ptr = write_data(&env, &SECP256R1_SIG);
+        // cut pubkey
+        let pubkey_ptr = write_data(&env, &SECP256R1_PUBKEY[..64]);
+
+        assert_eq!(
+            do_secp256r1_verify(&env, hash_ptr, sig_ptr, pubkey_ptr).unwrap(),
+            5 // mapped InvalidPubkeyFormat
+        );
+    }
+
     #[test]
     fn do_ed25519_verify_works() {
         let api = MockApi::default();
@@ -1670,6 +2132,322 @@ mod tests {
         )
     }
 
+    /// Creates a message, sr25519 signature and public key in the Substrate signing context
+    fn sr25519_signed_message() -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+        let keypair = MiniSecretKey::from_bytes(&[0x42; 32])
+            .unwrap()
+            .expand_to_keypair(ExpansionMode::Ed25519);
+        let msg = b"Picasso says hello".to_vec();
+        let sig = keypair.sign_simple(b"substrate", &msg).to_bytes().to_vec();
+        let pubkey = keypair.public.to_bytes().to_vec();
+        (msg, sig, pubkey)
+    }
+
+    #[test]
+    fn do_sr25519_verify_works() {
+        let api = MockApi::default();
+        let (env, mut _instance) = make_instance(api);
+
+        let (msg, sig, pubkey) = sr25519_signed_message();
+        let msg_ptr = write_data(&env, &msg);
+        let sig_ptr = write_data(&env, &sig);
+        let pubkey_ptr = write_data(&env, &pubkey);
+
+        let gas_before = env.get_gas_left();
+        assert_eq!(
+            do_sr25519_verify(&env, msg_ptr, sig_ptr, pubkey_ptr).unwrap(),
+            0
+        );
+        assert_eq!(
+            gas_before - env.get_gas_left(),
+            GasConfig::default().sr25519_verify_cost.total(msg.len())
+        );
+    }
+
+    #[test]
+    fn do_sr25519_verify_wrong_msg_verify_fails() {
+        let api = MockApi::default();
+        let (env, mut _instance) = make_instance(api);
+
+        let (mut msg, sig, pubkey) = sr25519_signed_message();
+        // alter msg
+        msg.push(0x01);
+        let msg_ptr = write_data(&env, &msg);
+        let sig_ptr = write_data(&env, &sig);
+        let pubkey_ptr = write_data(&env, &pubkey);
+
+        assert_eq!(
+            do_sr25519_verify(&env, msg_ptr, sig_ptr, pubkey_ptr).unwrap(),
+            1
+        );
+    }
+
+    #[test]
+    fn do_sr25519_verify_larger_msg_fails() {
+        let api = MockApi::default();
+        let (env, mut _instance) = make_instance(api);
+
+        let (mut msg, sig, pubkey) = sr25519_signed_message();
+        // extend / break msg
+        msg.extend_from_slice(&[0x00; MAX_LENGTH_SR25519_MESSAGE + 1]);
+        let msg_ptr = write_data(&env, &msg);
+        let sig_ptr = write_data(&env, &sig);
+        let pubkey_ptr = write_data(&env, &pubkey);
+
+        let result = do_sr25519_verify(&env, msg_ptr, sig_ptr, pubkey_ptr);
+        match result.unwrap_err() {
+            VmError::CommunicationErr {
+                source: CommunicationError::RegionLengthTooBig { length, .. },
+                ..
+            } => assert_eq!(length, msg.len()),
+            e => panic!("Unexpected error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn do_sr25519_verify_shorter_sig_fails() {
+        let api = MockApi::default();
+        let (env, mut _instance) = make_instance(api);
+
+        let (msg, mut sig, pubkey) = sr25519_signed_message();
+        // cut sig
+        sig.pop();
+        let msg_ptr = write_data(&env, &msg);
+        let sig_ptr = write_data(&env, &sig);
+        let pubkey_ptr = write_data(&env, &pubkey);
+
+        assert_eq!(
+            do_sr25519_verify(&env, msg_ptr, sig_ptr, pubkey_ptr).unwrap(),
+            4 // mapped InvalidSignatureFormat
+        );
+    }
+
+    #[test]
+    fn do_sr25519_verify_shorter_pubkey_fails() {
+        let api = MockApi::default();
+        let (env, mut _instance) = make_instance(api);
+
+        let (msg, sig, mut pubkey) = sr25519_signed_message();
+        // cut pubkey
+        pubkey.pop();
+        let msg_ptr = write_data(&env, &msg);
+        let sig_ptr = write_data(&env, &sig);
+        let pubkey_ptr = write_data(&env, &pubkey);
+
+        assert_eq!(
+            do_sr25519_verify(&env, msg_ptr, sig_ptr, pubkey_ptr).unwrap(),
+            5 // mapped InvalidPubkeyFormat
+        );
+    }
+
+    /// Enough gas for an aggregate verification of a few messages, which takes several ms
+    const BLS12_381_TESTING_GAS_LIMIT: u64 = 10 * TESTING_GAS_LIMIT;
+
+    /// Signs each message with its own key and returns the aggregate signature and the public keys
+    fn bls12_381_signed_messages(messages: &[&[u8]]) -> (Vec<u8>, Vec<Vec<u8>>) {
+        let mut signature = G2Projective::identity();
+        let mut pubkeys = vec![];
+        for (i, message) in messages.iter().enumerate() {
+            let secret = Scalar::from(i as u64 + 1);
+            let point = <G2Projective as HashToCurve<ExpandMsgXmd<Sha256>>>::hash_to_curve(
+                message,
+                BLS12_381_DST,
+            );
+            signature += point * secret;
+            pubkeys.push(
+                G1Affine::from(G1Affine::generator() * secret)
+                    .to_compressed()
+                    .to_vec(),
+            );
+        }
+        (G2Affine::from(signature).to_compressed().to_vec(), pubkeys)
+    }
+
+    #[test]
+    fn do_bls12_381_aggregate_verify_works() {
+        let api = MockApi::default();
+        let (env, mut _instance) = make_instance(api);
+        env.set_gas_left(BLS12_381_TESTING_GAS_LIMIT);
+
+        let messages: [&[u8]; 2] = [b"slot 1", b"slot 2"];
+        let (sig, pubkeys) = bls12_381_signed_messages(&messages);
+        let messages_ptr = write_data(
+            &env,
+            &encode_sections(&[messages[0].to_vec(), messages[1].to_vec()]).unwrap(),
+        );
+        let sig_ptr = write_data(&env, &sig);
+        let pubkeys_ptr = write_data(&env, &encode_sections(&pubkeys).unwrap());
+
+        let gas_before = env.get_gas_left();
+        assert_eq!(
+            do_bls12_381_aggregate_verify(&env, messages_ptr, sig_ptr, pubkeys_ptr).unwrap(),
+            0
+        );
+        let gas_config = GasConfig::default();
+        assert_eq!(
+            gas_before - env.get_gas_left(),
+            gas_config.bls12_381_aggregate_verify_cost.total(12)
+                + 2 * gas_config.bls12_381_aggregate_verify_per_message_cost
+                + 2 * gas_config.bls12_381_aggregate_verify_per_pubkey_cost
+        );
+    }
+
+    #[test]
+    fn do_bls12_381_aggregate_verify_wrong_msg_verify_fails() {
+        let api = MockApi::default();
+        let (env, mut _instance) = make_instance(api);
+        env.set_gas_left(BLS12_381_TESTING_GAS_LIMIT);
+
+        let messages: [&[u8]; 2] = [b"slot 1", b"slot 2"];
+        let (sig, pubkeys) = bls12_381_signed_messages(&messages);
+        let messages_ptr = write_data(
+            &env,
+            &encode_sections(&[messages[0].to_vec(), b"slot 3".to_vec()]).unwrap(),
+        );
+        let sig_ptr = write_data(&env, &sig);
+        let pubkeys_ptr = write_data(&env, &encode_sections(&pubkeys).unwrap());
+
+        assert_eq!(
+            do_bls12_381_aggregate_verify(&env, messages_ptr, sig_ptr, pubkeys_ptr).unwrap(),
+            1
+        );
+    }
+
+    #[test]
+    fn do_bls12_381_aggregate_verify_mismatched_counts_fails() {
+        let api = MockApi::default();
+        let (env, mut _instance) = make_instance(api);
+        env.set_gas_left(BLS12_381_TESTING_GAS_LIMIT);
+
+        let messages: [&[u8]; 3] = [b"slot 1", b"slot 2", b"slot 3"];
+        let (sig, pubkeys) = bls12_381_signed_messages(&messages);
+        // two messages for three public keys
+        let messages_ptr = write_data(
+            &env,
+            &encode_sections(&[messages[0].to_vec(), messages[1].to_vec()]).unwrap(),
+        );
+        let sig_ptr = write_data(&env, &sig);
+        let pubkeys_ptr = write_data(&env, &encode_sections(&pubkeys).unwrap());
+
+        assert_eq!(
+            do_bls12_381_aggregate_verify(&env, messages_ptr, sig_ptr, pubkeys_ptr).unwrap(),
+            7 // mapped BatchErr
+        );
+    }
+
+    /// Returns the gas used by `do_hash` for the data "abc" with the given gas schedule.
+    /// This includes the gas the contract uses to allocate the region for the hash.
+    fn hash_gas_used(
+        do_hash: fn(&Environment<MockApi, MockStorage, MockQuerier>, u32) -> VmResult<u32>,
+        gas_config: GasConfig,
+    ) -> u64 {
+        let api = MockApi::default();
+        let (mut env, _instance) = make_instance(api);
+        env.gas_config = gas_config;
+
+        let data_ptr = write_data(&env, b"abc");
+        let gas_before = env.get_gas_left();
+        do_hash(&env, data_ptr).unwrap();
+        gas_before - env.get_gas_left()
+    }
+
+    #[test]
+    fn do_sha256_works() {
+        let api = MockApi::default();
+        let (env, mut _instance) = make_instance(api);
+
+        let data_ptr = write_data(&env, b"abc");
+        let hash_ptr = do_sha256(&env, data_ptr).unwrap();
+        assert_eq!(
+            force_read(&env, hash_ptr),
+            hex!("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+        );
+
+        let free = GasConfig {
+            sha256_cost: ImportGasCost::free(),
+            ..GasConfig::default()
+        };
+        assert_eq!(
+            hash_gas_used(do_sha256, GasConfig::default()) - hash_gas_used(do_sha256, free),
+            GasConfig::default().sha256_cost.total(3)
+        );
+    }
+
+    #[test]
+    fn do_keccak256_works() {
+        let api = MockApi::default();
+        let (env, mut _instance) = make_instance(api);
+
+        let data_ptr = write_data(&env, b"abc");
+        let hash_ptr = do_keccak256(&env, data_ptr).unwrap();
+        assert_eq!(
+            force_read(&env, hash_ptr),
+            hex!("4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45")
+        );
+
+        let free = GasConfig {
+            keccak256_cost: ImportGasCost::free(),
+            ..GasConfig::default()
+        };
+        assert_eq!(
+            hash_gas_used(do_keccak256, GasConfig::default()) - hash_gas_used(do_keccak256, free),
+            GasConfig::default().keccak256_cost.total(3)
+        );
+    }
+
+    #[test]
+    fn do_blake2b_256_works() {
+        let api = MockApi::default();
+        let (env, mut _instance) = make_instance(api);
+
+        let data_ptr = write_data(&env, b"abc");
+        let hash_ptr = do_blake2b_256(&env, data_ptr).unwrap();
+        assert_eq!(
+            force_read(&env, hash_ptr),
+            hex!("bddd813c634239723171ef3fee98579b94964e3bb1cb3e427262c8c068d52319")
+        );
+
+        let free = GasConfig {
+            blake2b_256_cost: ImportGasCost::free(),
+            ..GasConfig::default()
+        };
+        assert_eq!(
+            hash_gas_used(do_blake2b_256, GasConfig::default())
+                - hash_gas_used(do_blake2b_256, free),
+            GasConfig::default().blake2b_256_cost.total(3)
+        );
+    }
+
+    #[test]
+    fn do_sha256_works_for_empty_data() {
+        let api = MockApi::default();
+        let (env, mut _instance) = make_instance(api);
+
+        let data_ptr = write_data(&env, b"");
+        let hash_ptr = do_sha256(&env, data_ptr).unwrap();
+        assert_eq!(
+            force_read(&env, hash_ptr),
+            hex!("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855")
+        );
+    }
+
+    #[test]
+    fn do_sha256_fails_for_large_data() {
+        let api = MockApi::default();
+        let (env, mut _instance) = make_instance(api);
+
+        let data = vec![0x42; MAX_LENGTH_HASH_DATA + 1];
+        let data_ptr = write_data(&env, &data);
+        let result = do_sha256(&env, data_ptr);
+        match result.unwrap_err() {
+            VmError::CommunicationErr {
+                source: CommunicationError::RegionLengthTooBig { length, .. },
+                ..
+            } => assert_eq!(length, data.len()),
+            e => panic!("Unexpected error: {:?}", e),
+        }
+    }
+
     #[test]
     fn do_query_chain_works() {
         let api = MockApi::default();
@@ -6,19 +6,21 @@ use wasmer::{Exports, Function, ImportObject, Instance as WasmerInstance, Module
 
 use crate::backend::{Backend, BackendApi, Querier, Storage};
 use crate::conversion::{ref_to_u32, to_u32};
-use crate::environment::Environment;
+use crate::environment::{Environment, GasConfig, ImportCall};
 use crate::errors::{CommunicationError, VmError, VmResult};
 use crate::features::required_features_from_module;
 use crate::imports::{
-    do_abort, do_addr_canonicalize, do_addr_humanize, do_addr_validate, do_db_read, do_db_remove,
-    do_db_write, do_debug, do_ed25519_batch_verify, do_ed25519_verify, do_query_chain,
-    do_secp256k1_recover_pubkey, do_secp256k1_verify,
+    do_abort, do_addr_canonicalize, do_addr_humanize, do_addr_validate,
+    do_bls12_381_aggregate_verify, do_db_read, do_db_remove, do_db_write, do_debug,
+    do_ed25519_batch_verify, do_ed25519_verify, do_ethereum_recover_address, do_query_chain,
+    do_secp256k1_recover_pubkey, do_secp256k1_verify, do_secp256r1_verify, do_sr25519_verify,
 };
+use crate::imports::{do_blake2b_256, do_keccak256, do_sha256};
 #[cfg(feature = "iterator")]
 use crate::imports::{do_db_next, do_db_scan};
 use crate::memory::{read_region, write_region};
 use crate::size::Size;
-use crate::wasm_backend::compile;
+use crate::wasm_backend::{compile_with_policy, GatekeeperPolicy};
 
 #[derive(Copy, Clone, Debug)]
 pub struct GasReport {
@@ -37,6 +39,9 @@ pub struct GasReport {
 pub struct InstanceOptions {
     pub gas_limit: u64,
     pub print_debug: bool,
+    /// Gas schedule of the imports. If `None`, the schedule of the cache is used,
+    /// or the default schedule for instances created without a cache.
+    pub gas_config: Option<GasConfig>,
 }
 
 pub struct Instance<A: BackendApi, S: Storage, Q: Querier> {
@@ -63,12 +68,30 @@ where
         options: InstanceOptions,
         memory_limit: Option<Size>,
     ) -> VmResult<Self> {
-        let module = compile(code, memory_limit, &[])?;
+        Instance::from_code_with_policy(
+            code,
+            backend,
+            options,
+            memory_limit,
+            GatekeeperPolicy::default(),
+        )
+    }
+
+    /// Like [`Instance::from_code`] but compiles the code with the given Gatekeeper policy.
+    pub(crate) fn from_code_with_policy(
+        code: &[u8],
+        backend: Backend<A, S, Q>,
+        options: InstanceOptions,
+        memory_limit: Option<Size>,
+        gatekeeper_policy: GatekeeperPolicy,
+    ) -> VmResult<Self> {
+        let module = compile_with_policy(code, memory_limit, &[], gatekeeper_policy)?;
         Instance::from_module(
             &module,
             backend,
             options.gas_limit,
             options.print_debug,
+            options.gas_config.unwrap_or_default(),
             None,
             None,
         )
@@ -79,12 +102,14 @@ where
         backend: Backend<A, S, Q>,
         gas_limit: u64,
         print_debug: bool,
+        gas_config: GasConfig,
         extra_imports: Option<HashMap<&str, Exports>>,
         instantiation_lock: Option<&Mutex<()>>,
     ) -> VmResult<Self> {
         let store = module.store();
 
-        let env = Environment::new(backend.api, gas_limit, print_debug);
+        let mut env = Environment::new(backend.api, gas_limit, print_debug);
+        env.gas_config = gas_config;
 
         let mut import_obj = ImportObject::new();
         let mut env_imports = Exports::new();
@@ -95,14 +120,28 @@ where
         // Ownership of the value pointer is transferred to the contract.
         env_imports.insert(
             "db_read",
-            Function::new_native_with_env(store, env.clone(), do_db_read),
+            Function::new_native_with_env(
+                store,
+                env.clone(),
+                |env: &Environment<A, S, Q>, key_ptr: u32| {
+                    env.trace_import("db_read", &[key_ptr], || do_db_read(env, key_ptr))
+                },
+            ),
         );
 
         // Writes the given value into the database entry at the given key.
         // Ownership of both input and output pointer is not transferred to the host.
         env_imports.insert(
             "db_write",
-            Function::new_native_with_env(store, env.clone(), do_db_write),
+            Function::new_native_with_env(
+                store,
+                env.clone(),
+                |env: &Environment<A, S, Q>, key_ptr: u32, value_ptr: u32| {
+                    env.trace_import("db_write", &[key_ptr, value_ptr], || {
+                        do_db_write(env, key_ptr, value_ptr)
+                    })
+                },
+            ),
         );
 
         // Removes the value at the given key. Different than writing &[] as future
@@ -111,7 +150,13 @@ where
         // Ownership of both key pointer is not transferred to the host.
         env_imports.insert(
             "db_remove",
-            Function::new_native_with_env(store, env.clone(), do_db_remove),
+            Function::new_native_with_env(
+                store,
+                env.clone(),
+                |env: &Environment<A, S, Q>, key_ptr: u32| {
+                    env.trace_import("db_remove", &[key_ptr], || do_db_remove(env, key_ptr))
+                },
+            ),
         );
 
         // Reads human address from source_ptr and checks if it is valid.
@@ -119,7 +164,15 @@ where
         // Ownership of the input pointer is not transferred to the host.
         env_imports.insert(
             "addr_validate",
-            Function::new_native_with_env(store, env.clone(), do_addr_validate),
+            Function::new_native_with_env(
+                store,
+                env.clone(),
+                |env: &Environment<A, S, Q>, source_ptr: u32| {
+                    env.trace_import("addr_validate", &[source_ptr], || {
+                        do_addr_validate(env, source_ptr)
+                    })
+                },
+            ),
         );
 
         // Reads human address from source_ptr and writes canonicalized representation to destination_ptr.
@@ -128,7 +181,15 @@ where
         // Ownership of both input and output pointer is not transferred to the host.
         env_imports.insert(
             "addr_canonicalize",
-            Function::new_native_with_env(store, env.clone(), do_addr_canonicalize),
+            Function::new_native_with_env(
+                store,
+                env.clone(),
+                |env: &Environment<A, S, Q>, source_ptr: u32, destination_ptr: u32| {
+                    env.trace_import("addr_canonicalize", &[source_ptr], || {
+                        do_addr_canonicalize(env, source_ptr, destination_ptr)
+                    })
+                },
+            ),
         );
 
         // Reads canonical address from source_ptr and writes humanized representation to destination_ptr.
@@ -137,7 +198,15 @@ where
         // Ownership of both input and output pointer is not transferred to the host.
         env_imports.insert(
             "addr_humanize",
-            Function::new_native_with_env(store, env.clone(), do_addr_humanize),
+            Function::new_native_with_env(
+                store,
+                env.clone(),
+                |env: &Environment<A, S, Q>, source_ptr: u32, destination_ptr: u32| {
+                    env.trace_import("addr_humanize", &[source_ptr], || {
+                        do_addr_humanize(env, source_ptr, destination_ptr)
+                    })
+                },
+            ),
         );
 
         // Verifies message hashes against a signature with a public key, using the secp256k1 ECDSA parametrization.
@@ -145,12 +214,74 @@ where
         // Ownership of input pointers is not transferred to the host.
         env_imports.insert(
             "secp256k1_verify",
-            Function::new_native_with_env(store, env.clone(), do_secp256k1_verify),
+            Function::new_native_with_env(
+                store,
+                env.clone(),
+                |env: &Environment<A, S, Q>, hash_ptr: u32, signature_ptr: u32, pubkey_ptr: u32| {
+                    env.trace_import(
+                        "secp256k1_verify",
+                        &[hash_ptr, signature_ptr, pubkey_ptr],
+                        || do_secp256k1_verify(env, hash_ptr, signature_ptr, pubkey_ptr),
+                    )
+                },
+            ),
         );
 
         env_imports.insert(
             "secp256k1_recover_pubkey",
-            Function::new_native_with_env(store, env.clone(), do_secp256k1_recover_pubkey),
+            Function::new_native_with_env(
+                store,
+                env.clone(),
+                |env: &Environment<A, S, Q>,
+                 hash_ptr: u32,
+                 signature_ptr: u32,
+                 recover_param: u32| {
+                    env.trace_import(
+                        "secp256k1_recover_pubkey",
+                        &[hash_ptr, signature_ptr],
+                        || do_secp256k1_recover_pubkey(env, hash_ptr, signature_ptr, recover_param),
+                    )
+                },
+            ),
+        );
+
+        // Verifies message hashes against a signature with a public key, using the secp256r1 (NIST P-256) ECDSA parametrization.
+        // Returns 0 on verification success, 1 on verification failure, and values greater than 1 in case of error.
+        // Ownership of input pointers is not transferred to the host.
+        env_imports.insert(
+            "secp256r1_verify",
+            Function::new_native_with_env(
+                store,
+                env.clone(),
+                |env: &Environment<A, S, Q>, hash_ptr: u32, signature_ptr: u32, pubkey_ptr: u32| {
+                    env.trace_import(
+                        "secp256r1_verify",
+                        &[hash_ptr, signature_ptr, pubkey_ptr],
+                        || do_secp256r1_verify(env, hash_ptr, signature_ptr, pubkey_ptr),
+                    )
+                },
+            ),
+        );
+
+        // Recovers the Ethereum address of the signer from a message hash, a signature (r and s)
+        // and the Ethereum style value v (27, 28 or EIP-155 with chain ID), like ecrecover in the EVM.
+        // Returns a combined value: the 32 higher bits contain an error code (0 on success),
+        // the 32 lower bits contain a pointer to a Region containing the 20 byte address.
+        // Ownership of input pointers is not transferred to the host.
+        // Ownership of the output pointer is transferred to the contract.
+        env_imports.insert(
+            "ethereum_recover_address",
+            Function::new_native_with_env(
+                store,
+                env.clone(),
+                |env: &Environment<A, S, Q>, hash_ptr: u32, signature_ptr: u32, v: u64| {
+                    env.trace_import(
+                        "ethereum_recover_address",
+                        &[hash_ptr, signature_ptr],
+                        || do_ethereum_recover_address(env, hash_ptr, signature_ptr, v),
+                    )
+                },
+            ),
         );
 
         // Verifies a message against a signature with a public key, using the ed25519 EdDSA scheme.
@@ -158,7 +289,20 @@ where
         // Ownership of input pointers is not transferred to the host.
         env_imports.insert(
             "ed25519_verify",
-            Function::new_native_with_env(store, env.clone(), do_ed25519_verify),
+            Function::new_native_with_env(
+                store,
+                env.clone(),
+                |env: &Environment<A, S, Q>,
+                 message_ptr: u32,
+                 signature_ptr: u32,
+                 pubkey_ptr: u32| {
+                    env.trace_import(
+                        "ed25519_verify",
+                        &[message_ptr, signature_ptr, pubkey_ptr],
+                        || do_ed25519_verify(env, message_ptr, signature_ptr, pubkey_ptr),
+                    )
+                },
+            ),
         );
 
         // Verifies a batch of messages against a batch of signatures with a batch of public keys,
@@ -168,7 +312,125 @@ where
         // Ownership of input pointers is not transferred to the host.
         env_imports.insert(
             "ed25519_batch_verify",
-            Function::new_native_with_env(store, env.clone(), do_ed25519_batch_verify),
+            Function::new_native_with_env(
+                store,
+                env.clone(),
+                |env: &Environment<A, S, Q>,
+                 messages_ptr: u32,
+                 signatures_ptr: u32,
+                 public_keys_ptr: u32| {
+                    env.trace_import(
+                        "ed25519_batch_verify",
+                        &[messages_ptr, signatures_ptr, public_keys_ptr],
+                        || {
+                            do_ed25519_batch_verify(
+                                env,
+                                messages_ptr,
+                                signatures_ptr,
+                                public_keys_ptr,
+                            )
+                        },
+                    )
+                },
+            ),
+        );
+
+        // Verifies a message against a signature with a public key, using the sr25519 Schnorr scheme
+        // in the "substrate" signing context.
+        // Returns 0 on verification success, 1 on verification failure, and values greater than 1 in case of error.
+        // Ownership of input pointers is not transferred to the host.
+        env_imports.insert(
+            "sr25519_verify",
+            Function::new_native_with_env(
+                store,
+                env.clone(),
+                |env: &Environment<A, S, Q>,
+                 message_ptr: u32,
+                 signature_ptr: u32,
+                 pubkey_ptr: u32| {
+                    env.trace_import(
+                        "sr25519_verify",
+                        &[message_ptr, signature_ptr, pubkey_ptr],
+                        || do_sr25519_verify(env, message_ptr, signature_ptr, pubkey_ptr),
+                    )
+                },
+            ),
+        );
+
+        // Verifies an aggregate signature of one or more messages with the public keys of the signers,
+        // using the BLS12-381 proof of possession scheme with signatures in G2.
+        // Messages and public keys are encoded as sections. There is either one message per public key
+        // or a single message signed by all public keys.
+        // Returns 0 on verification success, 1 on verification failure, and values greater than 1 in case of error.
+        // Ownership of input pointers is not transferred to the host.
+        env_imports.insert(
+            "bls12_381_aggregate_verify",
+            Function::new_native_with_env(
+                store,
+                env.clone(),
+                |env: &Environment<A, S, Q>,
+                 messages_ptr: u32,
+                 signature_ptr: u32,
+                 public_keys_ptr: u32| {
+                    env.trace_import(
+                        "bls12_381_aggregate_verify",
+                        &[messages_ptr, signature_ptr, public_keys_ptr],
+                        || {
+                            do_bls12_381_aggregate_verify(
+                                env,
+                                messages_ptr,
+                                signature_ptr,
+                                public_keys_ptr,
+                            )
+                        },
+                    )
+                },
+            ),
+        );
+
+        // Hashes the data with SHA-256.
+        // Returns a pointer to a Region containing the 32 byte hash.
+        // Ownership of the input pointer is not transferred to the host.
+        // Ownership of the output pointer is transferred to the contract.
+        env_imports.insert(
+            "sha256",
+            Function::new_native_with_env(
+                store,
+                env.clone(),
+                |env: &Environment<A, S, Q>, data_ptr: u32| {
+                    env.trace_import("sha256", &[data_ptr], || do_sha256(env, data_ptr))
+                },
+            ),
+        );
+
+        // Hashes the data with Keccak-256 as used by Ethereum.
+        // Returns a pointer to a Region containing the 32 byte hash.
+        // Ownership of the input pointer is not transferred to the host.
+        // Ownership of the output pointer is transferred to the contract.
+        env_imports.insert(
+            "keccak256",
+            Function::new_native_with_env(
+                store,
+                env.clone(),
+                |env: &Environment<A, S, Q>, data_ptr: u32| {
+                    env.trace_import("keccak256", &[data_ptr], || do_keccak256(env, data_ptr))
+                },
+            ),
+        );
+
+        // Hashes the data with BLAKE2b-256 as used by Substrate.
+        // Returns a pointer to a Region containing the 32 byte hash.
+        // Ownership of the input pointer is not transferred to the host.
+        // Ownership of the output pointer is transferred to the contract.
+        env_imports.insert(
+            "blake2b_256",
+            Function::new_native_with_env(
+                store,
+                env.clone(),
+                |env: &Environment<A, S, Q>, data_ptr: u32| {
+                    env.trace_import("blake2b_256", &[data_ptr], || do_blake2b_256(env, data_ptr))
+                },
+            ),
         );
 
         // Allows the contract to emit debug logs that the host can either process or ignore.
@@ -177,7 +439,13 @@ where
         // Ownership of both input and output pointer is not transferred to the host.
         env_imports.insert(
             "debug",
-            Function::new_native_with_env(store, env.clone(), do_debug),
+            Function::new_native_with_env(
+                store,
+                env.clone(),
+                |env: &Environment<A, S, Q>, message_ptr: u32| {
+                    env.trace_import("debug", &[message_ptr], || do_debug(env, message_ptr))
+                },
+            ),
         );
 
         // Aborts the contract execution with an error message provided by the contract.
@@ -185,12 +453,26 @@ where
         // Ownership of both input and output pointer is not transferred to the host.
         env_imports.insert(
             "abort",
-            Function::new_native_with_env(store, env.clone(), do_abort),
+            Function::new_native_with_env(
+                store,
+                env.clone(),
+                |env: &Environment<A, S, Q>, message_ptr: u32| {
+                    env.trace_import("abort", &[message_ptr], || do_abort(env, message_ptr))
+                },
+            ),
         );
 
         env_imports.insert(
             "query_chain",
-            Function::new_native_with_env(store, env.clone(), do_query_chain),
+            Function::new_native_with_env(
+                store,
+                env.clone(),
+                |env: &Environment<A, S, Q>, request_ptr: u32| {
+                    env.trace_import("query_chain", &[request_ptr], || {
+                        do_query_chain(env, request_ptr)
+                    })
+                },
+            ),
         );
 
         // Creates an iterator that will go from start to end.
@@ -202,7 +484,15 @@ where
         #[cfg(feature = "iterator")]
         env_imports.insert(
             "db_scan",
-            Function::new_native_with_env(store, env.clone(), do_db_scan),
+            Function::new_native_with_env(
+                store,
+                env.clone(),
+                |env: &Environment<A, S, Q>, start_ptr: u32, end_ptr: u32, order: i32| {
+                    env.trace_import("db_scan", &[start_ptr, end_ptr], || {
+                        do_db_scan(env, start_ptr, end_ptr, order)
+                    })
+                },
+            ),
         );
 
         // Get next element of iterator with ID `iterator_id`.
@@ -213,7 +503,13 @@ where
         #[cfg(feature = "iterator")]
         env_imports.insert(
             "db_next",
-            Function::new_native_with_env(store, env.clone(), do_db_next),
+            Function::new_native_with_env(
+                store,
+                env.clone(),
+                |env: &Environment<A, S, Q>, iterator_id: u32| {
+                    env.trace_import("db_next", &[], || do_db_next(env, iterator_id))
+                },
+            ),
         );
 
         import_obj.register("env", env_imports);
@@ -306,6 +602,19 @@ where
         }
     }
 
+    /// Enables or disables tracing of the import calls made by the contract. This is opt-in
+    /// since recording every call adds overhead. Enabling starts a new, empty trace.
+    pub fn set_import_tracing(&mut self, enabled: bool) {
+        self.env.set_import_tracing(enabled);
+    }
+
+    /// Returns the import calls recorded since tracing was enabled or the trace was last
+    /// taken, in call order. Take the trace after every call to get the calls of a single
+    /// entry point, analogous to a [`GasReport`].
+    pub fn take_import_trace(&mut self) -> Vec<ImportCall> {
+        self.env.take_import_trace()
+    }
+
     /// Sets the readonly storage flag on this instance. Since one instance can be used
     /// for multiple calls in integration tests, this should be set to the desired value
     /// right before every call.
@@ -371,6 +680,7 @@ pub fn instance_from_module<A, S, Q>(
     backend: Backend<A, S, Q>,
     gas_limit: u64,
     print_debug: bool,
+    gas_config: GasConfig,
     extra_imports: Option<HashMap<&str, Exports>>,
 ) -> VmResult<Instance<A, S, Q>>
 where
@@ -378,7 +688,15 @@ where
     S: Storage + 'static, // 'static is needed here to allow using this in an Environment that is cloned into closures
     Q: Querier + 'static,
 {
-    Instance::from_module(module, backend, gas_limit, print_debug, extra_imports, None)
+    Instance::from_module(
+        module,
+        backend,
+        gas_limit,
+        print_debug,
+        gas_config,
+        extra_imports,
+        None,
+    )
 }
 
 #[cfg(test)]
@@ -389,12 +707,14 @@ mod tests {
     use super::*;
     use crate::backend::Storage;
     use crate::calls::{call_execute, call_instantiate, call_query};
+    use crate::environment::ImportGasCost;
     use crate::errors::VmError;
     use crate::testing::{
         mock_backend, mock_env, mock_info, mock_instance, mock_instance_options,
         mock_instance_with_balances, mock_instance_with_failing_api, mock_instance_with_gas_limit,
         mock_instance_with_options, MockInstanceOptions,
     };
+    use crate::wasm_backend::compile;
     use cosmwasm_std::{
         coin, coins, from_binary, AllBalanceResponse, BalanceResponse, BankQuery, Empty,
         QueryRequest,
@@ -476,6 +796,7 @@ mod tests {
             backend,
             instance_options.gas_limit,
             false,
+            instance_options.gas_config.unwrap_or_default(),
             Some(extra_imports),
             None,
         )
@@ -715,6 +1036,56 @@ mod tests {
         );
     }
 
+    #[test]
+    fn import_tracing_works() {
+        let mut instance = mock_instance(CONTRACT, &[]);
+
+        // disabled by default
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let msg = br#"{"verifier": "verifies", "beneficiary": "benefits"}"#;
+        call_instantiate::<_, _, _, Empty>(&mut instance, &mock_env(), &info, msg)
+            .unwrap()
+            .unwrap();
+        assert_eq!(instance.take_import_trace(), vec![]);
+
+        instance.set_import_tracing(true);
+        let report_before = instance.create_gas_report();
+        call_instantiate::<_, _, _, Empty>(&mut instance, &mock_env(), &info, msg)
+            .unwrap()
+            .unwrap();
+        let report_after = instance.create_gas_report();
+        let trace = instance.take_import_trace();
+
+        assert_eq!(trace.first().unwrap().name, "debug");
+        let db_write = trace.last().unwrap();
+        assert_eq!(db_write.name, "db_write");
+        assert_eq!(db_write.arg_sizes.len(), 2);
+        assert_eq!(db_write.arg_sizes[0], b"config".len());
+        assert!(db_write.arg_sizes[1] > 0);
+
+        // all externally used gas is attributed to an import call
+        let used_externally: u64 = trace.iter().map(|call| call.used_externally).sum();
+        assert_eq!(
+            used_externally,
+            report_after.used_externally - report_before.used_externally
+        );
+        let used_internally: u64 = trace.iter().map(|call| call.used_internally).sum();
+        assert!(used_internally < report_after.used_internally - report_before.used_internally);
+
+        // taking resets the trace but tracing stays enabled
+        assert_eq!(instance.take_import_trace(), vec![]);
+        call_instantiate::<_, _, _, Empty>(&mut instance, &mock_env(), &info, msg)
+            .unwrap()
+            .unwrap();
+        assert_eq!(instance.take_import_trace().len(), trace.len());
+
+        instance.set_import_tracing(false);
+        call_instantiate::<_, _, _, Empty>(&mut instance, &mock_env(), &info, msg)
+            .unwrap()
+            .unwrap();
+        assert_eq!(instance.take_import_trace(), vec![]);
+    }
+
     #[test]
     fn set_storage_readonly_works() {
         let mut instance = mock_instance(CONTRACT, &[]);
@@ -899,6 +1270,44 @@ mod tests {
         assert_eq!(init_used, 5775750271);
     }
 
+    #[test]
+    fn from_code_uses_gas_config() {
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let msg = br#"{"verifier": "verifies", "beneficiary": "benefits"}"#;
+        let (options, memory_limit) = mock_instance_options();
+        let db_write_cost = 1_000_000_000;
+        let expensive_options = InstanceOptions {
+            gas_config: Some(GasConfig {
+                db_write_cost: ImportGasCost {
+                    per_call: db_write_cost,
+                    per_byte: 0,
+                },
+                ..GasConfig::default()
+            }),
+            ..options
+        };
+
+        let mut instance =
+            Instance::from_code(CONTRACT, mock_backend(&[]), options, memory_limit).unwrap();
+        let orig_gas = instance.get_gas_left();
+        call_instantiate::<_, _, _, Empty>(&mut instance, &mock_env(), &info, msg)
+            .unwrap()
+            .unwrap();
+        let default_used = orig_gas - instance.get_gas_left();
+
+        let mut instance =
+            Instance::from_code(CONTRACT, mock_backend(&[]), expensive_options, memory_limit)
+                .unwrap();
+        let orig_gas = instance.get_gas_left();
+        call_instantiate::<_, _, _, Empty>(&mut instance, &mock_env(), &info, msg)
+            .unwrap()
+            .unwrap();
+        let expensive_used = orig_gas - instance.get_gas_left();
+
+        // instantiate stores the config at least once
+        assert!(expensive_used >= default_used + db_write_cost);
+    }
+
     #[test]
     fn contract_deducts_gas_execute() {
         let mut instance = mock_instance(CONTRACT, &[]);
@@ -19,6 +19,7 @@ mod serde;
 mod size;
 mod static_analysis;
 pub mod testing;
+mod transactional;
 mod wasm_backend;
 
 pub use crate::backend::{
@@ -38,6 +39,8 @@ pub use crate::calls::{
     call_ibc_packet_receive_raw, call_ibc_packet_timeout, call_ibc_packet_timeout_raw,
 };
 pub use crate::checksum::Checksum;
+pub use crate::compatibility::WasmLimits;
+pub use crate::environment::{GasConfig, ImportCall, ImportGasCost};
 pub use crate::errors::{
     CommunicationError, CommunicationResult, RegionValidationError, RegionValidationResult,
     VmError, VmResult,
@@ -46,6 +49,9 @@ pub use crate::features::features_from_csv;
 pub use crate::instance::{GasReport, Instance, InstanceOptions};
 pub use crate::serde::{from_slice, to_vec};
 pub use crate::size::Size;
+pub use crate::static_analysis::Limits;
+pub use crate::transactional::TransactionalStorage;
+pub use crate::wasm_backend::{GatekeeperPolicy, FLOATS_FEATURE};
 
 #[doc(hidden)]
 pub mod internals {
@@ -54,7 +60,7 @@ pub mod internals {
     //! Please don't use any of these types directly, as
     //! they might change frequently or be removed in the future.
 
-    pub use crate::compatibility::check_wasm;
+    pub use crate::compatibility::{check_wasm, check_wasm_with_limits};
     pub use crate::instance::instance_from_module;
-    pub use crate::wasm_backend::{compile, make_runtime_store};
+    pub use crate::wasm_backend::{compile, compile_with_policy, make_runtime_store};
 }
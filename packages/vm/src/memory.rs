@@ -72,6 +72,12 @@ pub fn maybe_read_region(
     }
 }
 
+/// Returns the number of bytes used in the Region at ptr without copying the data
+pub fn region_length(memory: &wasmer::Memory, ptr: u32) -> VmResult<usize> {
+    let region = get_region(memory, ptr)?;
+    Ok(region.length as usize)
+}
+
 /// A prepared and sufficiently large memory Region is expected at ptr that points to pre-allocated memory.
 ///
 /// Returns number of bytes written on success.
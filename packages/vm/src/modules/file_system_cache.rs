@@ -1,14 +1,19 @@
+use std::collections::HashMap;
 use std::fs;
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use wasmer::{DeserializeError, Module, Store};
+use wasmer::{Module, Store};
 
 use crate::checksum::Checksum;
 use crate::errors::{VmError, VmResult};
+use crate::size::Size;
+use crate::wasm_backend::GatekeeperPolicy;
 
 use crate::modules::current_wasmer_module_version;
 
+use super::integrity::{artifact_digest, verify_artifact_digest};
+
 /// Bump this version whenever the module system changes in a way
 /// that old stored modules would be corrupt when loaded in the new system.
 /// This needs to be done e.g. when switching between the jit/native engine.
@@ -32,7 +37,19 @@ use crate::modules::current_wasmer_module_version;
 ///   Version for cosmwasm_vm 1.0.0-beta5 / wasmvm 1.0.0-beta6 that ships with Wasmer 2.1.1.
 /// - **v3**:<br>
 ///   Version for Wasmer 2.2.0 which contains a [module breaking change to 2.1.x](https://github.com/wasmerio/wasmer/pull/2747).
-const MODULE_SERIALIZATION_VERSION: &str = "v3";
+/// - **v4**:<br>
+///   Artifacts are stored with a digest that is verified on load. v3 artifacts have no digest.
+const MODULE_SERIALIZATION_VERSION: &str = "v4";
+
+/// File extension of the digests stored next to the artifacts
+const DIGEST_EXTENSION: &str = "digest";
+
+/// Size and recency of a module stored in the latest modules directory
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    size: usize,
+    last_used: u64,
+}
 
 /// Representation of a directory that contains compiled Wasm artifacts.
 pub struct FileSystemCache {
@@ -40,17 +57,42 @@ pub struct FileSystemCache {
     /// A sophisticated version of this cache might be able to read multiple input versions in the future.
     base_path: PathBuf,
     wasmer_module_version: u32,
+    /// The policy the stored modules were compiled with. Each policy uses its own directory.
+    policy: GatekeeperPolicy,
+    /// Maximum cumulative size of the stored modules in bytes. Unbounded if `None`.
+    max_size: Option<Size>,
+    /// Index of the stored modules, used to evict the least recently used ones.
+    entries: HashMap<Checksum, Entry>,
+    /// Logical clock that is incremented on every store and load
+    clock: u64,
+    /// Key of the artifact digests. Plain SHA-256 digests are used if `None`.
+    hmac_key: Option<Vec<u8>>,
 }
 
 impl FileSystemCache {
     /// Construct a new `FileSystemCache` around the specified directory.
     /// The contents of the cache are stored in sub-versioned directories.
     ///
+    /// When `max_size` is set, the least recently used modules are deleted from disk
+    /// whenever the stored modules exceed this size. Modules found on disk are ordered by
+    /// their modification time initially.
+    ///
+    /// Every artifact is stored with a digest that is verified when loading it. When `hmac_key`
+    /// is set, the digest is a HMAC with this key, which should be kept secret per node.
+    ///
+    /// Modules compiled under different Gatekeeper policies are stored in separate
+    /// directories, such that a module is never loaded under a policy it was not checked for.
+    ///
     /// # Safety
     ///
-    /// This method is unsafe because there's no way to ensure the artifacts
-    /// stored in this cache haven't been corrupted or tampered with.
-    pub unsafe fn new(path: impl Into<PathBuf>) -> io::Result<Self> {
+    /// This method is unsafe because without `hmac_key` there's no way to ensure the artifacts
+    /// stored in this cache haven't been tampered with. Corruption is detected in any case.
+    pub unsafe fn new(
+        path: impl Into<PathBuf>,
+        max_size: Option<Size>,
+        hmac_key: Option<Vec<u8>>,
+        policy: GatekeeperPolicy,
+    ) -> io::Result<Self> {
         let wasmer_module_version = current_wasmer_module_version();
 
         let path: PathBuf = path.into();
@@ -58,10 +100,18 @@ impl FileSystemCache {
             let metadata = path.metadata()?;
             if metadata.is_dir() {
                 if !metadata.permissions().readonly() {
-                    Ok(Self {
+                    let mut cache = Self {
                         base_path: path,
                         wasmer_module_version,
-                    })
+                        policy,
+                        max_size,
+                        entries: HashMap::new(),
+                        clock: 0,
+                        hmac_key,
+                    };
+                    cache.index_modules()?;
+                    cache.evict(None)?;
+                    Ok(cache)
                 } else {
                     // This directory is readonly.
                     Err(io::Error::new(
@@ -85,53 +135,295 @@ impl FileSystemCache {
             Ok(Self {
                 base_path: path,
                 wasmer_module_version,
+                policy,
+                max_size,
+                entries: HashMap::new(),
+                clock: 0,
+                hmac_key,
             })
         }
     }
 
+    /// Builds the index from the modules stored in the latest modules directory.
+    fn index_modules(&mut self) -> io::Result<()> {
+        let modules_dir = self.latest_modules_path();
+        if !modules_dir.is_dir() {
+            return Ok(());
+        }
+
+        let mut found = Vec::new();
+        for dir_entry in fs::read_dir(&modules_dir)? {
+            let dir_entry = dir_entry?;
+            let metadata = dir_entry.metadata()?;
+            if !metadata.is_file() {
+                continue;
+            }
+            // Ignore files not named after a checksum
+            let checksum = match dir_entry
+                .file_name()
+                .to_str()
+                .and_then(|name| Checksum::from_hex(name).ok())
+            {
+                Some(checksum) => checksum,
+                None => continue,
+            };
+            found.push((metadata.modified()?, checksum, metadata.len() as usize));
+        }
+
+        // Oldest first, such that they get the lowest clock values
+        found.sort_by_key(|(modified, _, _)| *modified);
+        for (_, checksum, size) in found {
+            self.touch(checksum, size);
+        }
+        Ok(())
+    }
+
     /// Loads a serialized module from the file system and returns a module (i.e. artifact + store),
     /// along with the size of the serialized module.
-    pub fn load(&self, checksum: &Checksum, store: &Store) -> VmResult<Option<Module>> {
-        let filename = checksum.to_hex();
-        let file_path = self.latest_modules_path().join(filename);
-
-        let result = unsafe { Module::deserialize_from_file(store, &file_path) };
-        match result {
-            Ok(module) => Ok(Some(module)),
-            Err(DeserializeError::Io(err)) => match err.kind() {
-                io::ErrorKind::NotFound => Ok(None),
-                _ => Err(VmError::cache_err(format!(
+    ///
+    /// The artifact is verified against the digest stored next to it before deserialization.
+    /// Artifacts that fail verification (including those stored without digest) are deleted and
+    /// an integrity error is returned, such that the caller can recompile the Wasm.
+    pub fn load(&mut self, checksum: &Checksum, store: &Store) -> VmResult<Option<Module>> {
+        let artifact = match self.load_artifact(checksum) {
+            Ok(Some(artifact)) => artifact,
+            Ok(None) => {
+                self.entries.remove(checksum);
+                return Ok(None);
+            }
+            Err(err @ VmError::IntegrityErr { .. }) => {
+                self.remove(checksum)?;
+                return Err(err);
+            }
+            Err(err) => return Err(err),
+        };
+
+        let module = unsafe { Module::deserialize(store, &artifact) }
+            .map_err(|e| VmError::cache_err(format!("Error deserializing module: {}", e)))?;
+        self.touch(*checksum, artifact.len());
+        Ok(Some(module))
+    }
+
+    /// Reads a serialized module from the file system and verifies it against the digest stored
+    /// next to it, without deserializing it.
+    ///
+    /// Returns `None` if the module is not stored. Artifacts that fail verification (including
+    /// those stored without digest) result in an integrity error but are kept on disk.
+    pub fn load_artifact(&self, checksum: &Checksum) -> VmResult<Option<Vec<u8>>> {
+        let artifact = match fs::read(self.module_path(checksum)) {
+            Ok(artifact) => artifact,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => {
+                return Err(VmError::cache_err(format!(
                     "Error opening module file: {}",
                     err
-                ))),
-            },
-            Err(err) => Err(VmError::cache_err(format!(
-                "Error deserializing module: {}",
-                err
-            ))),
+                )))
+            }
+        };
+
+        let stored_digest = match fs::read(self.digest_path(checksum)) {
+            Ok(digest) => Some(digest),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+            Err(err) => {
+                return Err(VmError::cache_err(format!(
+                    "Error opening module digest file: {}",
+                    err
+                )))
+            }
+        };
+        let valid = stored_digest.map_or(false, |digest| {
+            verify_artifact_digest(self.hmac_key.as_deref(), checksum, &artifact, &digest)
+        });
+        if !valid {
+            return Err(VmError::integrity_err());
         }
+        Ok(Some(artifact))
     }
 
-    /// Stores a serialized module to the file system. Returns the size of the serialized module.
+    /// Stores a serialized module to the file system, along with its digest.
     pub fn store(&mut self, checksum: &Checksum, module: &Module) -> VmResult<()> {
+        let artifact = module
+            .serialize()
+            .map_err(|e| VmError::cache_err(format!("Error serializing module: {}", e)))?;
+        // Safe because the artifact was just created by the current Wasmer version
+        unsafe { self.store_artifact(checksum, &artifact) }
+    }
+
+    /// Stores a module that was serialized elsewhere, along with its digest.
+    ///
+    /// # Safety
+    ///
+    /// The artifact is deserialized without further checks when loading it. It must be created
+    /// by the same module version (see [`FileSystemCache::module_version`]) for the same target
+    /// and come from a trusted source.
+    pub unsafe fn store_artifact(&mut self, checksum: &Checksum, artifact: &[u8]) -> VmResult<()> {
         let modules_dir = self.latest_modules_path();
         fs::create_dir_all(&modules_dir)
             .map_err(|e| VmError::cache_err(format!("Error creating directory: {}", e)))?;
-        let filename = checksum.to_hex();
-        let path = modules_dir.join(filename);
-        module
-            .serialize_to_file(path)
+        // The digest is written last. An interrupted write leaves an artifact without
+        // valid digest behind, which is treated like a corrupted one.
+        fs::write(self.module_path(checksum), artifact)
             .map_err(|e| VmError::cache_err(format!("Error writing module to disk: {}", e)))?;
-        Ok(())
+        let digest = artifact_digest(self.hmac_key.as_deref(), checksum, artifact);
+        fs::write(self.digest_path(checksum), digest).map_err(|e| {
+            VmError::cache_err(format!("Error writing module digest to disk: {}", e))
+        })?;
+        self.touch(*checksum, artifact.len());
+        self.evict(Some(checksum))
+            .map_err(|e| VmError::cache_err(format!("Error evicting modules: {}", e)))
     }
 
-    /// The path to the latest version of the modules.
-    fn latest_modules_path(&self) -> PathBuf {
-        let version = format!(
+    /// Removes a module from the file system.
+    ///
+    /// Returns true if the module was stored in this cache. Not found modules are silently ignored.
+    pub fn remove(&mut self, checksum: &Checksum) -> VmResult<bool> {
+        self.entries.remove(checksum);
+        self.remove_files(checksum)
+            .map_err(|e| VmError::cache_err(format!("Error deleting module file: {}", e)))
+    }
+
+    /// Deletes the artifact and digest of a module, returning false if the artifact did not exist.
+    fn remove_files(&self, checksum: &Checksum) -> io::Result<bool> {
+        remove_file_if_exists(&self.digest_path(checksum))?;
+        remove_file_if_exists(&self.module_path(checksum))
+    }
+
+    /// Deletes all directories of the base path that do not belong to the current module
+    /// serialization and Wasmer version, i.e. the artifacts of older (or newer)
+    /// `MODULE_SERIALIZATION_VERSION`s and Wasmer versions. Those can never be loaded again.
+    ///
+    /// The directories of other Gatekeeper policies are kept, since they may be used by
+    /// other caches sharing the base path or after a policy change.
+    ///
+    /// Returns the number of deleted directories.
+    pub fn remove_stale_versions(&mut self) -> VmResult<usize> {
+        let current = self.serialization_version();
+        let dir_entries = fs::read_dir(&self.base_path)
+            .map_err(|e| VmError::cache_err(format!("Error reading cache directory: {}", e)))?;
+        let mut removed = 0;
+        for dir_entry in dir_entries {
+            let path = dir_entry
+                .map_err(|e| VmError::cache_err(format!("Error reading cache directory: {}", e)))?
+                .path();
+            let name = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or_default();
+            // the current version without or with a policy suffix such as "-floats"
+            let is_current = match name.strip_prefix(current.as_str()) {
+                Some(suffix) => suffix.is_empty() || suffix.starts_with('-'),
+                None => false,
+            };
+            if path.is_dir() && !is_current {
+                fs::remove_dir_all(&path).map_err(|e| {
+                    VmError::cache_err(format!(
+                        "Error deleting directory {}: {}",
+                        path.display(),
+                        e
+                    ))
+                })?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Returns the version of the stored modules, which combines the module serialization
+    /// version, the Wasmer module version and the Gatekeeper policy (if not strict).
+    /// Modules of different versions are incompatible.
+    pub fn module_version(&self) -> String {
+        format!(
+            "{}{}",
+            self.serialization_version(),
+            self.policy.module_variant()
+        )
+    }
+
+    /// Returns the module version without the Gatekeeper policy, i.e. the part that
+    /// determines whether stored modules can be deserialized at all.
+    fn serialization_version(&self) -> String {
+        format!(
             "{}-wasmer{}",
             MODULE_SERIALIZATION_VERSION, self.wasmer_module_version
+        )
+    }
+
+    /// Returns the number of modules in the cache.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns the cumulative size of all modules in the cache, in bytes.
+    pub fn size(&self) -> usize {
+        self.entries.values().map(|entry| entry.size).sum()
+    }
+
+    /// Marks a module as most recently used.
+    fn touch(&mut self, checksum: Checksum, size: usize) {
+        self.clock += 1;
+        self.entries.insert(
+            checksum,
+            Entry {
+                size,
+                last_used: self.clock,
+            },
         );
-        self.base_path.join(version)
+    }
+
+    /// Deletes the least recently used modules until the cache fits into `max_size`.
+    /// The module identified by `keep` is never evicted, even if it exceeds `max_size` on its own.
+    fn evict(&mut self, keep: Option<&Checksum>) -> io::Result<()> {
+        let max_size = match self.max_size {
+            Some(max_size) => max_size.0,
+            None => return Ok(()),
+        };
+
+        let mut size = self.size();
+        if size <= max_size {
+            return Ok(());
+        }
+
+        let mut candidates: Vec<(u64, Checksum, usize)> = self
+            .entries
+            .iter()
+            .filter(|(checksum, _)| Some(*checksum) != keep)
+            .map(|(checksum, entry)| (entry.last_used, *checksum, entry.size))
+            .collect();
+        candidates.sort_unstable_by_key(|(last_used, _, _)| *last_used);
+        for (_, checksum, entry_size) in candidates {
+            if size <= max_size {
+                break;
+            }
+            self.entries.remove(&checksum);
+            self.remove_files(&checksum)?;
+            size -= entry_size;
+        }
+        Ok(())
+    }
+
+    /// The path to the artifact of a module of the latest version.
+    fn module_path(&self, checksum: &Checksum) -> PathBuf {
+        self.latest_modules_path().join(checksum.to_hex())
+    }
+
+    /// The path to the digest of a module of the latest version.
+    fn digest_path(&self, checksum: &Checksum) -> PathBuf {
+        self.latest_modules_path()
+            .join(format!("{}.{}", checksum.to_hex(), DIGEST_EXTENSION))
+    }
+
+    /// The path to the latest version of the modules.
+    fn latest_modules_path(&self) -> PathBuf {
+        self.base_path.join(self.module_version())
+    }
+}
+
+/// Deletes a file, returning false if it did not exist.
+fn remove_file_if_exists(path: &Path) -> io::Result<bool> {
+    match fs::remove_file(path) {
+        Ok(()) => Ok(true),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(err) => Err(err),
     }
 }
 
@@ -155,10 +447,36 @@ mod tests {
             i32.add))
     "#;
 
+    const OTHER_WAT: &str = r#"(module
+        (type $t0 (func (param i32) (result i32)))
+        (func $add_two (export "add_two") (type $t0) (param $p0 i32) (result i32)
+            get_local $p0
+            i32.const 2
+            i32.add))
+    "#;
+
+    const THIRD_WAT: &str = r#"(module
+        (type $t0 (func (param i32) (result i32)))
+        (func $add_six (export "add_six") (type $t0) (param $p0 i32) (result i32)
+            get_local $p0
+            i32.const 6
+            i32.add))
+    "#;
+
+    fn compile_wat(wat: &str) -> (Checksum, Module) {
+        let wasm = wat::parse_str(wat).unwrap();
+        (
+            Checksum::generate(&wasm),
+            compile(&wasm, None, &[]).unwrap(),
+        )
+    }
+
     #[test]
     fn file_system_cache_run() {
         let tmp_dir = TempDir::new().unwrap();
-        let mut cache = unsafe { FileSystemCache::new(tmp_dir.path()).unwrap() };
+        let mut cache = unsafe {
+            FileSystemCache::new(tmp_dir.path(), None, None, GatekeeperPolicy::strict()).unwrap()
+        };
 
         // Create module
         let wasm = wat::parse_str(SOME_WAT).unwrap();
@@ -194,7 +512,9 @@ mod tests {
     #[test]
     fn file_system_cache_store_uses_expected_path() {
         let tmp_dir = TempDir::new().unwrap();
-        let mut cache = unsafe { FileSystemCache::new(tmp_dir.path()).unwrap() };
+        let mut cache = unsafe {
+            FileSystemCache::new(tmp_dir.path(), None, None, GatekeeperPolicy::strict()).unwrap()
+        };
 
         // Create module
         let wasm = wat::parse_str(SOME_WAT).unwrap();
@@ -205,10 +525,347 @@ mod tests {
         cache.store(&checksum, &module).unwrap();
 
         let file_path = format!(
-            "{}/v3-wasmer1/{}",
+            "{}/v4-wasmer1/{}",
             tmp_dir.path().to_string_lossy(),
             checksum
         );
         let _serialized_module = fs::read(file_path).unwrap();
     }
+
+    #[test]
+    fn file_system_cache_separates_gatekeeper_policies() {
+        let tmp_dir = TempDir::new().unwrap();
+        let policy = GatekeeperPolicy::preset("reference-types").unwrap();
+        let mut cache =
+            unsafe { FileSystemCache::new(tmp_dir.path(), None, None, policy).unwrap() };
+        assert_eq!(
+            cache.module_version(),
+            "v4-wasmer1-bulk-memory-reference-types"
+        );
+
+        let wasm = wat::parse_str(SOME_WAT).unwrap();
+        let checksum = Checksum::generate(&wasm);
+        let module = compile(&wasm, None, &[]).unwrap();
+        cache.store(&checksum, &module).unwrap();
+        assert!(tmp_dir
+            .path()
+            .join("v4-wasmer1-bulk-memory-reference-types")
+            .join(checksum.to_hex())
+            .is_file());
+
+        // A cache with the strict policy does not see the module
+        let store = make_runtime_store(TESTING_MEMORY_LIMIT);
+        let mut strict_cache = unsafe {
+            FileSystemCache::new(tmp_dir.path(), None, None, GatekeeperPolicy::strict()).unwrap()
+        };
+        assert!(strict_cache.load(&checksum, &store).unwrap().is_none());
+        assert_eq!(strict_cache.len(), 0);
+    }
+
+    #[test]
+    fn file_system_cache_remove_works() {
+        let tmp_dir = TempDir::new().unwrap();
+        let mut cache = unsafe {
+            FileSystemCache::new(tmp_dir.path(), None, None, GatekeeperPolicy::strict()).unwrap()
+        };
+        let (checksum, module) = compile_wat(SOME_WAT);
+        cache.store(&checksum, &module).unwrap();
+        assert_eq!(cache.len(), 1);
+
+        assert!(cache.remove(&checksum).unwrap());
+        assert_eq!(cache.len(), 0);
+        assert_eq!(cache.size(), 0);
+        let store = make_runtime_store(TESTING_MEMORY_LIMIT);
+        assert!(cache.load(&checksum, &store).unwrap().is_none());
+
+        // removing again is a no-op
+        assert!(!cache.remove(&checksum).unwrap());
+    }
+
+    #[test]
+    fn file_system_cache_evicts_least_recently_used() {
+        let (checksum1, module1) = compile_wat(SOME_WAT);
+        let (checksum2, module2) = compile_wat(OTHER_WAT);
+        let (checksum3, module3) = compile_wat(THIRD_WAT);
+
+        // measure the module sizes in an unbounded cache
+        let tmp_dir = TempDir::new().unwrap();
+        let mut cache = unsafe {
+            FileSystemCache::new(tmp_dir.path(), None, None, GatekeeperPolicy::strict()).unwrap()
+        };
+        cache.store(&checksum1, &module1).unwrap();
+        cache.store(&checksum2, &module2).unwrap();
+        let max_size = cache.size();
+
+        // room for two modules
+        let tmp_dir = TempDir::new().unwrap();
+        let mut cache = unsafe {
+            FileSystemCache::new(
+                tmp_dir.path(),
+                Some(Size(max_size)),
+                None,
+                GatekeeperPolicy::strict(),
+            )
+            .unwrap()
+        };
+        cache.store(&checksum1, &module1).unwrap();
+        cache.store(&checksum2, &module2).unwrap();
+        assert_eq!(cache.len(), 2);
+
+        // using checksum1 makes checksum2 the least recently used module
+        let store = make_runtime_store(TESTING_MEMORY_LIMIT);
+        assert!(cache.load(&checksum1, &store).unwrap().is_some());
+        cache.store(&checksum3, &module3).unwrap();
+        assert!(cache.size() <= max_size);
+        assert!(cache.load(&checksum2, &store).unwrap().is_none());
+        assert!(cache.load(&checksum1, &store).unwrap().is_some());
+        assert!(cache.load(&checksum3, &store).unwrap().is_some());
+    }
+
+    #[test]
+    fn file_system_cache_applies_max_size_to_existing_modules() {
+        let tmp_dir = TempDir::new().unwrap();
+        let (checksum1, module1) = compile_wat(SOME_WAT);
+        let (checksum2, module2) = compile_wat(OTHER_WAT);
+        let mut cache = unsafe {
+            FileSystemCache::new(tmp_dir.path(), None, None, GatekeeperPolicy::strict()).unwrap()
+        };
+        cache.store(&checksum1, &module1).unwrap();
+        cache.store(&checksum2, &module2).unwrap();
+        let max_size = cache.size() - 1;
+
+        let cache = unsafe {
+            FileSystemCache::new(
+                tmp_dir.path(),
+                Some(Size(max_size)),
+                None,
+                GatekeeperPolicy::strict(),
+            )
+            .unwrap()
+        };
+        assert_eq!(cache.len(), 1);
+        assert!(cache.size() <= max_size);
+    }
+
+    #[test]
+    fn file_system_cache_keeps_module_larger_than_max_size() {
+        let tmp_dir = TempDir::new().unwrap();
+        let mut cache = unsafe {
+            FileSystemCache::new(
+                tmp_dir.path(),
+                Some(Size(1)),
+                None,
+                GatekeeperPolicy::strict(),
+            )
+            .unwrap()
+        };
+        let (checksum, module) = compile_wat(SOME_WAT);
+        cache.store(&checksum, &module).unwrap();
+
+        let store = make_runtime_store(TESTING_MEMORY_LIMIT);
+        assert!(cache.load(&checksum, &store).unwrap().is_some());
+    }
+
+    #[test]
+    fn file_system_cache_remove_stale_versions_works() {
+        let tmp_dir = TempDir::new().unwrap();
+        let mut cache = unsafe {
+            FileSystemCache::new(tmp_dir.path(), None, None, GatekeeperPolicy::strict()).unwrap()
+        };
+        let (checksum, module) = compile_wat(SOME_WAT);
+        cache.store(&checksum, &module).unwrap();
+
+        let stale = tmp_dir.path().join("v3-wasmer1");
+        fs::create_dir_all(&stale).unwrap();
+        fs::write(stale.join(checksum.to_hex()), b"old artifact").unwrap();
+        let stale_policy = tmp_dir.path().join("v3-wasmer1-floats");
+        fs::create_dir_all(&stale_policy).unwrap();
+        let newer_wasmer = tmp_dir.path().join("v4-wasmer10");
+        fs::create_dir_all(&newer_wasmer).unwrap();
+        let other_policy = tmp_dir.path().join("v4-wasmer1-floats");
+        fs::create_dir_all(&other_policy).unwrap();
+        fs::write(other_policy.join(checksum.to_hex()), b"floats artifact").unwrap();
+        let unrelated = tmp_dir.path().join("README");
+        fs::write(&unrelated, b"not a directory").unwrap();
+
+        assert_eq!(cache.remove_stale_versions().unwrap(), 3);
+        assert!(!stale.exists());
+        assert!(!stale_policy.exists());
+        assert!(!newer_wasmer.exists());
+        assert!(other_policy.join(checksum.to_hex()).exists());
+        assert!(unrelated.exists());
+        let store = make_runtime_store(TESTING_MEMORY_LIMIT);
+        assert!(cache.load(&checksum, &store).unwrap().is_some());
+
+        assert_eq!(cache.remove_stale_versions().unwrap(), 0);
+    }
+
+    #[test]
+    fn file_system_cache_store_writes_digest() {
+        let tmp_dir = TempDir::new().unwrap();
+        let mut cache = unsafe {
+            FileSystemCache::new(tmp_dir.path(), None, None, GatekeeperPolicy::strict()).unwrap()
+        };
+        let (checksum, module) = compile_wat(SOME_WAT);
+        cache.store(&checksum, &module).unwrap();
+
+        let artifact = fs::read(cache.module_path(&checksum)).unwrap();
+        let digest = fs::read(cache.digest_path(&checksum)).unwrap();
+        assert_eq!(digest, artifact_digest(None, &checksum, &artifact));
+        assert_eq!(
+            cache.digest_path(&checksum),
+            tmp_dir
+                .path()
+                .join("v4-wasmer1")
+                .join(format!("{}.digest", checksum))
+        );
+    }
+
+    #[test]
+    fn file_system_cache_load_rejects_corrupted_modules() {
+        let tmp_dir = TempDir::new().unwrap();
+        let mut cache = unsafe {
+            FileSystemCache::new(tmp_dir.path(), None, None, GatekeeperPolicy::strict()).unwrap()
+        };
+        let (checksum, module) = compile_wat(SOME_WAT);
+        let store = make_runtime_store(TESTING_MEMORY_LIMIT);
+
+        // corrupted artifact
+        cache.store(&checksum, &module).unwrap();
+        let mut artifact = fs::read(cache.module_path(&checksum)).unwrap();
+        let last = artifact.len() - 1;
+        artifact[last] ^= 0x01;
+        fs::write(cache.module_path(&checksum), &artifact).unwrap();
+        match cache.load(&checksum, &store).unwrap_err() {
+            VmError::IntegrityErr { .. } => {}
+            e => panic!("Unexpected error: {:?}", e),
+        }
+        // corrupted modules are deleted
+        assert!(!cache.module_path(&checksum).exists());
+        assert!(!cache.digest_path(&checksum).exists());
+        assert_eq!(cache.len(), 0);
+        assert!(cache.load(&checksum, &store).unwrap().is_none());
+
+        // missing digest, e.g. stored by an older version
+        cache.store(&checksum, &module).unwrap();
+        fs::remove_file(cache.digest_path(&checksum)).unwrap();
+        match cache.load(&checksum, &store).unwrap_err() {
+            VmError::IntegrityErr { .. } => {}
+            e => panic!("Unexpected error: {:?}", e),
+        }
+
+        // artifact of another module
+        let (other_checksum, other_module) = compile_wat(OTHER_WAT);
+        cache.store(&checksum, &module).unwrap();
+        cache.store(&other_checksum, &other_module).unwrap();
+        fs::copy(
+            cache.module_path(&other_checksum),
+            cache.module_path(&checksum),
+        )
+        .unwrap();
+        fs::copy(
+            cache.digest_path(&other_checksum),
+            cache.digest_path(&checksum),
+        )
+        .unwrap();
+        match cache.load(&checksum, &store).unwrap_err() {
+            VmError::IntegrityErr { .. } => {}
+            e => panic!("Unexpected error: {:?}", e),
+        }
+        assert!(cache.load(&other_checksum, &store).unwrap().is_some());
+    }
+
+    #[test]
+    fn file_system_cache_artifacts_can_be_moved_between_caches() {
+        let (checksum, module) = compile_wat(SOME_WAT);
+        let store = make_runtime_store(TESTING_MEMORY_LIMIT);
+
+        let source_dir = TempDir::new().unwrap();
+        let mut source = unsafe {
+            FileSystemCache::new(
+                source_dir.path(),
+                None,
+                Some(b"source key".to_vec()),
+                GatekeeperPolicy::strict(),
+            )
+            .unwrap()
+        };
+        assert!(source.load_artifact(&checksum).unwrap().is_none());
+        source.store(&checksum, &module).unwrap();
+        let artifact = source.load_artifact(&checksum).unwrap().unwrap();
+
+        // the target uses a different key
+        let target_dir = TempDir::new().unwrap();
+        let mut target = unsafe {
+            FileSystemCache::new(
+                target_dir.path(),
+                None,
+                Some(b"target key".to_vec()),
+                GatekeeperPolicy::strict(),
+            )
+            .unwrap()
+        };
+        assert_eq!(target.module_version(), source.module_version());
+        unsafe { target.store_artifact(&checksum, &artifact).unwrap() };
+        assert_eq!(target.len(), 1);
+        assert_eq!(target.load_artifact(&checksum).unwrap().unwrap(), artifact);
+        assert!(target.load(&checksum, &store).unwrap().is_some());
+
+        // load_artifact reports corrupted artifacts without deleting them
+        let mut corrupted = artifact.clone();
+        corrupted[0] ^= 0x01;
+        fs::write(target.module_path(&checksum), &corrupted).unwrap();
+        match target.load_artifact(&checksum).unwrap_err() {
+            VmError::IntegrityErr { .. } => {}
+            e => panic!("Unexpected error: {:?}", e),
+        }
+        assert!(target.module_path(&checksum).exists());
+    }
+
+    #[test]
+    fn file_system_cache_verifies_hmac() {
+        let tmp_dir = TempDir::new().unwrap();
+        let (checksum, module) = compile_wat(SOME_WAT);
+        let store = make_runtime_store(TESTING_MEMORY_LIMIT);
+
+        let mut cache = unsafe {
+            FileSystemCache::new(
+                tmp_dir.path(),
+                None,
+                Some(b"node key".to_vec()),
+                GatekeeperPolicy::strict(),
+            )
+            .unwrap()
+        };
+        cache.store(&checksum, &module).unwrap();
+        assert!(cache.load(&checksum, &store).unwrap().is_some());
+
+        // a plain digest, as written by someone who doesn't know the key, is rejected
+        let artifact = fs::read(cache.module_path(&checksum)).unwrap();
+        fs::write(
+            cache.digest_path(&checksum),
+            artifact_digest(None, &checksum, &artifact),
+        )
+        .unwrap();
+        match cache.load(&checksum, &store).unwrap_err() {
+            VmError::IntegrityErr { .. } => {}
+            e => panic!("Unexpected error: {:?}", e),
+        }
+
+        // so is a HMAC with a different key
+        cache.store(&checksum, &module).unwrap();
+        let mut cache = unsafe {
+            FileSystemCache::new(
+                tmp_dir.path(),
+                None,
+                Some(b"other key".to_vec()),
+                GatekeeperPolicy::strict(),
+            )
+            .unwrap()
+        };
+        match cache.load(&checksum, &store).unwrap_err() {
+            VmError::IntegrityErr { .. } => {}
+            e => panic!("Unexpected error: {:?}", e),
+        }
+    }
 }
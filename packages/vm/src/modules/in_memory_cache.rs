@@ -70,6 +70,15 @@ impl InMemoryCache {
         }
     }
 
+    /// Removes a module from the cache.
+    /// Not found modules are silently ignored.
+    pub fn remove(&mut self, checksum: &Checksum) -> VmResult<()> {
+        if let Some(modules) = &mut self.modules {
+            modules.pop(checksum);
+        }
+        Ok(())
+    }
+
     /// Returns the number of elements in the cache.
     pub fn len(&self) -> usize {
         self.modules
@@ -0,0 +1,137 @@
+use hmac::{Hmac, Mac, NewMac};
+use sha2::{Digest, Sha256};
+
+use crate::checksum::Checksum;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Computes the digest that is stored next to a serialized module.
+///
+/// The digest covers the checksum of the Wasm, such that a valid artifact cannot be
+/// moved to another checksum. Without a key, this is a plain SHA-256, which detects
+/// accidental corruption. With a key, this is a HMAC-SHA256, which additionally
+/// detects tampering by anyone who does not know the key.
+pub fn artifact_digest(key: Option<&[u8]>, checksum: &Checksum, artifact: &[u8]) -> [u8; 32] {
+    let checksum = Vec::<u8>::from(*checksum);
+    match key {
+        Some(key) => hmac_sha256(key, &[checksum.as_slice(), artifact])
+            .finalize()
+            .into_bytes()
+            .into(),
+        None => {
+            let mut hasher = Sha256::new();
+            hasher.update(&checksum);
+            hasher.update(artifact);
+            hasher.finalize().into()
+        }
+    }
+}
+
+/// Checks a stored digest against the one computed by [`artifact_digest`].
+///
+/// Keyed digests are compared in constant time, so that the comparison does not reveal
+/// how much of a forged digest is correct.
+pub fn verify_artifact_digest(
+    key: Option<&[u8]>,
+    checksum: &Checksum,
+    artifact: &[u8],
+    digest: &[u8],
+) -> bool {
+    match key {
+        Some(key) => {
+            let checksum = Vec::<u8>::from(*checksum);
+            hmac_sha256(key, &[checksum.as_slice(), artifact])
+                .verify(digest)
+                .is_ok()
+        }
+        None => artifact_digest(None, checksum, artifact) == digest,
+    }
+}
+
+/// HMAC-SHA256 as specified in RFC 2104 over the concatenation of `parts`
+fn hmac_sha256(key: &[u8], parts: &[&[u8]]) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+    for part in parts {
+        mac.update(part);
+    }
+    mac
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex_literal::hex;
+
+    #[test]
+    fn hmac_sha256_works() {
+        // Test vectors from RFC 4231, test cases 2 and 6
+        assert_eq!(
+            hmac_sha256(b"Jefe", &[b"what do ya want ", b"for nothing?"])
+                .finalize()
+                .into_bytes()[..],
+            hex!("5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843")
+        );
+        assert_eq!(
+            hmac_sha256(
+                &[0xaa; 131],
+                &[b"Test Using Larger Than Block-Size Key - Hash Key First"]
+            )
+            .finalize()
+            .into_bytes()[..],
+            hex!("60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54")
+        );
+    }
+
+    #[test]
+    fn artifact_digest_depends_on_all_inputs() {
+        let checksum = Checksum::generate(b"wasm");
+        let other_checksum = Checksum::generate(b"other wasm");
+        let digest = artifact_digest(None, &checksum, b"artifact");
+        assert_eq!(digest, artifact_digest(None, &checksum, b"artifact"));
+        assert_ne!(digest, artifact_digest(None, &checksum, b"artifacT"));
+        assert_ne!(digest, artifact_digest(None, &other_checksum, b"artifact"));
+
+        let keyed = artifact_digest(Some(b"node key"), &checksum, b"artifact");
+        assert_ne!(keyed, digest);
+        assert_ne!(
+            keyed,
+            artifact_digest(Some(b"other key"), &checksum, b"artifact")
+        );
+    }
+
+    #[test]
+    fn verify_artifact_digest_works() {
+        let checksum = Checksum::generate(b"wasm");
+        for key in [None, Some(&b"node key"[..])] {
+            let digest = artifact_digest(key, &checksum, b"artifact");
+            assert!(verify_artifact_digest(key, &checksum, b"artifact", &digest));
+            assert!(!verify_artifact_digest(
+                key,
+                &checksum,
+                b"artifacT",
+                &digest
+            ));
+            assert!(!verify_artifact_digest(
+                key,
+                &checksum,
+                b"artifact",
+                &digest[..31]
+            ));
+            assert!(!verify_artifact_digest(key, &checksum, b"artifact", &[]));
+        }
+
+        let digest = artifact_digest(Some(b"node key"), &checksum, b"artifact");
+        assert!(!verify_artifact_digest(
+            Some(b"other key"),
+            &checksum,
+            b"artifact",
+            &digest
+        ));
+        assert!(!verify_artifact_digest(
+            None,
+            &checksum,
+            b"artifact",
+            &digest
+        ));
+    }
+}
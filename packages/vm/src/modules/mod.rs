@@ -1,10 +1,12 @@
 mod file_system_cache;
 mod in_memory_cache;
+mod integrity;
 mod pinned_memory_cache;
 mod sized_module;
 mod versioning;
 
 pub use file_system_cache::FileSystemCache;
 pub use in_memory_cache::InMemoryCache;
+pub use integrity::artifact_digest;
 pub use pinned_memory_cache::PinnedMemoryCache;
 pub use versioning::current_wasmer_module_version;
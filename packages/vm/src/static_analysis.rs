@@ -1,7 +1,9 @@
-use parity_wasm::elements::{deserialize_buffer, Internal, Module};
+use parity_wasm::elements::{deserialize_buffer, ImportEntry, Internal, Module, ResizableLimits};
 use std::collections::HashSet;
+use wasmer::wasmparser::{self, BinaryReaderError, Parser, Payload, TypeDef};
 
 use crate::errors::{VmError, VmResult};
+use crate::wasm_backend::OperatorGroup;
 
 pub const REQUIRED_IBC_EXPORTS: &[&str] = &[
     "ibc_channel_open",
@@ -12,6 +14,40 @@ pub const REQUIRED_IBC_EXPORTS: &[&str] = &[
     "ibc_packet_timeout",
 ];
 
+/// All entry points known to the VM, i.e. the exports it may call
+pub const ENTRY_POINTS: &[&str] = &[
+    "instantiate",
+    "execute",
+    "migrate",
+    "sudo",
+    "reply",
+    "query",
+    "ibc_channel_open",
+    "ibc_channel_connect",
+    "ibc_channel_close",
+    "ibc_packet_receive",
+    "ibc_packet_ack",
+    "ibc_packet_timeout",
+];
+
+pub const INTERFACE_VERSION_PREFIX: &str = "interface_version_";
+
+/// Initial and maximum size of a memory (in Wasm pages) or table (in elements)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    pub initial: u32,
+    pub maximum: Option<u32>,
+}
+
+impl From<&ResizableLimits> for Limits {
+    fn from(limits: &ResizableLimits) -> Self {
+        Limits {
+            initial: limits.initial(),
+            maximum: limits.maximum(),
+        }
+    }
+}
+
 pub fn deserialize_wasm(wasm_code: &[u8]) -> VmResult<Module> {
     deserialize_buffer(wasm_code).map_err(|err| {
         VmError::static_validation_err(format!(
@@ -82,6 +118,121 @@ pub fn has_ibc_entry_points(module: &impl ExportInfo) -> bool {
         .all(|required| available_exports.contains(*required))
 }
 
+/// Returns the known entry points ([`ENTRY_POINTS`]) that exist as exported functions
+pub fn entry_points(module: &impl ExportInfo) -> HashSet<String> {
+    module
+        .exported_function_names(None)
+        .into_iter()
+        .filter(|name| ENTRY_POINTS.contains(&name.as_str()))
+        .collect()
+}
+
+/// Returns the full names of all imports, e.g. "env.db_read"
+pub fn import_names(module: &Module) -> HashSet<String> {
+    module
+        .import_section()
+        .map_or(HashSet::default(), |import_section| {
+            import_section
+                .entries()
+                .iter()
+                .map(full_import_name)
+                .collect()
+        })
+}
+
+pub fn full_import_name(ie: &ImportEntry) -> String {
+    format!("{}.{}", ie.module(), ie.field())
+}
+
+/// Returns the version of the interface_version_* marker export,
+/// or None if there is not exactly one such export with a numeric version.
+pub fn interface_version(module: &impl ExportInfo) -> Option<u32> {
+    let mut markers = module
+        .exported_function_names(Some(INTERFACE_VERSION_PREFIX))
+        .into_iter();
+    match (markers.next(), markers.next()) {
+        (Some(marker), None) => marker[INTERFACE_VERSION_PREFIX.len()..].parse().ok(),
+        _ => None,
+    }
+}
+
+/// Returns the limits of the first memory defined in the module
+pub fn memory_limits(module: &Module) -> Option<Limits> {
+    module
+        .memory_section()
+        .and_then(|section| section.entries().first())
+        .map(|memory| memory.limits().into())
+}
+
+/// Returns the limits of the first table defined in the module
+pub fn table_limits(module: &Module) -> Option<Limits> {
+    module
+        .table_section()
+        .and_then(|section| section.entries().first())
+        .map(|table| table.limits().into())
+}
+
+/// Returns the number of functions defined in the module (i.e. without imports)
+pub fn function_count(module: &Module) -> usize {
+    module
+        .function_section()
+        .map_or(0, |section| section.entries().len())
+}
+
+/// Returns true if the module uses floats anywhere, i.e. in types, locals,
+/// globals or instructions. Such contracts are rejected by the Gatekeeper unless
+/// deterministic floats are enabled. Instructions are classified like in the Gatekeeper,
+/// which is why this parses the Wasm with wasmparser.
+pub fn has_floats(wasm_code: &[u8]) -> VmResult<bool> {
+    find_floats(wasm_code).map_err(|err| {
+        VmError::static_validation_err(format!(
+            "Wasm bytecode could not be analyzed. Parsing error: {}",
+            err
+        ))
+    })
+}
+
+fn find_floats(wasm_code: &[u8]) -> Result<bool, BinaryReaderError> {
+    let is_float =
+        |ty: &wasmparser::Type| matches!(ty, wasmparser::Type::F32 | wasmparser::Type::F64);
+
+    for payload in Parser::new(0).parse_all(wasm_code) {
+        match payload? {
+            Payload::TypeSection(reader) => {
+                for ty in reader {
+                    if let TypeDef::Func(func) = ty? {
+                        if func.params.iter().chain(func.returns.iter()).any(is_float) {
+                            return Ok(true);
+                        }
+                    }
+                }
+            }
+            Payload::GlobalSection(reader) => {
+                for global in reader {
+                    if is_float(&global?.ty.content_type) {
+                        return Ok(true);
+                    }
+                }
+            }
+            Payload::CodeSectionEntry(body) => {
+                for local in body.get_locals_reader()? {
+                    let (_count, ty) = local?;
+                    if is_float(&ty) {
+                        return Ok(true);
+                    }
+                }
+                for operator in body.get_operators_reader()? {
+                    if OperatorGroup::of(&operator?).uses_floats() {
+                        return Ok(true);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(false)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -306,4 +457,168 @@ mod tests {
         let module = deserialize_wasm(&wasm).unwrap();
         assert!(!has_ibc_entry_points(&module));
     }
+
+    #[test]
+    fn entry_points_works() {
+        let wasm = wat::parse_str(
+            r#"(module
+                (type (func))
+                (func (type 0) nop)
+                (export "interface_version_8" (func 0))
+                (export "instantiate" (func 0))
+                (export "allocate" (func 0))
+                (export "reply" (func 0))
+                (export "ibc_channel_open" (func 0))
+            )"#,
+        )
+        .unwrap();
+        let module = deserialize_wasm(&wasm).unwrap();
+        assert_eq!(
+            entry_points(&module),
+            HashSet::from_iter(vec![
+                "instantiate".to_string(),
+                "reply".to_string(),
+                "ibc_channel_open".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn import_names_works() {
+        let wasm = wat::parse_str(
+            r#"(module
+                (import "env" "db_read" (func (param i32) (result i32)))
+                (import "env" "addr_validate" (func (param i32) (result i32)))
+            )"#,
+        )
+        .unwrap();
+        let module = deserialize_wasm(&wasm).unwrap();
+        assert_eq!(
+            import_names(&module),
+            HashSet::from_iter(vec![
+                "env.db_read".to_string(),
+                "env.addr_validate".to_string()
+            ])
+        );
+
+        let module = deserialize_wasm(&wat::parse_str("(module)").unwrap()).unwrap();
+        assert_eq!(import_names(&module), HashSet::new());
+    }
+
+    #[test]
+    fn interface_version_works() {
+        let module = deserialize_wasm(CONTRACT).unwrap();
+        assert_eq!(interface_version(&module), Some(8));
+
+        // none
+        let module = deserialize_wasm(&wat::parse_str("(module)").unwrap()).unwrap();
+        assert_eq!(interface_version(&module), None);
+
+        // more than one
+        let wasm = wat::parse_str(
+            r#"(module
+                (type (func))
+                (func (type 0) nop)
+                (export "interface_version_7" (func 0))
+                (export "interface_version_8" (func 0))
+            )"#,
+        )
+        .unwrap();
+        let module = deserialize_wasm(&wasm).unwrap();
+        assert_eq!(interface_version(&module), None);
+
+        // not a number
+        let wasm = wat::parse_str(
+            r#"(module
+                (type (func))
+                (func (type 0) nop)
+                (export "interface_version_eight" (func 0))
+            )"#,
+        )
+        .unwrap();
+        let module = deserialize_wasm(&wasm).unwrap();
+        assert_eq!(interface_version(&module), None);
+    }
+
+    #[test]
+    fn limits_and_function_count_work() {
+        let wasm = wat::parse_str(
+            r#"(module
+                (memory 3 5)
+                (table 2 funcref)
+                (func nop)
+                (func nop)
+            )"#,
+        )
+        .unwrap();
+        let module = deserialize_wasm(&wasm).unwrap();
+        assert_eq!(
+            memory_limits(&module),
+            Some(Limits {
+                initial: 3,
+                maximum: Some(5)
+            })
+        );
+        assert_eq!(
+            table_limits(&module),
+            Some(Limits {
+                initial: 2,
+                maximum: None
+            })
+        );
+        assert_eq!(function_count(&module), 2);
+
+        let module = deserialize_wasm(&wat::parse_str("(module)").unwrap()).unwrap();
+        assert_eq!(memory_limits(&module), None);
+        assert_eq!(table_limits(&module), None);
+        assert_eq!(function_count(&module), 0);
+    }
+
+    #[test]
+    fn has_floats_works() {
+        assert!(!has_floats(CONTRACT).unwrap());
+
+        // instruction
+        let wasm = wat::parse_str(
+            r#"(module
+                (func (param i32) (result i32)
+                    local.get 0
+                    f32.convert_i32_u
+                    i32.trunc_f32_u))"#,
+        )
+        .unwrap();
+        assert!(has_floats(&wasm).unwrap());
+
+        // signature
+        let wasm = wat::parse_str(r#"(module (func (param f64)))"#).unwrap();
+        assert!(has_floats(&wasm).unwrap());
+
+        // local
+        let wasm = wat::parse_str(r#"(module (func (local f32)))"#).unwrap();
+        assert!(has_floats(&wasm).unwrap());
+
+        // global
+        let wasm = wat::parse_str(r#"(module (global f64 (f64.const 1.5)))"#).unwrap();
+        assert!(has_floats(&wasm).unwrap());
+
+        // SIMD
+        let wasm = wat::parse_str(
+            r#"(module
+                (func (param v128 v128) (result v128)
+                    local.get 0
+                    local.get 1
+                    f32x4.add))"#,
+        )
+        .unwrap();
+        assert!(has_floats(&wasm).unwrap());
+        let wasm = wat::parse_str(
+            r#"(module
+                (func (param v128 v128) (result v128)
+                    local.get 0
+                    local.get 1
+                    i32x4.add))"#,
+        )
+        .unwrap();
+        assert!(!has_floats(&wasm).unwrap());
+    }
 }
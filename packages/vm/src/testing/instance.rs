@@ -1,13 +1,16 @@
 //! This file has some helpers for integration tests.
 //! They should be imported via full path to ensure there is no confusion
 //! use cosmwasm_vm::testing::X
+use cosmwasm_std::testing::AddressCodec;
 use cosmwasm_std::Coin;
 use std::collections::HashSet;
 
 use crate::compatibility::check_wasm;
+use crate::environment::GasConfig;
 use crate::features::features_from_csv;
 use crate::instance::{Instance, InstanceOptions};
 use crate::size::Size;
+use crate::wasm_backend::GatekeeperPolicy;
 use crate::{Backend, BackendApi, Querier, Storage};
 
 use super::mock::{MockApi, MOCK_CONTRACT_ADDR};
@@ -83,6 +86,8 @@ pub struct MockInstanceOptions<'a> {
     pub contract_balance: Option<&'a [Coin]>,
     /// When set, all calls to the API fail with BackendError::Unknown containing this message
     pub backend_error: Option<&'static str>,
+    /// When set, the API uses this address format instead of the fake default one
+    pub address_codec: Option<AddressCodec>,
 
     // instance
     pub supported_features: HashSet<String>,
@@ -90,12 +95,15 @@ pub struct MockInstanceOptions<'a> {
     pub print_debug: bool,
     /// Memory limit in bytes. Use a value that is divisible by the Wasm page size 65536, e.g. full MiBs.
     pub memory_limit: Option<Size>,
+    pub gas_config: GasConfig,
 }
 
 impl MockInstanceOptions<'_> {
     fn default_features() -> HashSet<String> {
+        // The VM implements the imports of the crypto capabilities
         #[allow(unused_mut)]
-        let mut out = features_from_csv("iterator,staking");
+        let mut out =
+            features_from_csv("iterator,staking,secp256r1,ethereum,sr25519,bls12_381,hashes");
         #[cfg(feature = "stargate")]
         out.insert("stargate".to_string());
         out
@@ -109,12 +117,14 @@ impl Default for MockInstanceOptions<'_> {
             balances: Default::default(),
             contract_balance: Default::default(),
             backend_error: None,
+            address_codec: None,
 
             // instance
             supported_features: Self::default_features(),
             gas_limit: DEFAULT_GAS_LIMIT,
             print_debug: DEFAULT_PRINT_DEBUG,
             memory_limit: DEFAULT_MEMORY_LIMIT,
+            gas_config: GasConfig::default(),
         }
     }
 }
@@ -136,11 +146,14 @@ pub fn mock_instance_with_options(
         balances.push((contract_address, contract_balance));
     }
 
-    let api = if let Some(backend_error) = options.backend_error {
+    let mut api = if let Some(backend_error) = options.backend_error {
         MockApi::new_failing(backend_error)
     } else {
         MockApi::default()
     };
+    if let Some(codec) = options.address_codec {
+        api = api.with_address_codec(codec);
+    }
 
     let backend = Backend {
         api,
@@ -148,11 +161,13 @@ pub fn mock_instance_with_options(
         querier: MockQuerier::new(&balances),
     };
     let memory_limit = options.memory_limit;
+    let policy = GatekeeperPolicy::default().with_features(&options.supported_features);
     let options = InstanceOptions {
         gas_limit: options.gas_limit,
         print_debug: options.print_debug,
+        gas_config: Some(options.gas_config),
     };
-    Instance::from_code(wasm, backend, options, memory_limit).unwrap()
+    Instance::from_code_with_policy(wasm, backend, options, memory_limit, policy).unwrap()
 }
 
 /// Creates InstanceOptions for testing
@@ -161,6 +176,7 @@ pub fn mock_instance_options() -> (InstanceOptions, Option<Size>) {
         InstanceOptions {
             gas_limit: DEFAULT_GAS_LIMIT,
             print_debug: DEFAULT_PRINT_DEBUG,
+            gas_config: None,
         },
         DEFAULT_MEMORY_LIMIT,
     )
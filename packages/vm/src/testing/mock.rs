@@ -1,4 +1,4 @@
-use cosmwasm_std::testing::{digit_sum, riffle_shuffle};
+use cosmwasm_std::testing::{digit_sum, riffle_shuffle, AddressCodec};
 use cosmwasm_std::{
     Addr, BlockInfo, Coin, ContractInfo, Env, MessageInfo, Timestamp, TransactionInfo,
 };
@@ -44,17 +44,22 @@ const SHUFFLES_DECODE: usize = 2;
 /// Zero-pads all human addresses to make them fit the canonical_length and
 /// trims off zeros for the reverse operation.
 /// This is not really smart, but allows us to see a difference (and consistent length for canonical adddresses).
+///
+/// When configured with an address codec, the codec is used instead.
 #[derive(Copy, Clone)]
 pub struct MockApi {
     /// Length of canonical addresses created with this API. Contracts should not make any assumtions
     /// what this value is.
     canonical_length: usize,
+    /// A real world address format to use instead of the fake one described above
+    address_codec: Option<AddressCodec>,
     /// When set, all calls to the API fail with BackendError::Unknown containing this message
     backend_error: Option<&'static str>,
 }
 
 impl MockApi {
     /// Read-only getter for `canonical_length`, which must not be changed by the caller.
+    /// This does not apply when an address codec is set.
     pub fn canonical_length(&self) -> usize {
         self.canonical_length
     }
@@ -65,12 +70,20 @@ impl MockApi {
             ..MockApi::default()
         }
     }
+
+    /// Uses the given address format for canonicalization and humanization,
+    /// such that tests accept exactly the addresses the chain accepts.
+    pub fn with_address_codec(mut self, codec: AddressCodec) -> Self {
+        self.address_codec = Some(codec);
+        self
+    }
 }
 
 impl Default for MockApi {
     fn default() -> Self {
         MockApi {
             canonical_length: CANONICAL_LENGTH,
+            address_codec: None,
             backend_error: None,
         }
     }
@@ -87,6 +100,13 @@ impl BackendApi for MockApi {
             return (Err(BackendError::unknown(backend_error)), gas_info);
         }
 
+        if let Some(codec) = self.address_codec {
+            let result = codec
+                .canonicalize(input)
+                .map_err(|err| BackendError::user_err(err.to_string()));
+            return (result, gas_info);
+        }
+
         // Dummy input validation. This is more sophisticated for formats like bech32, where format and checksum are validated.
         if normalized.len() < 3 {
             return (
@@ -125,6 +145,13 @@ impl BackendApi for MockApi {
             return (Err(BackendError::unknown(backend_error)), gas_info);
         }
 
+        if let Some(codec) = self.address_codec {
+            let result = codec
+                .humanize(canonical)
+                .map_err(|err| BackendError::user_err(err.to_string()));
+            return (result, gas_info);
+        }
+
         if canonical.len() != self.canonical_length {
             return (
                 Err(BackendError::user_err(
@@ -261,4 +288,33 @@ mod tests {
             err => panic!("Unexpected error: {:?}", err),
         }
     }
+
+    #[test]
+    fn canonicalize_and_humanize_works_with_address_codec() {
+        let api = MockApi::default().with_address_codec(AddressCodec::Hex);
+
+        let original = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+        let canonical = api.canonical_address(original).0.unwrap();
+        assert_eq!(canonical.len(), 20);
+        let recovered = api.human_address(&canonical).0.unwrap();
+        assert_eq!(recovered, original);
+
+        // normalizes input
+        let canonical = api
+            .canonical_address("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed")
+            .0
+            .unwrap();
+        let recovered = api.human_address(&canonical).0.unwrap();
+        assert_eq!(recovered, original);
+
+        // address accepted by the default MockApi
+        match api.canonical_address("foobar123").0.unwrap_err() {
+            BackendError::UserErr { .. } => {}
+            err => panic!("Unexpected error: {:?}", err),
+        }
+        match api.human_address(&[0x61; 32]).0.unwrap_err() {
+            BackendError::UserErr { .. } => {}
+            err => panic!("Unexpected error: {:?}", err),
+        }
+    }
 }
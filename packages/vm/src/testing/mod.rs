@@ -6,6 +6,8 @@ mod mock;
 mod querier;
 mod storage;
 
+pub use cosmwasm_std::testing::AddressCodec;
+
 pub use calls::{execute, instantiate, migrate, query, reply, sudo};
 #[cfg(feature = "stargate")]
 pub use calls::{
@@ -0,0 +1,434 @@
+#[cfg(feature = "iterator")]
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+#[cfg(feature = "iterator")]
+use std::collections::HashMap;
+#[cfg(feature = "iterator")]
+use std::ops::Bound;
+
+#[cfg(feature = "iterator")]
+use cosmwasm_std::{Order, Record};
+
+#[cfg(feature = "iterator")]
+use crate::backend::BackendError;
+use crate::backend::{BackendResult, GasInfo, Storage};
+
+/// A buffered write. `None` marks a removed key.
+type Write = (Vec<u8>, Option<Vec<u8>>);
+
+#[cfg(feature = "iterator")]
+#[derive(Debug)]
+struct Iter {
+    /// The ID of the iterator in the inner storage
+    inner_id: u32,
+    descending: bool,
+    /// The next record of the inner iterator, if it was already fetched
+    inner_next: Option<Record>,
+    inner_done: bool,
+    /// The buffered writes in range at the time the iterator was created, in iteration order
+    writes: Vec<Write>,
+    position: usize,
+}
+
+/// A storage that buffers all writes until they are committed to the inner storage.
+///
+/// This allows running a contract speculatively, e.g. to estimate the gas of an execution,
+/// and to discard its writes afterwards. Reads and iterators see the buffered writes.
+/// Like in `MockStorage`, an iterator is not affected by writes after its creation.
+///
+/// Reads from the inner storage report the gas of the inner storage. Buffered writes do not
+/// reach the inner storage before [`TransactionalStorage::commit`] and report no gas. Use
+/// [`GasConfig::db_write_cost`] and [`GasConfig::db_remove_cost`] to charge for them in a
+/// simulation.
+///
+/// [`GasConfig::db_write_cost`]: crate::GasConfig::db_write_cost
+/// [`GasConfig::db_remove_cost`]: crate::GasConfig::db_remove_cost
+#[derive(Debug)]
+pub struct TransactionalStorage<S: Storage> {
+    inner: S,
+    writes: BTreeMap<Vec<u8>, Option<Vec<u8>>>,
+    #[cfg(feature = "iterator")]
+    iterators: HashMap<u32, Iter>,
+}
+
+impl<S: Storage> TransactionalStorage<S> {
+    pub fn new(inner: S) -> Self {
+        TransactionalStorage {
+            inner,
+            writes: BTreeMap::new(),
+            #[cfg(feature = "iterator")]
+            iterators: HashMap::new(),
+        }
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Returns the inner storage. Writes that were not committed are lost.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    /// Returns true if there are writes that were neither committed nor discarded
+    pub fn has_pending_writes(&self) -> bool {
+        !self.writes.is_empty()
+    }
+
+    /// Writes all buffered writes to the inner storage in key order and returns the gas
+    /// reported by the inner storage.
+    ///
+    /// Stops at the first error. The failed write and all writes after it remain buffered.
+    pub fn commit(&mut self) -> BackendResult<()> {
+        let mut gas_info = GasInfo::free();
+        let mut writes = std::mem::take(&mut self.writes).into_iter();
+        while let Some((key, value)) = writes.next() {
+            let (result, info) = match &value {
+                Some(value) => self.inner.set(&key, value),
+                None => self.inner.remove(&key),
+            };
+            gas_info += info;
+            if let Err(err) = result {
+                self.writes.insert(key, value);
+                self.writes.extend(writes);
+                return (Err(err), gas_info);
+            }
+        }
+        (Ok(()), gas_info)
+    }
+
+    /// Drops all buffered writes
+    pub fn discard(&mut self) {
+        self.writes.clear();
+    }
+}
+
+impl<S: Storage> Storage for TransactionalStorage<S> {
+    fn get(&self, key: &[u8]) -> BackendResult<Option<Vec<u8>>> {
+        match self.writes.get(key) {
+            Some(value) => (Ok(value.clone()), GasInfo::free()),
+            None => self.inner.get(key),
+        }
+    }
+
+    #[cfg(feature = "iterator")]
+    fn scan(
+        &mut self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> BackendResult<u32> {
+        let (result, gas_info) = self.inner.scan(start, end, order);
+        let inner_id = match result {
+            Ok(inner_id) => inner_id,
+            Err(err) => return (Err(err), gas_info),
+        };
+
+        // Like in `MockStorage`, the buffered writes in range are copied such that the iterator
+        // is not affected by later writes. This is linear in their number and size.
+        let writes: Vec<Write> = match (start, end) {
+            // BTreeMap.range panics if range is start > end.
+            // However, this cases represent just empty range and we treat it as such.
+            (Some(start), Some(end)) if start > end => Vec::new(),
+            _ => {
+                let bounds = (
+                    start.map_or(Bound::Unbounded, Bound::Included),
+                    end.map_or(Bound::Unbounded, Bound::Excluded),
+                );
+                let range = self.writes.range::<[u8], _>(bounds);
+                match order {
+                    Order::Ascending => range.map(clone_write).collect(),
+                    Order::Descending => range.rev().map(clone_write).collect(),
+                }
+            }
+        };
+
+        let last_id: u32 = self
+            .iterators
+            .len()
+            .try_into()
+            .expect("Found more iterator IDs than supported");
+        let new_id = last_id + 1;
+        let iter = Iter {
+            inner_id,
+            descending: matches!(order, Order::Descending),
+            inner_next: None,
+            inner_done: false,
+            writes,
+            position: 0,
+        };
+        self.iterators.insert(new_id, iter);
+
+        (Ok(new_id), gas_info)
+    }
+
+    #[cfg(feature = "iterator")]
+    fn next(&mut self, iterator_id: u32) -> BackendResult<Option<Record>> {
+        let iter = match self.iterators.get_mut(&iterator_id) {
+            Some(i) => i,
+            None => {
+                return (
+                    Err(BackendError::iterator_does_not_exist(iterator_id)),
+                    GasInfo::free(),
+                )
+            }
+        };
+
+        let mut gas_info = GasInfo::free();
+        loop {
+            if iter.inner_next.is_none() && !iter.inner_done {
+                let (result, info) = self.inner.next(iter.inner_id);
+                gas_info += info;
+                match result {
+                    Ok(Some(record)) => iter.inner_next = Some(record),
+                    Ok(None) => iter.inner_done = true,
+                    Err(err) => return (Err(err), gas_info),
+                }
+            }
+
+            // Less means the inner record comes first, Greater means the buffered write comes first
+            let ordering = match (&iter.inner_next, iter.writes.get(iter.position)) {
+                (None, None) => return (Ok(None), gas_info),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (Some((inner_key, _)), Some((write_key, _))) if iter.descending => {
+                    write_key.cmp(inner_key)
+                }
+                (Some((inner_key, _)), Some((write_key, _))) => inner_key.cmp(write_key),
+            };
+            if ordering == Ordering::Less {
+                return (Ok(iter.inner_next.take()), gas_info);
+            }
+            if ordering == Ordering::Equal {
+                // The inner record is overridden by the buffered write
+                iter.inner_next = None;
+            }
+            let (key, value) = iter.writes[iter.position].clone();
+            iter.position += 1;
+            if let Some(value) = value {
+                return (Ok(Some((key, value))), gas_info);
+            }
+            // The key was removed, continue with the next one
+        }
+    }
+
+    fn set(&mut self, key: &[u8], value: &[u8]) -> BackendResult<()> {
+        self.writes.insert(key.to_vec(), Some(value.to_vec()));
+        (Ok(()), GasInfo::free())
+    }
+
+    fn remove(&mut self, key: &[u8]) -> BackendResult<()> {
+        self.writes.insert(key.to_vec(), None);
+        (Ok(()), GasInfo::free())
+    }
+}
+
+#[cfg(feature = "iterator")]
+fn clone_write((key, value): (&Vec<u8>, &Option<Vec<u8>>)) -> Write {
+    (key.clone(), value.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calls::call_instantiate;
+    use crate::testing::{
+        mock_env, mock_info, mock_instance_options, MockApi, MockQuerier, MockStorage,
+        MOCK_CONTRACT_ADDR,
+    };
+    use crate::{Backend, Instance};
+    use cosmwasm_std::{coins, Empty};
+
+    static CONTRACT: &[u8] = include_bytes!("../testdata/hackatom.wasm");
+
+    fn prepared_storage() -> TransactionalStorage<MockStorage> {
+        let mut inner = MockStorage::new();
+        inner.set(b"foo", b"bar").0.unwrap();
+        inner.set(b"food", b"bank").0.unwrap();
+        TransactionalStorage::new(inner)
+    }
+
+    #[test]
+    fn get_sees_buffered_writes() {
+        let mut store = prepared_storage();
+        assert!(!store.has_pending_writes());
+
+        store.set(b"foo", b"baz").0.unwrap();
+        store.remove(b"food").0.unwrap();
+        store.set(b"new", b"value").0.unwrap();
+        assert!(store.has_pending_writes());
+
+        assert_eq!(store.get(b"foo").0.unwrap(), Some(b"baz".to_vec()));
+        assert_eq!(store.get(b"food").0.unwrap(), None);
+        assert_eq!(store.get(b"new").0.unwrap(), Some(b"value".to_vec()));
+
+        // inner storage is unchanged
+        assert_eq!(store.inner().get(b"foo").0.unwrap(), Some(b"bar".to_vec()));
+        assert_eq!(
+            store.inner().get(b"food").0.unwrap(),
+            Some(b"bank".to_vec())
+        );
+        assert_eq!(store.inner().get(b"new").0.unwrap(), None);
+    }
+
+    #[test]
+    fn get_reports_gas_of_inner_storage() {
+        let mut store = prepared_storage();
+        let (_, gas_info) = store.get(b"foo");
+        assert_eq!(gas_info, GasInfo::with_externally_used(3));
+
+        store.set(b"foo", b"baz").0.unwrap();
+        let (_, gas_info) = store.get(b"foo");
+        assert_eq!(gas_info, GasInfo::free());
+    }
+
+    #[test]
+    fn commit_works() {
+        let mut store = prepared_storage();
+        store.set(b"foo", b"baz").0.unwrap();
+        store.remove(b"food").0.unwrap();
+
+        let (result, gas_info) = store.commit();
+        result.unwrap();
+        assert_eq!(
+            gas_info,
+            GasInfo::with_externally_used(b"foobaz".len() as u64 + b"food".len() as u64)
+        );
+        assert!(!store.has_pending_writes());
+
+        let inner = store.into_inner();
+        assert_eq!(inner.get(b"foo").0.unwrap(), Some(b"baz".to_vec()));
+        assert_eq!(inner.get(b"food").0.unwrap(), None);
+    }
+
+    #[test]
+    fn discard_works() {
+        let mut store = prepared_storage();
+        store.set(b"foo", b"baz").0.unwrap();
+        store.remove(b"food").0.unwrap();
+
+        store.discard();
+        assert!(!store.has_pending_writes());
+        assert_eq!(store.get(b"foo").0.unwrap(), Some(b"bar".to_vec()));
+        assert_eq!(store.get(b"food").0.unwrap(), Some(b"bank".to_vec()));
+
+        // nothing left to commit
+        store.commit().0.unwrap();
+        let inner = store.into_inner();
+        assert_eq!(inner.get(b"foo").0.unwrap(), Some(b"bar".to_vec()));
+    }
+
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn iterator_merges_buffered_writes() {
+        let mut store = prepared_storage();
+        store.set(b"ant", b"hill").0.unwrap();
+        store.set(b"foo", b"baz").0.unwrap();
+        store.remove(b"food").0.unwrap();
+        store.set(b"ze", b"bra").0.unwrap();
+        // removing a key that does not exist is a no-op
+        store.remove(b"bye").0.unwrap();
+
+        let collect = |store: &mut TransactionalStorage<MockStorage>, iter_id: u32| {
+            let mut out = Vec::new();
+            while let Some(record) = store.next(iter_id).0.unwrap() {
+                out.push(record);
+            }
+            out
+        };
+
+        let iter_id = store.scan(None, None, Order::Ascending).0.unwrap();
+        assert_eq!(
+            collect(&mut store, iter_id),
+            vec![
+                (b"ant".to_vec(), b"hill".to_vec()),
+                (b"foo".to_vec(), b"baz".to_vec()),
+                (b"ze".to_vec(), b"bra".to_vec()),
+            ]
+        );
+
+        let iter_id = store.scan(None, None, Order::Descending).0.unwrap();
+        assert_eq!(
+            collect(&mut store, iter_id),
+            vec![
+                (b"ze".to_vec(), b"bra".to_vec()),
+                (b"foo".to_vec(), b"baz".to_vec()),
+                (b"ant".to_vec(), b"hill".to_vec()),
+            ]
+        );
+
+        let iter_id = store
+            .scan(Some(b"f"), Some(b"z"), Order::Ascending)
+            .0
+            .unwrap();
+        assert_eq!(
+            collect(&mut store, iter_id),
+            vec![(b"foo".to_vec(), b"baz".to_vec())]
+        );
+
+        // start > end is an empty range
+        let iter_id = store
+            .scan(Some(b"z"), Some(b"f"), Order::Ascending)
+            .0
+            .unwrap();
+        assert_eq!(collect(&mut store, iter_id), Vec::<Record>::new());
+    }
+
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn iterator_is_not_affected_by_later_writes() {
+        let mut store = prepared_storage();
+        let iter_id = store.scan(None, None, Order::Ascending).0.unwrap();
+
+        store.set(b"ant", b"hill").0.unwrap();
+        store.remove(b"food").0.unwrap();
+
+        assert_eq!(
+            store.next(iter_id).0.unwrap(),
+            Some((b"foo".to_vec(), b"bar".to_vec()))
+        );
+        assert_eq!(
+            store.next(iter_id).0.unwrap(),
+            Some((b"food".to_vec(), b"bank".to_vec()))
+        );
+        assert_eq!(store.next(iter_id).0.unwrap(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn next_errors_for_unknown_iterator() {
+        let mut store = prepared_storage();
+        let (result, gas_info) = store.next(42);
+        assert_eq!(
+            result.unwrap_err(),
+            BackendError::iterator_does_not_exist(42)
+        );
+        assert_eq!(gas_info, GasInfo::free());
+    }
+
+    #[test]
+    fn dry_run_works_with_instance() {
+        let backend = Backend {
+            api: MockApi::default(),
+            storage: TransactionalStorage::new(MockStorage::new()),
+            querier: MockQuerier::<Empty>::new(&[(MOCK_CONTRACT_ADDR, &coins(1000, "earth"))]),
+        };
+        let (instance_options, memory_limit) = mock_instance_options();
+        let mut instance =
+            Instance::from_code(CONTRACT, backend, instance_options, memory_limit).unwrap();
+
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let msg = br#"{"verifier": "verifies", "beneficiary": "benefits"}"#;
+        call_instantiate::<_, _, _, Empty>(&mut instance, &mock_env(), &info, msg)
+            .unwrap()
+            .unwrap();
+
+        let backend = instance.recycle().unwrap();
+        let mut storage = backend.storage;
+        assert!(storage.has_pending_writes());
+        assert!(storage.get(b"config").0.unwrap().is_some());
+
+        storage.discard();
+        assert_eq!(storage.into_inner().get(b"config").0.unwrap(), None);
+    }
+}
@@ -5,6 +5,7 @@ use wasmer::{Module, ModuleMiddleware};
 use crate::errors::VmResult;
 use crate::size::Size;
 
+use super::gatekeeper::GatekeeperPolicy;
 use super::store::make_compile_time_store;
 
 /// Compiles a given Wasm bytecode into a module.
@@ -16,7 +17,17 @@ pub fn compile(
     memory_limit: Option<Size>,
     middlewares: &[Arc<dyn ModuleMiddleware>],
 ) -> VmResult<Module> {
-    let store = make_compile_time_store(memory_limit, middlewares);
+    compile_with_policy(code, memory_limit, middlewares, GatekeeperPolicy::strict())
+}
+
+/// Like [`compile`] but accepts the Wasm features enabled by the given Gatekeeper policy.
+pub fn compile_with_policy(
+    code: &[u8],
+    memory_limit: Option<Size>,
+    middlewares: &[Arc<dyn ModuleMiddleware>],
+    policy: GatekeeperPolicy,
+) -> VmResult<Module> {
+    let store = make_compile_time_store(memory_limit, middlewares, policy);
     let module = Module::new(&store, code)?;
     Ok(module)
 }
@@ -32,4 +43,24 @@ mod tests {
         let err = compile(CONTRACT, None, &[]).unwrap_err();
         assert!(err.to_string().contains("Float operator detected:"));
     }
+
+    #[test]
+    fn compile_with_policy_works() {
+        let wasm = wat::parse_str(
+            r#"(module
+              (memory 1)
+              (func (param i32 i32 i32)
+                local.get 0
+                local.get 1
+                local.get 2
+                memory.copy))"#,
+        )
+        .unwrap();
+
+        let err = compile(&wasm, None, &[]).unwrap_err();
+        assert!(err.to_string().contains("Bulk memory operation detected"));
+
+        let policy = GatekeeperPolicy::preset("bulk-memory").unwrap();
+        compile_with_policy(&wasm, None, &[], policy).unwrap();
+    }
 }
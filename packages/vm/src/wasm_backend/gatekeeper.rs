@@ -5,12 +5,20 @@ use wasmer::{
     ModuleMiddleware,
 };
 
+use std::collections::HashSet;
+
+use crate::errors::{VmError, VmResult};
+
+/// The capability that enables deterministic floats, see [`GatekeeperPolicy::with_features`].
+/// Contracts that rely on floats can require it via a `requires_floats` export.
+pub const FLOATS_FEATURE: &str = "floats";
+
 #[derive(Debug, MemoryUsage, Clone, Copy)]
 struct GatekeeperConfig {
     /// True iff float operations are allowed.
     ///
-    /// Note: there are float operations in the SIMD block as well and we do not yet handle
-    /// any combination of `allow_floats` and `allow_feature_simd` properly.
+    /// Note: float operations in the SIMD block are only allowed if both `allow_floats`
+    /// and `allow_feature_simd` are set.
     allow_floats: bool,
     //
     // Standardized features
@@ -37,6 +45,149 @@ struct GatekeeperConfig {
     allow_feature_threads: bool,
 }
 
+/// The set of Wasm features a chain accepts in contracts.
+///
+/// Non-final Wasm proposals (exception handling, threads) are never allowed. Floats are
+/// only allowed in the deterministic floats mode, in which NaNs are canonicalized at compile
+/// time. SIMD operations involving floats are rejected unless both SIMD and deterministic
+/// floats are enabled, such that all policies are deterministic.
+/// Use [`GatekeeperPolicy::new`] or [`GatekeeperPolicy::preset`] to create an instance.
+#[derive(Debug, MemoryUsage, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GatekeeperPolicy {
+    bulk_memory_operations: bool,
+    reference_types: bool,
+    simd: bool,
+    deterministic_floats: bool,
+}
+
+impl GatekeeperPolicy {
+    /// Names of the presets supported by [`GatekeeperPolicy::preset`]
+    pub const PRESETS: [&'static str; 4] = ["strict", "bulk-memory", "reference-types", "simd"];
+
+    /// Creates a policy and checks that the combination of features is supported.
+    ///
+    /// Reference types require bulk memory operations (`table.init`, `elem.drop` etc. are
+    /// defined there). SIMD can only be compiled with the Cranelift backend.
+    pub fn new(bulk_memory_operations: bool, reference_types: bool, simd: bool) -> VmResult<Self> {
+        if reference_types && !bulk_memory_operations {
+            return Err(VmError::generic_err(
+                "Gatekeeper policy: reference types require bulk memory operations",
+            ));
+        }
+        if simd && !cfg!(feature = "cranelift") {
+            return Err(VmError::generic_err(
+                "Gatekeeper policy: SIMD requires the cranelift feature",
+            ));
+        }
+        Ok(Self {
+            bulk_memory_operations,
+            reference_types,
+            simd,
+            deterministic_floats: false,
+        })
+    }
+
+    /// The default policy, which allows no Wasm features beyond the MVP.
+    pub const fn strict() -> Self {
+        Self {
+            bulk_memory_operations: false,
+            reference_types: false,
+            simd: false,
+            deterministic_floats: false,
+        }
+    }
+
+    /// Creates a policy from its name. Each preset includes the features of the ones before
+    /// it in [`GatekeeperPolicy::PRESETS`].
+    pub fn preset(name: &str) -> VmResult<Self> {
+        match name {
+            "strict" => Ok(Self::strict()),
+            "bulk-memory" => Self::new(true, false, false),
+            "reference-types" => Self::new(true, true, false),
+            "simd" => Self::new(true, true, true),
+            _ => Err(VmError::generic_err(format!(
+                "Unknown Gatekeeper policy preset '{}'. Supported presets: {}",
+                name,
+                Self::PRESETS.join(", ")
+            ))),
+        }
+    }
+
+    pub fn bulk_memory_operations(&self) -> bool {
+        self.bulk_memory_operations
+    }
+
+    pub fn reference_types(&self) -> bool {
+        self.reference_types
+    }
+
+    pub fn simd(&self) -> bool {
+        self.simd
+    }
+
+    pub fn deterministic_floats(&self) -> bool {
+        self.deterministic_floats
+    }
+
+    /// Returns a copy of this policy that allows floats. All NaNs produced by float
+    /// operations are canonicalized by a middleware, which makes the results identical
+    /// on all architectures.
+    pub fn with_deterministic_floats(self) -> Self {
+        Self {
+            deterministic_floats: true,
+            ..self
+        }
+    }
+
+    /// Returns a copy of this policy adjusted to the capabilities of a chain, i.e. with
+    /// deterministic floats enabled if [`FLOATS_FEATURE`] is supported.
+    pub fn with_features(self, supported_features: &HashSet<String>) -> Self {
+        if supported_features.contains(FLOATS_FEATURE) {
+            self.with_deterministic_floats()
+        } else {
+            self
+        }
+    }
+
+    /// A short identifier of the enabled features, which is part of the module cache key.
+    /// This is empty for the strict policy such that existing caches remain valid.
+    pub(crate) fn module_variant(&self) -> String {
+        let mut out = String::new();
+        if self.bulk_memory_operations {
+            out.push_str("-bulk-memory");
+        }
+        if self.reference_types {
+            out.push_str("-reference-types");
+        }
+        if self.simd {
+            out.push_str("-simd");
+        }
+        if self.deterministic_floats {
+            out.push_str("-floats");
+        }
+        out
+    }
+}
+
+impl Default for GatekeeperPolicy {
+    fn default() -> Self {
+        Self::strict()
+    }
+}
+
+impl From<GatekeeperPolicy> for GatekeeperConfig {
+    fn from(policy: GatekeeperPolicy) -> Self {
+        GatekeeperConfig {
+            allow_floats: policy.deterministic_floats,
+            allow_feature_bulk_memory_operations: policy.bulk_memory_operations,
+            allow_feature_reference_types: policy.reference_types,
+            allow_feature_simd: policy.simd,
+            allow_feature_exception_handling: false,
+            allow_feature_threads: false,
+        }
+    }
+}
+
 /// A middleware that ensures only deterministic operations are used (i.e. no floats).
 /// It also disallows the use of Wasm features that are not explicitly enabled.
 #[derive(Debug, MemoryUsage)]
@@ -49,22 +200,20 @@ impl Gatekeeper {
     /// Creates a new Gatekeeper with a custom config.
     ///
     /// A costum configuration is potentially dangerous (non-final Wasm proposals, floats in SIMD operation).
-    /// For this reason, only [`Gatekeeper::default()`] is public.
+    /// For this reason, only [`Gatekeeper::from_policy`] and [`Gatekeeper::default()`] are public.
     fn new(config: GatekeeperConfig) -> Self {
         Self { config }
     }
+
+    /// Creates a new Gatekeeper enforcing the given policy.
+    pub fn from_policy(policy: GatekeeperPolicy) -> Self {
+        Self::new(policy.into())
+    }
 }
 
 impl Default for Gatekeeper {
     fn default() -> Self {
-        Self::new(GatekeeperConfig {
-            allow_floats: false,
-            allow_feature_bulk_memory_operations: false,
-            allow_feature_reference_types: false,
-            allow_feature_simd: false,
-            allow_feature_exception_handling: false,
-            allow_feature_threads: false,
-        })
+        Self::from_policy(GatekeeperPolicy::strict())
     }
 }
 
@@ -96,6 +245,116 @@ impl FunctionMiddleware for FunctionGatekeeper {
         operator: Operator<'a>,
         state: &mut MiddlewareReaderState<'a>,
     ) -> Result<(), MiddlewareError> {
+        match OperatorGroup::of(&operator) {
+            OperatorGroup::Base => {
+                state.push_operator(operator);
+                Ok(())
+            }
+            OperatorGroup::ReferenceTypes => {
+                if self.config.allow_feature_reference_types {
+                    state.push_operator(operator);
+                    Ok(())
+                } else {
+                    let msg = format!("Reference type operation detected: {:?}. Reference types are not supported.", operator);
+                    Err(MiddlewareError::new(MIDDLEWARE_NAME, msg))
+                }
+            }
+            OperatorGroup::Threads => {
+                if self.config.allow_feature_threads {
+                    state.push_operator(operator);
+                    Ok(())
+                } else {
+                    let msg = format!("Threads operator detected: {:?}. The Wasm Threads extension is not supported.", operator);
+                    Err(MiddlewareError::new(MIDDLEWARE_NAME, msg))
+                }
+            }
+            OperatorGroup::Simd => {
+                if self.config.allow_feature_simd {
+                    state.push_operator(operator);
+                    Ok(())
+                } else {
+                    let msg = format!(
+                        "SIMD operator detected: {:?}. The Wasm SIMD extension is not supported.",
+                        operator
+                    );
+                    Err(MiddlewareError::new(MIDDLEWARE_NAME, msg))
+                }
+            }
+            OperatorGroup::Floats => {
+                if self.config.allow_floats {
+                    state.push_operator(operator);
+                    Ok(())
+                } else {
+                    let msg = format!(
+                        "Float operator detected: {:?}. The use of floats is not supported.",
+                        operator
+                    );
+                    Err(MiddlewareError::new(MIDDLEWARE_NAME, msg))
+                }
+            }
+            OperatorGroup::FloatSimd => {
+                // Float SIMD operations are only allowed if both floats and SIMD are allowed,
+                // such that allowing SIMD alone keeps the execution deterministic.
+                if self.config.allow_floats && self.config.allow_feature_simd {
+                    state.push_operator(operator);
+                    Ok(())
+                } else if !self.config.allow_floats {
+                    let msg = format!(
+                        "Float operator detected: {:?}. The use of floats is not supported.",
+                        operator
+                    );
+                    Err(MiddlewareError::new(MIDDLEWARE_NAME, msg))
+                } else {
+                    let msg = format!(
+                        "SIMD operator detected: {:?}. The Wasm SIMD extension is not supported.",
+                        operator
+                    );
+                    Err(MiddlewareError::new(MIDDLEWARE_NAME, msg))
+                }
+            }
+            OperatorGroup::BulkMemory => {
+                if self.config.allow_feature_bulk_memory_operations {
+                    state.push_operator(operator);
+                    Ok(())
+                } else {
+                    let msg = format!("Bulk memory operation detected: {:?}. Bulk memory operations are not supported.", operator);
+                    Err(MiddlewareError::new(MIDDLEWARE_NAME, msg))
+                }
+            }
+            OperatorGroup::ExceptionHandling => {
+                if self.config.allow_feature_exception_handling {
+                    state.push_operator(operator);
+                    Ok(())
+                } else {
+                    let msg = format!("Exception handling operation detected: {:?}. Exception handling is not supported.", operator);
+                    Err(MiddlewareError::new(MIDDLEWARE_NAME, msg))
+                }
+            }
+        }
+    }
+}
+
+/// The groups of operators the Gatekeeper decides about. This is the only place that classifies
+/// operators, such that the Gatekeeper and [`has_floats`](crate::static_analysis::has_floats)
+/// agree on which operators use floats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum OperatorGroup {
+    /// Operators of the Wasm MVP that don't use floats, which are always allowed
+    Base,
+    ReferenceTypes,
+    Threads,
+    /// SIMD operators that don't use floats
+    Simd,
+    /// Scalar float operators
+    Floats,
+    /// SIMD operators with float lanes or conversions between floats and integers
+    FloatSimd,
+    BulkMemory,
+    ExceptionHandling,
+}
+
+impl OperatorGroup {
+    pub(crate) fn of(operator: &Operator) -> Self {
         match operator {
             Operator::Unreachable
             | Operator::Nop
@@ -205,10 +464,7 @@ impl FunctionMiddleware for FunctionGatekeeper {
             | Operator::I64Extend16S
             | Operator::I64ExtendI32S
             | Operator::I64Extend32S
-            | Operator::I64ExtendI32U => {
-                state.push_operator(operator);
-                Ok(())
-            }
+            | Operator::I64ExtendI32U => OperatorGroup::Base,
             Operator::RefNull { .. }
             | Operator::RefIsNull
             | Operator::RefFunc { .. }
@@ -218,15 +474,7 @@ impl FunctionMiddleware for FunctionGatekeeper {
             | Operator::TableGet { .. }
             | Operator::TableSet { .. }
             | Operator::TableGrow { .. }
-            | Operator::TableSize { .. } => {
-                if self.config.allow_feature_reference_types {
-                    state.push_operator(operator);
-                    Ok(())
-                } else {
-                    let msg = format!("Reference type operation detected: {:?}. Reference types are not supported.", operator);
-                    Err(MiddlewareError::new(MIDDLEWARE_NAME, msg))
-                }
-            }
+            | Operator::TableSize { .. } => OperatorGroup::ReferenceTypes,
             Operator::MemoryAtomicNotify { .. }
             | Operator::MemoryAtomicWait32 { .. }
             | Operator::MemoryAtomicWait64 { .. }
@@ -293,15 +541,7 @@ impl FunctionMiddleware for FunctionGatekeeper {
             | Operator::I32AtomicRmw16CmpxchgU { .. }
             | Operator::I64AtomicRmw8CmpxchgU { .. }
             | Operator::I64AtomicRmw16CmpxchgU { .. }
-            | Operator::I64AtomicRmw32CmpxchgU { .. } => {
-                if self.config.allow_feature_threads {
-                    state.push_operator(operator);
-                    Ok(())
-                } else {
-                    let msg = format!("Threads operator detected: {:?}. The Wasm Threads extension is not supported.", operator);
-                    Err(MiddlewareError::new(MIDDLEWARE_NAME, msg))
-                }
-            }
+            | Operator::I64AtomicRmw32CmpxchgU { .. } => OperatorGroup::Threads,
             Operator::V128Load { .. }
             | Operator::V128Store { .. }
             | Operator::V128Const { .. }
@@ -479,24 +719,7 @@ impl FunctionMiddleware for FunctionGatekeeper {
             | Operator::I64x2ExtMulLowI32x4S
             | Operator::I64x2ExtMulHighI32x4S
             | Operator::I64x2ExtMulLowI32x4U
-            | Operator::I64x2ExtMulHighI32x4U
-            | Operator::I32x4TruncSatF64x2SZero
-            | Operator::I32x4TruncSatF64x2UZero
-            | Operator::F64x2ConvertLowI32x4S
-            | Operator::F64x2ConvertLowI32x4U
-            | Operator::F32x4DemoteF64x2Zero
-            | Operator::F64x2PromoteLowF32x4 => {
-                if self.config.allow_feature_simd {
-                    state.push_operator(operator);
-                    Ok(())
-                } else {
-                    let msg = format!(
-                        "SIMD operator detected: {:?}. The Wasm SIMD extension is not supported.",
-                        operator
-                    );
-                    Err(MiddlewareError::new(MIDDLEWARE_NAME, msg))
-                }
-            }
+            | Operator::I64x2ExtMulHighI32x4U => OperatorGroup::Simd,
             Operator::F32Load { .. }
             | Operator::F64Load { .. }
             | Operator::F32Store { .. }
@@ -572,8 +795,8 @@ impl FunctionMiddleware for FunctionGatekeeper {
             | Operator::I64TruncSatF32S
             | Operator::I64TruncSatF32U
             | Operator::I64TruncSatF64S
-            | Operator::I64TruncSatF64U
-            | Operator::F32x4Splat
+            | Operator::I64TruncSatF64U => OperatorGroup::Floats,
+            Operator::F32x4Splat
             | Operator::F32x4ExtractLane { .. }
             | Operator::F32x4ReplaceLane { .. }
             | Operator::F64x2Splat
@@ -624,18 +847,13 @@ impl FunctionMiddleware for FunctionGatekeeper {
             | Operator::I32x4TruncSatF32x4S
             | Operator::I32x4TruncSatF32x4U
             | Operator::F32x4ConvertI32x4S
-            | Operator::F32x4ConvertI32x4U => {
-                if self.config.allow_floats {
-                    state.push_operator(operator);
-                    Ok(())
-                } else {
-                    let msg = format!(
-                        "Float operator detected: {:?}. The use of floats is not supported.",
-                        operator
-                    );
-                    Err(MiddlewareError::new(MIDDLEWARE_NAME, msg))
-                }
-            }
+            | Operator::F32x4ConvertI32x4U
+            | Operator::I32x4TruncSatF64x2SZero
+            | Operator::I32x4TruncSatF64x2UZero
+            | Operator::F64x2ConvertLowI32x4S
+            | Operator::F64x2ConvertLowI32x4U
+            | Operator::F32x4DemoteF64x2Zero
+            | Operator::F64x2PromoteLowF32x4 => OperatorGroup::FloatSimd,
             Operator::MemoryInit { .. }
             | Operator::DataDrop { .. }
             | Operator::MemoryCopy { .. }
@@ -643,39 +861,27 @@ impl FunctionMiddleware for FunctionGatekeeper {
             | Operator::TableInit { .. }
             | Operator::ElemDrop { .. }
             | Operator::TableCopy { .. }
-            | Operator::TableFill { .. } => {
-                if self.config.allow_feature_bulk_memory_operations {
-                    state.push_operator(operator);
-                    Ok(())
-                } else {
-                    let msg = format!("Bulk memory operation detected: {:?}. Bulk memory operations are not supported.", operator);
-                    Err(MiddlewareError::new(MIDDLEWARE_NAME, msg))
-                }
-            }
+            | Operator::TableFill { .. } => OperatorGroup::BulkMemory,
             Operator::Try { .. }
             | Operator::Catch { .. }
             | Operator::Throw { .. }
             | Operator::Rethrow { .. }
             | Operator::Unwind { .. }
             | Operator::Delegate { .. }
-            | Operator::CatchAll => {
-                if self.config.allow_feature_exception_handling {
-                    state.push_operator(operator);
-                    Ok(())
-                } else {
-                    let msg = format!("Exception handling operation detected: {:?}. Exception handling is not supported.", operator);
-                    Err(MiddlewareError::new(MIDDLEWARE_NAME, msg))
-                }
-            }
+            | Operator::CatchAll => OperatorGroup::ExceptionHandling,
         }
     }
+
+    pub(crate) fn uses_floats(self) -> bool {
+        matches!(self, OperatorGroup::Floats | OperatorGroup::FloatSimd)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::sync::Arc;
-    use wasmer::{CompilerConfig, Cranelift, Module, Store, Universal};
+    use wasmer::{CompilerConfig, Cranelift, Features, Module, Store, Universal};
 
     #[test]
     fn valid_wasm_instance_sanity() {
@@ -749,4 +955,150 @@ mod tests {
             .to_string()
             .contains("Bulk memory operation"));
     }
+
+    #[test]
+    fn bulk_operations_supported_by_policy() {
+        let wasm = wat::parse_str(
+            r#"
+            (module
+              (memory (export "memory") 1)
+              (func (param $dst i32) (param $src i32) (param $size i32) (result i32)
+                local.get $dst
+                local.get $src
+                local.get $size
+                memory.copy
+                local.get $dst))
+            "#,
+        )
+        .unwrap();
+
+        let policy = GatekeeperPolicy::preset("bulk-memory").unwrap();
+        let deterministic = Arc::new(Gatekeeper::from_policy(policy));
+        let mut compiler_config = Cranelift::default();
+        compiler_config.push_middleware(deterministic);
+        let store = Store::new(&Universal::new(compiler_config).engine());
+        let result = Module::new(&store, &wasm);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn float_simd_operations_not_supported_with_simd() {
+        let wasm = wat::parse_str(
+            r#"
+            (module
+              (func (param v128 v128) (result v128)
+                local.get 0
+                local.get 1
+                i32x4.add)
+              (func (param v128 v128) (result v128)
+                local.get 0
+                local.get 1
+                f32x4.add))
+            "#,
+        )
+        .unwrap();
+
+        // Created directly since the preset requires the cranelift feature
+        let policy = GatekeeperPolicy {
+            bulk_memory_operations: true,
+            reference_types: true,
+            simd: true,
+            deterministic_floats: false,
+        };
+        let deterministic = Arc::new(Gatekeeper::from_policy(policy));
+        let mut compiler_config = Cranelift::default();
+        compiler_config.push_middleware(deterministic);
+        let mut features = Features::default();
+        features.simd(true);
+        let engine = Universal::new(compiler_config).features(features).engine();
+        let store = Store::new(&engine);
+        let result = Module::new(&store, &wasm);
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Float operator detected: F32x4Add"));
+    }
+
+    #[test]
+    fn floats_supported_with_deterministic_floats() {
+        let wasm = wat::parse_str(
+            r#"
+            (module
+              (func $to_float (param i32) (result f32)
+                get_local 0
+                f32.convert_u/i32
+              ))
+            "#,
+        )
+        .unwrap();
+
+        let policy = GatekeeperPolicy::strict().with_deterministic_floats();
+        let deterministic = Arc::new(Gatekeeper::from_policy(policy));
+        let mut compiler_config = Cranelift::default();
+        compiler_config.push_middleware(deterministic);
+        let store = Store::new(&Universal::new(compiler_config).engine());
+        let result = Module::new(&store, &wasm);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn gatekeeper_policy_with_features_works() {
+        let policy = GatekeeperPolicy::strict();
+        assert_eq!(
+            policy.with_features(&HashSet::from_iter(vec!["iterator".to_string()])),
+            policy
+        );
+        let policy = policy.with_features(&HashSet::from_iter(vec![
+            "iterator".to_string(),
+            "floats".to_string(),
+        ]));
+        assert!(policy.deterministic_floats());
+        assert_eq!(policy.module_variant(), "-floats");
+    }
+
+    #[test]
+    fn gatekeeper_policy_new_works() {
+        let policy = GatekeeperPolicy::new(true, false, false).unwrap();
+        assert!(policy.bulk_memory_operations());
+        assert!(!policy.reference_types());
+        assert!(!policy.simd());
+
+        let err = GatekeeperPolicy::new(false, true, false).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("reference types require bulk memory operations"));
+    }
+
+    #[test]
+    fn gatekeeper_policy_preset_works() {
+        assert_eq!(
+            GatekeeperPolicy::preset("strict").unwrap(),
+            GatekeeperPolicy::default()
+        );
+        assert_eq!(
+            GatekeeperPolicy::preset("reference-types").unwrap(),
+            GatekeeperPolicy::new(true, true, false).unwrap()
+        );
+        let err = GatekeeperPolicy::preset("floats").unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Unknown Gatekeeper policy preset 'floats'"));
+    }
+
+    #[test]
+    fn gatekeeper_policy_module_variant_works() {
+        assert_eq!(GatekeeperPolicy::strict().module_variant(), "");
+        assert_eq!(
+            GatekeeperPolicy::preset("bulk-memory")
+                .unwrap()
+                .module_variant(),
+            "-bulk-memory"
+        );
+        assert_eq!(
+            GatekeeperPolicy::preset("reference-types")
+                .unwrap()
+                .module_variant(),
+            "-bulk-memory-reference-types"
+        );
+    }
 }
@@ -1,8 +1,11 @@
 mod compile;
 mod gatekeeper;
 mod limiting_tunables;
+mod nan_canonicalizer;
 mod store;
 
-pub use compile::compile;
+pub use compile::{compile, compile_with_policy};
+pub(crate) use gatekeeper::OperatorGroup;
+pub use gatekeeper::{GatekeeperPolicy, FLOATS_FEATURE};
 pub use limiting_tunables::LimitingTunables;
 pub use store::make_runtime_store;
@@ -0,0 +1,515 @@
+use loupe::MemoryUsage;
+use std::sync::Mutex;
+use wasmer::wasmparser::Operator;
+use wasmer::{
+    FunctionMiddleware, GlobalInit, GlobalType, LocalFunctionIndex, MiddlewareError,
+    MiddlewareReaderState, ModuleMiddleware, Mutability, Type,
+};
+use wasmer_types::{GlobalIndex, ModuleInfo, V128};
+
+/// The canonical NaN of f32, i.e. positive with only the quiet bit of the payload set
+const CANONICAL_NAN_F32: u32 = 0x7fc0_0000;
+/// The canonical NaN of f64, i.e. positive with only the quiet bit of the payload set
+const CANONICAL_NAN_F64: u64 = 0x7ff8_0000_0000_0000;
+
+/// The name used in errors
+const MIDDLEWARE_NAME: &str = "NanCanonicalizer";
+
+/// The type of a float result that is canonicalized
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FloatType {
+    F32,
+    F64,
+    F32x4,
+    F64x2,
+}
+
+impl FloatType {
+    /// Returns the type of the result if the operator can produce a NaN with a sign and payload
+    /// that depend on the architecture.
+    ///
+    /// Operations that only move bits (constants, loads, stores, `abs`, `neg`, `copysign`,
+    /// `reinterpret`, `pmin`, `pmax`) are deterministic and not listed.
+    fn of_nan_result(operator: &Operator) -> Option<Self> {
+        match operator {
+            Operator::F32Add
+            | Operator::F32Sub
+            | Operator::F32Mul
+            | Operator::F32Div
+            | Operator::F32Min
+            | Operator::F32Max
+            | Operator::F32Sqrt
+            | Operator::F32Ceil
+            | Operator::F32Floor
+            | Operator::F32Trunc
+            | Operator::F32Nearest
+            | Operator::F32DemoteF64 => Some(FloatType::F32),
+            Operator::F64Add
+            | Operator::F64Sub
+            | Operator::F64Mul
+            | Operator::F64Div
+            | Operator::F64Min
+            | Operator::F64Max
+            | Operator::F64Sqrt
+            | Operator::F64Ceil
+            | Operator::F64Floor
+            | Operator::F64Trunc
+            | Operator::F64Nearest
+            | Operator::F64PromoteF32 => Some(FloatType::F64),
+            Operator::F32x4Add
+            | Operator::F32x4Sub
+            | Operator::F32x4Mul
+            | Operator::F32x4Div
+            | Operator::F32x4Min
+            | Operator::F32x4Max
+            | Operator::F32x4Sqrt
+            | Operator::F32x4Ceil
+            | Operator::F32x4Floor
+            | Operator::F32x4Trunc
+            | Operator::F32x4Nearest
+            | Operator::F32x4DemoteF64x2Zero => Some(FloatType::F32x4),
+            Operator::F64x2Add
+            | Operator::F64x2Sub
+            | Operator::F64x2Mul
+            | Operator::F64x2Div
+            | Operator::F64x2Min
+            | Operator::F64x2Max
+            | Operator::F64x2Sqrt
+            | Operator::F64x2Ceil
+            | Operator::F64x2Floor
+            | Operator::F64x2Trunc
+            | Operator::F64x2Nearest
+            | Operator::F64x2PromoteLowF32x4 => Some(FloatType::F64x2),
+            _ => None,
+        }
+    }
+}
+
+/// The globals holding a float result while it is canonicalized.
+/// A middleware cannot add locals to a function, so they are added to the module.
+#[derive(Debug, Clone, Copy, MemoryUsage)]
+struct ScratchGlobals {
+    f32: GlobalIndex,
+    f64: GlobalIndex,
+    /// Only added if SIMD is enabled
+    v128: Option<GlobalIndex>,
+}
+
+/// A middleware that makes float operations deterministic by replacing every NaN they
+/// produce with the canonical NaN of its type.
+///
+/// The Wasm spec leaves the sign and payload of NaN results to the architecture. Those bits
+/// become observable via `reinterpret`, `copysign` or stores, so they must not differ between
+/// nodes. The middleware must run after the [`super::gatekeeper::Gatekeeper`], which decides
+/// whether floats are allowed at all.
+///
+/// Like `Metering`, an instance must only be used to compile a single module.
+#[derive(Debug, MemoryUsage)]
+pub struct NanCanonicalizer {
+    simd: bool,
+    scratch_globals: Mutex<Option<ScratchGlobals>>,
+}
+
+impl NanCanonicalizer {
+    /// Creates a new NanCanonicalizer. `simd` must be set if the Gatekeeper allows SIMD
+    /// operations on floats.
+    pub fn new(simd: bool) -> Self {
+        Self {
+            simd,
+            scratch_globals: Mutex::new(None),
+        }
+    }
+}
+
+impl ModuleMiddleware for NanCanonicalizer {
+    /// Generates a `FunctionMiddleware` for a given function.
+    fn generate_function_middleware(&self, _: LocalFunctionIndex) -> Box<dyn FunctionMiddleware> {
+        let scratch_globals = self
+            .scratch_globals
+            .lock()
+            .unwrap()
+            .expect("Scratch globals not set. This is a bug in the NanCanonicalizer.");
+        Box::new(FunctionNanCanonicalizer::new(scratch_globals))
+    }
+
+    /// Adds the scratch globals to the module.
+    fn transform_module_info(&self, module_info: &mut ModuleInfo) {
+        let mut scratch_globals = self.scratch_globals.lock().unwrap();
+        if scratch_globals.is_some() {
+            panic!("Attempting to use a NanCanonicalizer middleware for multiple modules.");
+        }
+
+        let f32 = add_scratch_global(module_info, Type::F32, GlobalInit::F32Const(0.0));
+        let f64 = add_scratch_global(module_info, Type::F64, GlobalInit::F64Const(0.0));
+        let v128 = if self.simd {
+            let zero = V128::from([0u8; 16]);
+            Some(add_scratch_global(
+                module_info,
+                Type::V128,
+                GlobalInit::V128Const(zero),
+            ))
+        } else {
+            None
+        };
+        *scratch_globals = Some(ScratchGlobals { f32, f64, v128 });
+    }
+}
+
+fn add_scratch_global(module_info: &mut ModuleInfo, ty: Type, init: GlobalInit) -> GlobalIndex {
+    let index = module_info
+        .globals
+        .push(GlobalType::new(ty, Mutability::Var));
+    module_info.global_initializers.push(init);
+    index
+}
+
+#[derive(Debug)]
+struct FunctionNanCanonicalizer {
+    scratch_globals: ScratchGlobals,
+}
+
+impl FunctionNanCanonicalizer {
+    fn new(scratch_globals: ScratchGlobals) -> Self {
+        Self { scratch_globals }
+    }
+
+    /// Replaces the float on top of the stack with the canonical NaN if it is a NaN.
+    ///
+    /// For scalars, the selection happens on the integer representation, such that the
+    /// compilers only need to move the bits of the float. E.g. for f32:
+    ///
+    /// ```wat
+    /// global.set $scratch
+    /// i32.const 0x7fc00000
+    /// global.get $scratch
+    /// i32.reinterpret_f32
+    /// global.get $scratch
+    /// global.get $scratch
+    /// f32.ne                ;; only a NaN is not equal to itself
+    /// select
+    /// f32.reinterpret_i32
+    /// ```
+    ///
+    /// Vectors use `v128.bitselect` with the lane mask of the comparison instead.
+    fn canonicalize<'a>(
+        &self,
+        float_type: FloatType,
+        state: &mut MiddlewareReaderState<'a>,
+    ) -> Result<(), MiddlewareError> {
+        match float_type {
+            FloatType::F32 => {
+                let global_index = self.scratch_globals.f32.as_u32();
+                state.extend([
+                    Operator::GlobalSet { global_index },
+                    Operator::I32Const {
+                        value: CANONICAL_NAN_F32 as i32,
+                    },
+                    Operator::GlobalGet { global_index },
+                    Operator::I32ReinterpretF32,
+                    Operator::GlobalGet { global_index },
+                    Operator::GlobalGet { global_index },
+                    Operator::F32Ne,
+                    Operator::Select,
+                    Operator::F32ReinterpretI32,
+                ]);
+            }
+            FloatType::F64 => {
+                let global_index = self.scratch_globals.f64.as_u32();
+                state.extend([
+                    Operator::GlobalSet { global_index },
+                    Operator::I64Const {
+                        value: CANONICAL_NAN_F64 as i64,
+                    },
+                    Operator::GlobalGet { global_index },
+                    Operator::I64ReinterpretF64,
+                    Operator::GlobalGet { global_index },
+                    Operator::GlobalGet { global_index },
+                    Operator::F64Ne,
+                    Operator::Select,
+                    Operator::F64ReinterpretI64,
+                ]);
+            }
+            FloatType::F32x4 | FloatType::F64x2 => {
+                let global_index = self
+                    .scratch_globals
+                    .v128
+                    .ok_or_else(|| {
+                        let msg = "SIMD operator detected without a scratch global for vectors. The SIMD extension must be enabled in the NanCanonicalizer.";
+                        MiddlewareError::new(MIDDLEWARE_NAME, msg)
+                    })?
+                    .as_u32();
+                let (canonical_nan, is_nan) = if float_type == FloatType::F32x4 {
+                    (
+                        [
+                            Operator::I32Const {
+                                value: CANONICAL_NAN_F32 as i32,
+                            },
+                            Operator::I32x4Splat,
+                        ],
+                        Operator::F32x4Ne,
+                    )
+                } else {
+                    (
+                        [
+                            Operator::I64Const {
+                                value: CANONICAL_NAN_F64 as i64,
+                            },
+                            Operator::I64x2Splat,
+                        ],
+                        Operator::F64x2Ne,
+                    )
+                };
+                state.push_operator(Operator::GlobalSet { global_index });
+                state.extend(canonical_nan);
+                state.extend([
+                    Operator::GlobalGet { global_index },
+                    Operator::GlobalGet { global_index },
+                    Operator::GlobalGet { global_index },
+                    is_nan,
+                    Operator::V128Bitselect,
+                ]);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FunctionMiddleware for FunctionNanCanonicalizer {
+    fn feed<'a>(
+        &mut self,
+        operator: Operator<'a>,
+        state: &mut MiddlewareReaderState<'a>,
+    ) -> Result<(), MiddlewareError> {
+        let float_type = FloatType::of_nan_result(&operator);
+        state.push_operator(operator);
+        match float_type {
+            Some(float_type) => self.canonicalize(float_type, state),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wasm_backend::gatekeeper::{Gatekeeper, GatekeeperPolicy};
+    use std::sync::Arc;
+    use wasmer::{
+        CompilerConfig, Cranelift, Features, ImportObject, Instance, Module, Store, Universal, Val,
+    };
+
+    fn instantiate(wat: &str, policy: GatekeeperPolicy) -> Instance {
+        let wasm = wat::parse_str(wat).unwrap();
+        let mut compiler_config = Cranelift::default();
+        compiler_config.push_middleware(Arc::new(Gatekeeper::from_policy(policy)));
+        compiler_config.push_middleware(Arc::new(NanCanonicalizer::new(policy.simd())));
+        let mut features = Features::default();
+        features.simd(policy.simd());
+        let engine = Universal::new(compiler_config).features(features).engine();
+        let module = Module::new(&Store::new(&engine), &wasm).unwrap();
+        Instance::new(&module, &ImportObject::new()).unwrap()
+    }
+
+    fn call(instance: &Instance, name: &str) -> Val {
+        let result = instance
+            .exports
+            .get_function(name)
+            .unwrap()
+            .call(&[])
+            .unwrap();
+        result[0].clone()
+    }
+
+    #[test]
+    fn float_type_of_nan_result_works() {
+        assert_eq!(
+            FloatType::of_nan_result(&Operator::F32Div),
+            Some(FloatType::F32)
+        );
+        assert_eq!(
+            FloatType::of_nan_result(&Operator::F64PromoteF32),
+            Some(FloatType::F64)
+        );
+        assert_eq!(
+            FloatType::of_nan_result(&Operator::F32x4Sqrt),
+            Some(FloatType::F32x4)
+        );
+        assert_eq!(
+            FloatType::of_nan_result(&Operator::F64x2Min),
+            Some(FloatType::F64x2)
+        );
+        // bit operations
+        assert_eq!(FloatType::of_nan_result(&Operator::F32Neg), None);
+        assert_eq!(FloatType::of_nan_result(&Operator::F64Copysign), None);
+        assert_eq!(FloatType::of_nan_result(&Operator::F32ReinterpretI32), None);
+        // no floats
+        assert_eq!(FloatType::of_nan_result(&Operator::I32Add), None);
+    }
+
+    #[test]
+    fn nan_canonicalizer_canonicalizes_scalars() {
+        let instance = instantiate(
+            r#"(module
+            (func (export "f32_div_zero") (result i32)
+                f32.const 0
+                f32.const 0
+                f32.div
+                i32.reinterpret_f32)
+            (func (export "f32_sqrt_negative") (result i32)
+                f32.const -1
+                f32.sqrt
+                i32.reinterpret_f32)
+            (func (export "f32_add_nan") (result i32)
+                f32.const -nan:0x200000
+                f32.const 1
+                f32.add
+                i32.reinterpret_f32)
+            (func (export "f32_demote_nan") (result i32)
+                f64.const -nan:0x4000000000000
+                f32.demote_f64
+                i32.reinterpret_f32)
+            (func (export "f64_div_zero") (result i64)
+                f64.const 0
+                f64.const 0
+                f64.div
+                i64.reinterpret_f64)
+            (func (export "f64_min_nan") (result i64)
+                f64.const -nan:0x4000000000000
+                f64.const 1
+                f64.min
+                i64.reinterpret_f64)
+            (func (export "f64_promote_nan") (result i64)
+                f32.const -nan:0x200000
+                f64.promote_f32
+                i64.reinterpret_f64)
+            )"#,
+            GatekeeperPolicy::strict().with_deterministic_floats(),
+        );
+
+        for name in [
+            "f32_div_zero",
+            "f32_sqrt_negative",
+            "f32_add_nan",
+            "f32_demote_nan",
+        ] {
+            assert_eq!(
+                call(&instance, name).unwrap_i32() as u32,
+                CANONICAL_NAN_F32,
+                "{}",
+                name
+            );
+        }
+        for name in ["f64_div_zero", "f64_min_nan", "f64_promote_nan"] {
+            assert_eq!(
+                call(&instance, name).unwrap_i64() as u64,
+                CANONICAL_NAN_F64,
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn nan_canonicalizer_keeps_other_values() {
+        let instance = instantiate(
+            r#"(module
+            (func (export "f32_add") (result i32)
+                f32.const 1.5
+                f32.const 1
+                f32.add
+                i32.reinterpret_f32)
+            (func (export "f32_div_zero") (result i32)
+                f32.const -1
+                f32.const 0
+                f32.div
+                i32.reinterpret_f32)
+            (func (export "f64_mul") (result i64)
+                f64.const -2.5
+                f64.const 2
+                f64.mul
+                i64.reinterpret_f64)
+            (func (export "f32_neg_nan") (result i32)
+                f32.const nan:0x200000
+                f32.neg
+                i32.reinterpret_f32)
+            )"#,
+            GatekeeperPolicy::strict().with_deterministic_floats(),
+        );
+
+        assert_eq!(
+            call(&instance, "f32_add").unwrap_i32() as u32,
+            2.5f32.to_bits()
+        );
+        assert_eq!(
+            call(&instance, "f32_div_zero").unwrap_i32() as u32,
+            f32::NEG_INFINITY.to_bits()
+        );
+        assert_eq!(
+            call(&instance, "f64_mul").unwrap_i64() as u64,
+            (-5.0f64).to_bits()
+        );
+        // neg only flips the sign bit and is deterministic
+        assert_eq!(
+            call(&instance, "f32_neg_nan").unwrap_i32() as u32,
+            0xffa0_0000
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "cranelift")]
+    fn nan_canonicalizer_canonicalizes_vectors() {
+        let instance = instantiate(
+            r#"(module
+            (func (export "f32x4_div") (result i64)
+                v128.const f32x4 0 1 0 -1
+                v128.const f32x4 0 2 0 0
+                f32x4.div
+                i64x2.extract_lane 0)
+            (func (export "f64x2_sqrt") (result i64)
+                v128.const f64x2 -1 4
+                f64x2.sqrt
+                i64x2.extract_lane 0)
+            (func (export "f64x2_sqrt_lane_1") (result i64)
+                v128.const f64x2 -1 4
+                f64x2.sqrt
+                i64x2.extract_lane 1)
+            )"#,
+            GatekeeperPolicy::new(true, false, true)
+                .unwrap()
+                .with_deterministic_floats(),
+        );
+
+        // lane 0 is a NaN, lane 1 is 0.5
+        let expected = (u64::from(0.5f32.to_bits()) << 32) | u64::from(CANONICAL_NAN_F32);
+        assert_eq!(call(&instance, "f32x4_div").unwrap_i64() as u64, expected);
+        assert_eq!(
+            call(&instance, "f64x2_sqrt").unwrap_i64() as u64,
+            CANONICAL_NAN_F64
+        );
+        assert_eq!(
+            call(&instance, "f64x2_sqrt_lane_1").unwrap_i64() as u64,
+            2.0f64.to_bits()
+        );
+    }
+
+    #[test]
+    fn nan_canonicalizer_adds_no_vector_global_without_simd() {
+        let wasm = wat::parse_str(
+            r#"(module
+            (func (result f32)
+                f32.const 0
+                f32.const 0
+                f32.div)
+            )"#,
+        )
+        .unwrap();
+
+        let canonicalizer = Arc::new(NanCanonicalizer::new(false));
+        let mut compiler_config = Cranelift::default();
+        compiler_config.push_middleware(canonicalizer.clone());
+        let store = Store::new(&Universal::new(compiler_config).engine());
+        Module::new(&store, &wasm).unwrap();
+        let scratch_globals = canonicalizer.scratch_globals.lock().unwrap().unwrap();
+        assert!(scratch_globals.v128.is_none());
+    }
+}
@@ -4,15 +4,16 @@ use wasmer::Cranelift;
 #[cfg(not(feature = "cranelift"))]
 use wasmer::Singlepass;
 use wasmer::{
-    wasmparser::Operator, BaseTunables, CompilerConfig, Engine, ModuleMiddleware, Pages, Store,
-    Target, Universal, WASM_PAGE_SIZE,
+    wasmparser::Operator, BaseTunables, CompilerConfig, Engine, Features, ModuleMiddleware, Pages,
+    Store, Target, Universal, WASM_PAGE_SIZE,
 };
 use wasmer_middlewares::Metering;
 
 use crate::size::Size;
 
-use super::gatekeeper::Gatekeeper;
+use super::gatekeeper::{Gatekeeper, GatekeeperPolicy};
 use super::limiting_tunables::LimitingTunables;
+use super::nan_canonicalizer::NanCanonicalizer;
 
 /// WebAssembly linear memory objects have sizes measured in pages. Each page
 /// is 65536 (2^16) bytes. In WebAssembly version 1, a linear memory can have at
@@ -32,12 +33,22 @@ fn cost(_operator: &Operator) -> u64 {
 
 /// Created a store with the default compiler and the given memory limit (in bytes).
 /// If memory_limit is None, no limit is applied.
+/// The Gatekeeper policy determines which Wasm features are accepted.
+///
+/// In the deterministic floats mode, the NaNs produced by float operations are canonicalized
+/// by the [`NanCanonicalizer`] middleware, which runs after the Gatekeeper.
 pub fn make_compile_time_store(
     memory_limit: Option<Size>,
     middlewares: &[Arc<dyn ModuleMiddleware>],
+    policy: GatekeeperPolicy,
 ) -> Store {
     let gas_limit = 0;
-    let deterministic = Arc::new(Gatekeeper::default());
+    let deterministic = Arc::new(Gatekeeper::from_policy(policy));
+    let mut features = Features::default();
+    features.simd(policy.simd());
+    let nan_canonicalizer = policy
+        .deterministic_floats()
+        .then(|| Arc::new(NanCanonicalizer::new(policy.simd())));
     let metering = Arc::new(Metering::new(gas_limit, cost));
 
     #[cfg(feature = "cranelift")]
@@ -47,8 +58,11 @@ pub fn make_compile_time_store(
             config.push_middleware(middleware.clone());
         }
         config.push_middleware(deterministic);
+        if let Some(nan_canonicalizer) = nan_canonicalizer {
+            config.push_middleware(nan_canonicalizer);
+        }
         config.push_middleware(metering);
-        let engine = Universal::new(config).engine();
+        let engine = Universal::new(config).features(features).engine();
         make_store_with_engine(&engine, memory_limit)
     }
 
@@ -59,8 +73,11 @@ pub fn make_compile_time_store(
             config.push_middleware(middleware.clone());
         }
         config.push_middleware(deterministic);
+        if let Some(nan_canonicalizer) = nan_canonicalizer {
+            config.push_middleware(nan_canonicalizer);
+        }
         config.push_middleware(metering);
-        let engine = Universal::new(config).engine();
+        let engine = Universal::new(config).features(features).engine();
         make_store_with_engine(&engine, memory_limit)
     }
 }
@@ -98,6 +115,7 @@ fn limit_to_pages(limit: Size) -> Pages {
 mod tests {
     use super::*;
     use wasmer::{ImportObject, Instance, Memory, Module};
+    use wasmer_middlewares::metering::set_remaining_points;
 
     /// A Wasm module with an exported memory (min: 4 pages, max: none)
     const EXPORTED_MEMORY_WAT: &str = r#"(module
@@ -124,7 +142,7 @@ mod tests {
         let wasm = wat::parse_str(EXPORTED_MEMORY_WAT).unwrap();
 
         // No limit
-        let store = make_compile_time_store(None, &[]);
+        let store = make_compile_time_store(None, &[], GatekeeperPolicy::strict());
         let module = Module::new(&store, &wasm).unwrap();
         let module_memory = module.info().memories.last().unwrap();
         assert_eq!(module_memory.minimum, Pages(4));
@@ -141,7 +159,8 @@ mod tests {
         assert_eq!(instance_memory.ty().maximum, None);
 
         // Set limit
-        let store = make_compile_time_store(Some(Size::kibi(23 * 64)), &[]);
+        let store =
+            make_compile_time_store(Some(Size::kibi(23 * 64)), &[], GatekeeperPolicy::strict());
         let module = Module::new(&store, &wasm).unwrap();
         let module_memory = module.info().memories.last().unwrap();
         assert_eq!(module_memory.minimum, Pages(4));
@@ -158,12 +177,60 @@ mod tests {
         assert_eq!(instance_memory.ty().maximum, Some(Pages(23)));
     }
 
+    #[test]
+    fn make_compile_time_store_canonicalizes_nans() {
+        let wasm = wat::parse_str(
+            r#"(module
+            (func (export "div_zero") (result i32)
+                f32.const 0
+                f32.const 0
+                f32.div
+                i32.reinterpret_f32)
+            (func (export "add_nan") (result i32)
+                f32.const -nan:0x200000
+                f32.const 1
+                f32.add
+                i32.reinterpret_f32)
+            (func (export "sqrt_negative") (result i64)
+                f64.const -1
+                f64.sqrt
+                i64.reinterpret_f64)
+            )"#,
+        )
+        .unwrap();
+
+        let policy = GatekeeperPolicy::strict().with_deterministic_floats();
+        let store = make_compile_time_store(None, &[], policy);
+        let module = Module::new(&store, &wasm).unwrap();
+        let instance = Instance::new(&module, &ImportObject::new()).unwrap();
+        // the compile time store has a gas limit of 0
+        set_remaining_points(&instance, 1_000_000_000);
+        for name in ["div_zero", "add_nan"] {
+            let result = instance
+                .exports
+                .get_function(name)
+                .unwrap()
+                .call(&[])
+                .unwrap();
+            // the canonical NaN of f32, no matter the sign and payload produced by the hardware
+            assert_eq!(result[0].unwrap_i32() as u32, 0x7fc00000, "{}", name);
+        }
+        let result = instance
+            .exports
+            .get_function("sqrt_negative")
+            .unwrap()
+            .call(&[])
+            .unwrap();
+        // the canonical NaN of f64
+        assert_eq!(result[0].unwrap_i64() as u64, 0x7ff8000000000000);
+    }
+
     #[test]
     fn make_runtime_store_applies_memory_limit() {
         // Compile
         let serialized = {
             let wasm = wat::parse_str(EXPORTED_MEMORY_WAT).unwrap();
-            let store = make_compile_time_store(None, &[]);
+            let store = make_compile_time_store(None, &[], GatekeeperPolicy::strict());
             let module = Module::new(&store, &wasm).unwrap();
             module.serialize().unwrap()
         };